@@ -0,0 +1,39 @@
+use sentinel_macros::flow;
+
+use sentinel_core::utils::sleep_for_ms;
+
+/// an example on `flow::CalculateStrategy::CpuAdaptive`
+fn main() {
+    // Init sentienl configurations
+    sentinel_core::init_default().unwrap_or_else(|err| sentinel_core::logging::error!("{:?}", err));
+
+    let mut handlers = Vec::new();
+    for _ in 0..20 {
+        handlers.push(std::thread::spawn(move || {
+            loop {
+                task().unwrap_or_else(|_| {
+                    // blocked
+                    sleep_for_ms(10);
+                });
+            }
+        }));
+    }
+    for h in handlers {
+        h.join().expect("Couldn't join on the associated thread");
+    }
+}
+
+#[flow(
+    traffic_type = "Inbound",
+    calculate_strategy = "CpuAdaptive",
+    cpu_low_water_mark = 20.0,
+    cpu_high_water_mark = 80.0,
+    low_cpu_usage_threshold = 50,
+    high_cpu_usage_threshold = 5
+)]
+fn task() {
+    println!("{}: passed", sentinel_core::utils::curr_time_millis());
+    // busy loop, so that the CPU usage grows and the threshold shrinks
+    let start = sentinel_core::utils::curr_time_millis();
+    while sentinel_core::utils::curr_time_millis() - start < 10 {}
+}
@@ -17,7 +17,7 @@ fn custom_extractor(_req: &ServiceRequest) -> String {
 
 fn custom_fallback<B>(
     req: ServiceRequest,
-    _: &sentinel_core::Error,
+    _: &sentinel_core::base::EntryError,
 ) -> Result<ServiceResponse<EitherBody<B>>, Error> {
     Ok(req.into_response(HttpResponse::new(StatusCode::IM_A_TEAPOT).map_into_right_body()))
 }
@@ -9,7 +9,7 @@ use actix_web::{
     http::StatusCode,
     Error, HttpResponse,
 };
-use sentinel_core::EntryBuilder;
+use sentinel_core::{base::EntryError, EntryBuilder};
 use std::{future::Future, pin::Pin, rc::Rc};
 
 /// It is used to extractor a resource name from requests for Sentinel.
@@ -17,7 +17,7 @@ pub type Extractor = fn(&ServiceRequest) -> String;
 
 /// The fallback function when service is rejected by sentinel.
 pub type Fallback<B> =
-    fn(ServiceRequest, &sentinel_core::Error) -> Result<ServiceResponse<EitherBody<B>>, Error>;
+    fn(ServiceRequest, &EntryError) -> Result<ServiceResponse<EitherBody<B>>, Error>;
 
 /// Sentinel wrapper
 pub struct Sentinel<B> {
@@ -94,26 +94,27 @@ where
             None => req.uri().to_string(),
         };
         let service = Rc::clone(&self.service);
+        let fallback = self.fallback;
         let entry_builder = EntryBuilder::new(resource)
             .with_traffic_type(sentinel_core::base::TrafficType::Inbound);
 
-        match entry_builder.build() {
-            Ok(entry) => Box::pin(async move {
-                let response = service
-                    .call(req)
-                    .await
-                    .map(ServiceResponse::map_into_left_body);
-                entry.exit();
-                response
-            }),
-            Err(err) => match self.fallback {
-                Some(fallback) => Box::pin(async move { fallback(req, &err) }),
-                None => Box::pin(async move {
-                    Ok(req.into_response(
+        Box::pin(async move {
+            match entry_builder.build_async().await {
+                Ok(entry) => {
+                    let response = service
+                        .call(req)
+                        .await
+                        .map(ServiceResponse::map_into_left_body);
+                    entry.exit();
+                    response
+                }
+                Err(err) => match fallback {
+                    Some(fallback) => fallback(req, &err),
+                    None => Ok(req.into_response(
                         HttpResponse::new(StatusCode::TOO_MANY_REQUESTS).map_into_right_body(),
-                    ))
-                }),
-            },
-        }
+                    )),
+                },
+            }
+        })
     }
 }
@@ -21,7 +21,7 @@ fn custom_extractor(_req: &Request) -> String {
     RESOURCE_NAME.into()
 }
 
-fn custom_fallback(req: &Request, err: sentinel_core::Error) -> Result<Response, BoxError> {
+fn custom_fallback(req: &Request, err: sentinel_core::base::EntryError) -> Result<Response, BoxError> {
     let resource = req.uri().clone();
     let response = Full::<Bytes>::new(format!("Requested resource is: {:?}.\nHowever, it is blocked by Sentinel, the reported error is:\n{}", resource, err).into())
         .map_err(move |e| Error::new(e))
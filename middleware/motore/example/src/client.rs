@@ -20,7 +20,7 @@ fn custom_extractor(_cx: &ClientContext, _req: &Request<HelloServiceRequestSend>
 fn custom_fallback(
     _cx: &ClientContext,
     _req: &Request<HelloServiceRequestSend>,
-    err: &sentinel_core::Error,
+    err: &sentinel_core::base::EntryError,
 ) -> Result<Response<HelloServiceResponseRecv>, BoxError> {
     Err(Status::new(
         Code::Cancelled,
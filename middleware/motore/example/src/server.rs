@@ -21,7 +21,7 @@ fn custom_extractor(_cx: &ServerContext, _req: &Request<HelloServiceRequestRecv>
 fn custom_fallback(
     _cx: &ServerContext,
     _req: &Request<HelloServiceRequestRecv>,
-    err: &sentinel_core::Error,
+    err: &sentinel_core::base::EntryError,
 ) -> Result<Response<HelloServiceResponseSend>, BoxError> {
     Err(Status::new(
         Code::ResourceExhausted,
@@ -5,7 +5,7 @@
 
 use core::marker::PhantomData;
 use motore::{layer::Layer, service::Service};
-use sentinel_core::EntryBuilder;
+use sentinel_core::{base::EntryError, EntryBuilder};
 
 /// The side where the middleware is deplyed.
 #[derive(Debug, Copy, Clone)]
@@ -22,7 +22,7 @@ type Extractor<Cx, R> = fn(&Cx, &R) -> String;
 
 /// The fallback function when service is rejected by sentinel.
 type Fallback<S, Cx, R> =
-    fn(&Cx, &R, &sentinel_core::Error) -> Result<<S as Service<Cx, R>>::Response, BoxError>;
+    fn(&Cx, &R, &EntryError) -> Result<<S as Service<Cx, R>>::Response, BoxError>;
 
 /// The sentinel middleware service in motore.
 pub struct SentinelService<S, Cx, R>
@@ -83,7 +83,7 @@ where
 macro_rules! deal_with_sentinel {
     ($resource:ident,$self:ident,$cx:ident,$req:ident) => {{
         let entry_builder = EntryBuilder::new($resource).with_traffic_type($self.traffic_type);
-        match entry_builder.build() {
+        match entry_builder.build_async().await {
             Ok(entry) => {
                 let fut = $self.inner.call($cx, $req).await.map_err(Into::into);
                 entry.exit();
@@ -9,7 +9,7 @@ use rocket::{
     request::{self, FromRequest},
     route, Build, Data, Request, Rocket, Route,
 };
-use sentinel_core::EntryBuilder;
+use sentinel_core::{base::EntryError, EntryBuilder};
 use std::sync::Mutex;
 
 pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
@@ -18,7 +18,7 @@ pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
 pub type Extractor = fn(&Request<'_>) -> String;
 
 /// The fallback function when service is rejected by sentinel.
-pub type Fallback<R> = fn(&Request<'_>, sentinel_core::Error) -> R;
+pub type Fallback<R> = fn(&Request<'_>, EntryError) -> R;
 
 fn default_extractor(req: &Request<'_>) -> String {
     req.uri().path().to_string()
@@ -26,7 +26,7 @@ fn default_extractor(req: &Request<'_>) -> String {
 
 fn default_fallback_for_guard(
     _request: &Request<'_>,
-    err: sentinel_core::Error,
+    err: EntryError,
 ) -> request::Outcome<SentinelGuard, BoxError> {
     request::Outcome::Failure((Status::TooManyRequests, err.into()))
 }
@@ -111,7 +111,7 @@ impl<'r> FromRequest<'r> for SentinelGuard {
         let entry_builder = EntryBuilder::new(resource)
             .with_traffic_type(sentinel_core::base::TrafficType::Inbound);
 
-        match entry_builder.build() {
+        match entry_builder.build_async().await {
             Ok(entry) => {
                 entry.exit();
                 request::Outcome::Success(SentinelGuard {})
@@ -251,7 +251,7 @@ impl Fairing for SentinelFairing {
         let entry_builder = EntryBuilder::new(resource)
             .with_traffic_type(sentinel_core::base::TrafficType::Inbound);
 
-        match entry_builder.build() {
+        match entry_builder.build_async().await {
             Ok(entry) => {
                 entry.exit();
             }
@@ -15,7 +15,7 @@ fn custom_extractor(_req: &Request<()>) -> String {
     RESOURCE_NAME.into()
 }
 
-fn custom_fallback(_req: &Request<()>, err: &sentinel_core::Error) -> Result<Request<()>, Status> {
+fn custom_fallback(_req: &Request<()>, err: &sentinel_core::base::EntryError) -> Result<Request<()>, Status> {
     Err(Status::resource_exhausted(format!(
         "Blocked by sentinel at server side: : {:?}",
         err
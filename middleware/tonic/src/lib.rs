@@ -3,15 +3,18 @@
 //! The are two kinds of middlewares in tonic.
 //! - [`SentinelInterceptor`] based on [`tonic::service::interceptor::Interceptor`](https://docs.rs/tonic/latest/tonic/service/interceptor/trait.Interceptor.html)
 //! - [`SentinelService`] and [`SentinelLayer`] based on [`tower::Service`](https://docs.rs/tower/latest/tower/trait.Service.html)
+//!
+//! Since the interceptors in tonic are synchronous, [`SentinelInterceptor`] blocks the current thread
+//! when the rules require the requests to wait (e.g., throttling), while [`SentinelService`] awaits it.
 //! See [examples](https://github.com/sentinel-group/sentinel-rust/tree/main/middleware) for help.
 
-use sentinel_core::EntryBuilder;
+use sentinel_core::{base::EntryError, EntryBuilder};
 pub use sentinel_tower::{SentinelLayer, SentinelService, ServiceRole};
 use tonic::service::interceptor::Interceptor;
 use tonic::{Request, Status};
 
 pub type Extractor = fn(&Request<()>) -> String;
-pub type Fallback = fn(&Request<()>, &sentinel_core::Error) -> Result<Request<()>, Status>;
+pub type Fallback = fn(&Request<()>, &EntryError) -> Result<Request<()>, Status>;
 
 #[derive(Clone)]
 pub struct SentinelInterceptor {
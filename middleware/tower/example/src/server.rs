@@ -26,7 +26,7 @@ fn custom_extractor(_req: &Request) -> String {
     RESOURCE_NAME.into()
 }
 
-fn custom_fallback(req: &Request, err: sentinel_core::Error) -> Result<Response, BoxError> {
+fn custom_fallback(req: &Request, err: sentinel_core::base::EntryError) -> Result<Response, BoxError> {
     let resource = req.uri().clone();
     // the message should be encoded with functions in `tonic\src\codec\encode.rs`, but we cannot access them
     let response = http_body::Full::new(format!("Requested resource is: {:?}", resource).into())
@@ -3,7 +3,7 @@
 //! See the [examples](https://github.com/sentinel-group/sentinel-rust/tree/main/middleware) for help.
 //!
 
-use sentinel_core::EntryBuilder;
+use sentinel_core::{base::EntryError, EntryBuilder};
 use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
@@ -17,8 +17,7 @@ pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
 pub type Extractor<R> = fn(&R) -> String;
 
 /// The fallback function when service is rejected by sentinel.
-pub type Fallback<S, R> =
-    fn(&R, sentinel_core::Error) -> Result<<S as Service<R>>::Response, BoxError>;
+pub type Fallback<S, R> = fn(&R, EntryError) -> Result<<S as Service<R>>::Response, BoxError>;
 
 /// The side where the middleware is deplyed.
 #[derive(Debug, Copy, Clone)]
@@ -91,28 +90,24 @@ where
 macro_rules! deal_with_sentinel {
     ($resource:ident,$self:ident,$req:ident) => {{
         let entry_builder = EntryBuilder::new($resource).with_traffic_type($self.traffic_type);
-        match entry_builder.build() {
-            Ok(entry) => {
-                let fut = $self.inner.call($req);
-                Box::pin(async move {
-                    let response = fut.await.map_err(Into::<BoxError>::into)?;
+        // the entry is built asynchronously, so the ready inner service is moved into the future,
+        // and a clone of it is left for the following requests
+        let clone = $self.inner.clone();
+        let mut inner = std::mem::replace(&mut $self.inner, clone);
+        let fallback = $self.fallback;
+        Box::pin(async move {
+            match entry_builder.build_async().await {
+                Ok(entry) => {
+                    let response = inner.call($req).await.map_err(Into::<BoxError>::into)?;
                     entry.exit();
                     Ok(response)
-                })
-            }
-            Err(err) => match $self.fallback {
-                Some(fallback) => {
-                    let response = fallback(&$req, err);
-                    Box::pin(async move {
-                        match response {
-                            Ok(response) => Ok(response),
-                            Err(err) => Err(err.into()),
-                        }
-                    })
                 }
-                None => Box::pin(async move { Err(err.into()) }),
-            },
-        }
+                Err(err) => match fallback {
+                    Some(fallback) => fallback(&$req, err),
+                    None => Err(err.into()),
+                },
+            }
+        })
     }};
 }
 
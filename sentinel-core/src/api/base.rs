@@ -1,10 +1,13 @@
-use super::global_slot_chain;
+use super::{current_context_name, global_slot_chain};
 use crate::base::{
-    EntryContext, EntryStrongPtr, ParamsList, ParamsMap, ResourceType, ResourceWrapper,
-    SentinelEntry, SentinelInput, SlotChain, TokenResult, TrafficType,
+    ContextPtr, EntryContext, EntryError, EntryGuard, EntryStrongPtr, ParamsList, ParamsMap,
+    ResourceType, ResourceWrapper, SentinelEntry, SentinelInput, SlotChain, TokenResult,
+    TrafficType,
 };
 use crate::utils::format_time_nanos_curr;
-use crate::{Error, Result};
+use crate::Error;
+use std::fmt;
+use std::future::Future;
 use std::sync::Arc;
 use std::sync::RwLock;
 
@@ -13,8 +16,11 @@ pub struct EntryBuilder {
     resource_name: String,
     resource_type: ResourceType,
     traffic_type: TrafficType,
+    origin: String,
+    context_name: Option<String>,
     batch_count: u32,
     flag: i32,
+    prioritized: bool,
     slot_chain: Arc<SlotChain>,
     args: Option<ParamsList>,
     attachments: Option<ParamsMap>,
@@ -28,8 +34,11 @@ impl Default for EntryBuilder {
             resource_name: format_time_nanos_curr(),
             resource_type: ResourceType::default(),
             traffic_type: TrafficType::default(),
+            origin: String::new(),
+            context_name: None,
             batch_count: 1,
             flag: 0,
+            prioritized: false,
             slot_chain: global_slot_chain(),
             args: None,
             attachments: None,
@@ -45,8 +54,54 @@ impl EntryBuilder {
         }
     }
 
-    /// `build()` would consume EntryBuilder
-    pub fn build(self) -> Result<EntryStrongPtr> {
+    /// `build()` would consume EntryBuilder.
+    /// If the entry is blocked, it returns `EntryError::Blocked` with the `BlockError`,
+    /// which keeps the block type, the triggered rule and the snapshot value.
+    pub fn build(self) -> Result<EntryStrongPtr, EntryError> {
+        let (entry, ctx, slot_chain) = self.create_entry();
+        let r = slot_chain.entry(ctx);
+        Self::check_result(entry, r)
+    }
+
+    /// `build_async()` would consume EntryBuilder.
+    /// Different from `build()`, it awaits the waiting time required by rules (e.g., throttling)
+    /// with a runtime-agnostic timer, instead of sleeping the current thread.
+    /// Therefore, it is preferred in async functions.
+    /// Note that the cluster mode rules still block the current thread while requesting tokens
+    /// from the token server, see `cluster` for details.
+    pub async fn build_async(self) -> Result<EntryStrongPtr, EntryError> {
+        let (entry, ctx, slot_chain) = self.create_entry();
+        let r = slot_chain.entry_async(ctx).await;
+        Self::check_result(entry, r)
+    }
+
+    /// `run()` would consume EntryBuilder.
+    /// It builds the entry and calls `f` if the entry passes,
+    /// the entry is exited automatically, even if `f` panics.
+    /// If `f` returns an `Err`, a `TracedError` carrying its message and type name is traced on the entry
+    /// by `trace_error()`, so that the circuit breakers (and their error classifiers) are aware of it.
+    /// The original error is returned to the caller.
+    pub fn run<T, E, F>(self, f: F) -> Result<Result<T, E>, EntryError>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let entry = EntryGuard::new(self.build()?);
+        Ok(f().map_err(|err| exit_with_error(entry, err)))
+    }
+
+    /// `run_async()` would consume EntryBuilder.
+    /// It is the async version of `run()`, the entry is built by `build_async()`.
+    pub async fn run_async<T, E, Fut>(self, fut: Fut) -> Result<Result<T, E>, EntryError>
+    where
+        Fut: Future<Output = Result<T, E>>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let entry = EntryGuard::new(self.build_async().await?);
+        Ok(fut.await.map_err(|err| exit_with_error(entry, err)))
+    }
+
+    fn create_entry(self) -> (Arc<RwLock<SentinelEntry>>, ContextPtr, Arc<SlotChain>) {
         // get context from pool.
         let mut ctx = EntryContext::new();
 
@@ -55,8 +110,11 @@ impl EntryBuilder {
             self.resource_type,
             self.traffic_type,
         ));
+        ctx.set_origin(self.origin);
+        ctx.set_context_name(self.context_name.unwrap_or_else(current_context_name));
 
         let mut input = SentinelInput::new(self.batch_count, self.flag);
+        input.set_prioritized(self.prioritized);
         if let Some(args) = self.args {
             input.set_args(args);
         }
@@ -71,15 +129,17 @@ impl EntryBuilder {
             Arc::clone(&self.slot_chain),
         )));
         ctx.write().unwrap().set_entry(Arc::downgrade(&entry));
+        (entry, ctx, self.slot_chain)
+    }
 
-        let r = self.slot_chain.entry(Arc::clone(&ctx));
+    fn check_result(
+        entry: Arc<RwLock<SentinelEntry>>,
+        r: TokenResult,
+    ) -> Result<EntryStrongPtr, EntryError> {
         match r {
-            TokenResult::Blocked(_) => {
-                // todo:
-                // if return block_error,
-                // must deep copy the error, since Arc only clone pointer
+            TokenResult::Blocked(block_err) => {
                 entry.read().unwrap().exit();
-                Err(Error::msg(r.to_string()))
+                Err(EntryError::Blocked(block_err))
             }
             _ => Ok(EntryStrongPtr::new(entry)),
         }
@@ -95,6 +155,20 @@ impl EntryBuilder {
         self
     }
 
+    /// `with_origin` sets the origin (caller) of the invocation,
+    /// e.g., the name of the upstream service, which is used by origin-aware rules
+    pub fn with_origin(mut self, origin: String) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// `with_context_name` sets the name of the invocation context (the entrance) explicitly,
+    /// otherwise, the context entered by `enter_context()` on current thread is used
+    pub fn with_context_name(mut self, context_name: String) -> Self {
+        self.context_name = Some(context_name);
+        self
+    }
+
     pub fn with_batch_count(mut self, batch_count: u32) -> Self {
         self.batch_count = batch_count;
         self
@@ -105,6 +179,15 @@ impl EntryBuilder {
         self
     }
 
+    /// `with_prioritized` marks the invocation as prioritized.
+    /// When the window of a flow rule with `Reject` control strategy is exhausted,
+    /// prioritized invocations may occupy the tokens of the next bucket and wait until it starts,
+    /// instead of being blocked immediately.
+    pub fn with_prioritized(mut self, prioritized: bool) -> Self {
+        self.prioritized = prioritized;
+        self
+    }
+
     pub fn with_slot_chain(mut self, slot_chain: Arc<SlotChain>) -> Self {
         self.slot_chain = slot_chain;
         self
@@ -125,13 +208,80 @@ pub fn trace_error(entry: &EntryStrongPtr, err: Error) {
     entry.set_err(err);
 }
 
+/// `TracedError` is traced on the entry by `run()` and `run_async()` in place of the error returned by the protected
+/// closure or future, since the original error is returned to the caller.
+/// It keeps the message and the type name of the original error, so that the error classifiers can tell it.
+#[derive(Debug, Clone)]
+pub struct TracedError {
+    type_name: &'static str,
+    message: String,
+}
+
+impl TracedError {
+    pub fn new<E: std::error::Error>(err: &E) -> Self {
+        TracedError {
+            type_name: std::any::type_name::<E>(),
+            message: err.to_string(),
+        }
+    }
+
+    /// `type_name` returns `std::any::type_name()` of the original error
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Display for TracedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for TracedError {}
+
+/// `exit_with_error` traces `err` on the entry by a `TracedError` and exits it, the original error is returned.
+fn exit_with_error<E>(entry: EntryGuard, err: E) -> E
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    trace_error(&entry, Error::new(TracedError::new(&err)));
+    err
+}
+
+/// `run()` protects the closure `f` by the resource `resource_name` with default entry settings.
+/// The outer `Result` indicates whether the calling is blocked by Sentinel,
+/// while the inner one is the result of `f`. See `EntryBuilder::run()`.
+pub fn run<T, E, F>(resource_name: impl Into<String>, f: F) -> Result<Result<T, E>, EntryError>
+where
+    F: FnOnce() -> Result<T, E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    EntryBuilder::new(resource_name.into()).run(f)
+}
+
+/// `run_async()` protects the future `fut` by the resource `resource_name` with default entry settings.
+/// The outer `Result` indicates whether the calling is blocked by Sentinel,
+/// while the inner one is the output of `fut`. See `EntryBuilder::run_async()`.
+pub async fn run_async<T, E, Fut>(
+    resource_name: impl Into<String>,
+    fut: Fut,
+) -> Result<Result<T, E>, EntryError>
+where
+    Fut: Future<Output = Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    EntryBuilder::new(resource_name.into()).run_async(fut).await
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::base::{
-        BlockType, MockRuleCheckSlot, MockStatNode, MockStatPrepareSlot, MockStatSlot, TokenResult,
+        BaseSlot, BlockType, MockRuleCheckSlot, MockStatNode, MockStatPrepareSlot, MockStatSlot,
+        RuleCheckSlot, TokenResult,
     };
     use mockall::*;
+    use std::task::Poll;
 
     #[test]
     fn pass() {
@@ -249,6 +399,126 @@ mod test {
         ctx.write().unwrap().set_entry(Arc::downgrade(&entry));
 
         let builder = EntryBuilder::new("abc".into()).with_slot_chain(sc);
-        assert!(builder.build().is_err());
+        let err = builder.build().err().unwrap();
+        let block_err = err.block_error().unwrap();
+        assert_eq!(block_err.block_type(), BlockType::Flow);
+    }
+
+    struct WaitSlot {}
+    impl BaseSlot for WaitSlot {}
+    impl RuleCheckSlot for WaitSlot {
+        fn check(&self, ctx: &mut EntryContext) -> TokenResult {
+            ctx.wait_for_ns(100_000_000);
+            ctx.result().clone()
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn build_async_without_blocking() {
+        let mut ssm = Arc::new(MockStatSlot::new());
+        Arc::get_mut(&mut ssm)
+            .unwrap()
+            .expect_on_entry_pass()
+            .once()
+            .return_const(());
+        Arc::get_mut(&mut ssm)
+            .unwrap()
+            .expect_on_completed()
+            .once()
+            .return_const(());
+        let mut sc = SlotChain::new();
+        sc.add_rule_check_slot(Arc::new(WaitSlot {}));
+        sc.add_stat_slot(ssm);
+        let sc = Arc::new(sc);
+
+        let mut fut = Box::pin(
+            EntryBuilder::new("abc".into())
+                .with_slot_chain(sc)
+                .build_async(),
+        );
+        // the first poll yields instead of sleeping the current thread until the waiting is over
+        let first = std::future::poll_fn(|cx| Poll::Ready(fut.as_mut().poll(cx))).await;
+        assert!(first.is_pending());
+        fut.await.unwrap().exit();
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct BizError;
+
+    impl fmt::Display for BizError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "biz error")
+        }
+    }
+
+    impl std::error::Error for BizError {}
+
+    fn error_traced_stat_slot() -> Arc<MockStatSlot> {
+        let mut ssm = Arc::new(MockStatSlot::new());
+        Arc::get_mut(&mut ssm)
+            .unwrap()
+            .expect_on_entry_pass()
+            .once()
+            .return_const(());
+        Arc::get_mut(&mut ssm)
+            .unwrap()
+            .expect_on_completed()
+            .withf(|ctx| {
+                ctx.get_err()
+                    .as_ref()
+                    .and_then(|err| err.downcast_ref::<TracedError>())
+                    .is_some_and(|err| {
+                        err.type_name() == std::any::type_name::<BizError>()
+                            && err.to_string() == "biz error"
+                    })
+            })
+            .once()
+            .return_const(());
+        ssm
+    }
+
+    #[test]
+    fn run_with_error() {
+        let mut sc = SlotChain::new();
+        sc.add_stat_slot(error_traced_stat_slot());
+        let sc = Arc::new(sc);
+
+        let res = EntryBuilder::new("abc".into())
+            .with_slot_chain(sc)
+            .run(|| -> std::result::Result<(), BizError> { Err(BizError) })
+            .unwrap();
+        assert_eq!(res, Err(BizError));
+    }
+
+    #[tokio::test]
+    async fn run_async_with_error() {
+        let mut sc = SlotChain::new();
+        sc.add_stat_slot(error_traced_stat_slot());
+        let sc = Arc::new(sc);
+
+        let res = EntryBuilder::new("abc".into())
+            .with_slot_chain(sc)
+            .run_async(async { std::result::Result::<(), BizError>::Err(BizError) })
+            .await
+            .unwrap();
+        assert_eq!(res, Err(BizError));
+    }
+
+    #[test]
+    fn run_blocked() {
+        let mut rcs = Arc::new(MockRuleCheckSlot::new());
+        Arc::get_mut(&mut rcs)
+            .unwrap()
+            .expect_check()
+            .once()
+            .returning(|_ctx| TokenResult::new_blocked(BlockType::Flow));
+        let mut sc = SlotChain::new();
+        sc.add_rule_check_slot(rcs);
+        let sc = Arc::new(sc);
+
+        let res = EntryBuilder::new("abc".into())
+            .with_slot_chain(sc)
+            .run(|| -> std::result::Result<(), BizError> { panic!("should be blocked") });
+        assert!(res.is_err());
     }
 }
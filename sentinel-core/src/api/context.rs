@@ -0,0 +1,63 @@
+//! Named invocation contexts.
+//! An invocation context is identified by its name, i.e., the entrance of the invocations.
+//! Entries built inside a context are recorded in the call tree of the context,
+//! which is used by the flow rules with `RelationStrategy::Chain`.
+
+use crate::base::DEFAULT_CONTEXT_NAME;
+use std::cell::RefCell;
+
+std::thread_local! {
+    static CURRENT_CONTEXT_NAME: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// ContextGuard restores the previous invocation context of current thread when it is dropped.
+pub struct ContextGuard {
+    prev: Option<String>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CURRENT_CONTEXT_NAME.with(|name| *name.borrow_mut() = prev);
+    }
+}
+
+/// `enter_context` enters the invocation context named `name` on current thread,
+/// until the returned guard is dropped.
+/// Since the context is thread-local, in async functions,
+/// please set the context explicitly by `EntryBuilder::with_context_name()`.
+#[must_use]
+pub fn enter_context(name: String) -> ContextGuard {
+    let prev = CURRENT_CONTEXT_NAME.with(|curr| curr.borrow_mut().replace(name));
+    ContextGuard { prev }
+}
+
+/// `current_context_name` returns the name of the invocation context entered on current thread,
+/// or the `DEFAULT_CONTEXT_NAME` if no context is entered.
+pub fn current_context_name() -> String {
+    CURRENT_CONTEXT_NAME.with(|name| {
+        name.borrow()
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CONTEXT_NAME.into())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_context() {
+        assert_eq!(current_context_name(), DEFAULT_CONTEXT_NAME);
+        {
+            let _outer = enter_context("outer".into());
+            assert_eq!(current_context_name(), "outer");
+            {
+                let _inner = enter_context("inner".into());
+                assert_eq!(current_context_name(), "inner");
+            }
+            assert_eq!(current_context_name(), "outer");
+        }
+        assert_eq!(current_context_name(), DEFAULT_CONTEXT_NAME);
+    }
+}
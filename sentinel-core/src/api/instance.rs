@@ -0,0 +1,203 @@
+use super::EntryBuilder;
+use crate::{
+    authority, base::SlotChain, circuitbreaker, cluster, flow, hotspot, isolation, stat, system,
+};
+use lazy_static::lazy_static;
+use std::sync::Arc;
+
+lazy_static! {
+    static ref DEFAULT_INSTANCE: Arc<Sentinel> = Arc::new(Sentinel::with_components(
+        stat::default_node_storage(),
+        flow::default_rule_manager(),
+        isolation::default_rule_manager(),
+        hotspot::default_rule_manager(),
+        circuitbreaker::default_rule_manager(),
+        system::default_rule_manager(),
+        authority::default_rule_manager(),
+    ));
+}
+
+/// `default_instance` returns the Sentinel instance used by the free functions,
+/// e.g., `flow::load_rules()` and `EntryBuilder::new()`.
+pub fn default_instance() -> Arc<Sentinel> {
+    DEFAULT_INSTANCE.clone()
+}
+
+/// `Sentinel` is an instance of the Sentinel runtime.
+/// It owns its rule managers, the statistic nodes and the slot chain,
+/// so that several independently configured instances can live in a single process,
+/// e.g., for multi-tenant gateways or tests in parallel.
+/// Note that the configurations, the metric log and the state change listeners of circuit breakers
+/// are still shared by all the instances.
+pub struct Sentinel {
+    node_storage: Arc<stat::NodeStorage>,
+    flow: Arc<flow::RuleManager>,
+    isolation: Arc<isolation::RuleManager>,
+    hotspot: Arc<hotspot::RuleManager>,
+    circuitbreaker: Arc<circuitbreaker::RuleManager>,
+    system: Arc<system::RuleManager>,
+    authority: Arc<authority::RuleManager>,
+    slot_chain: Arc<SlotChain>,
+}
+
+impl Default for Sentinel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sentinel {
+    /// `new` creates an isolated instance with empty rules and statistic nodes.
+    pub fn new() -> Self {
+        let node_storage = Arc::new(stat::NodeStorage::new());
+        Self::with_components(
+            node_storage.clone(),
+            Arc::new(flow::RuleManager::new(node_storage)),
+            Arc::new(isolation::RuleManager::new()),
+            Arc::new(hotspot::RuleManager::new()),
+            Arc::new(circuitbreaker::RuleManager::new()),
+            Arc::new(system::RuleManager::new()),
+            Arc::new(authority::RuleManager::new()),
+        )
+    }
+
+    fn with_components(
+        node_storage: Arc<stat::NodeStorage>,
+        flow: Arc<flow::RuleManager>,
+        isolation: Arc<isolation::RuleManager>,
+        hotspot: Arc<hotspot::RuleManager>,
+        circuitbreaker: Arc<circuitbreaker::RuleManager>,
+        system: Arc<system::RuleManager>,
+        authority: Arc<authority::RuleManager>,
+    ) -> Self {
+        let mut sc = SlotChain::new();
+
+        sc.add_stat_prepare_slot(Arc::new(stat::ResourceNodePrepareSlot::new(
+            node_storage.clone(),
+        )));
+
+        sc.add_rule_check_slot(Arc::new(authority::Slot::new(authority.clone()))); // 500
+        sc.add_rule_check_slot(Arc::new(system::AdaptiveSlot::new(
+            system.clone(),
+            node_storage.clone(),
+        ))); // 1000
+        sc.add_rule_check_slot(Arc::new(flow::Slot::new(flow.clone()))); // 2000
+        sc.add_rule_check_slot(Arc::new(isolation::AdaptiveSlot::new(isolation.clone()))); // 3000
+        sc.add_rule_check_slot(Arc::new(hotspot::Slot::new(hotspot.clone()))); // 4000
+        sc.add_rule_check_slot(Arc::new(circuitbreaker::Slot::new(circuitbreaker.clone()))); // 5000
+
+        sc.add_stat_slot(Arc::new(stat::ResourceNodeStatSlot::new(
+            node_storage.clone(),
+        ))); // 1000
+        sc.add_stat_slot(crate::log::default_stat_slot()); // 2000
+        sc.add_stat_slot(Arc::new(flow::StandaloneStatSlot::new(flow.clone()))); // 3000
+        sc.add_stat_slot(Arc::new(isolation::LimiterStatSlot::new(isolation.clone()))); // 3000
+        sc.add_stat_slot(Arc::new(hotspot::ConcurrencyStatSlot::new(hotspot.clone()))); // 4000
+        sc.add_stat_slot(Arc::new(circuitbreaker::MetricStatSlot::new(
+            circuitbreaker.clone(),
+        ))); // 5000
+
+        Sentinel {
+            node_storage,
+            flow,
+            isolation,
+            hotspot,
+            circuitbreaker,
+            system,
+            authority,
+            slot_chain: Arc::new(sc),
+        }
+    }
+
+    /// `entry` creates an `EntryBuilder` on the slot chain of this instance.
+    pub fn entry(&self, resource_name: String) -> EntryBuilder {
+        EntryBuilder::new(resource_name).with_slot_chain(self.slot_chain())
+    }
+
+    pub fn slot_chain(&self) -> Arc<SlotChain> {
+        self.slot_chain.clone()
+    }
+
+    pub fn node_storage(&self) -> &Arc<stat::NodeStorage> {
+        &self.node_storage
+    }
+
+    pub fn flow_rule_manager(&self) -> &Arc<flow::RuleManager> {
+        &self.flow
+    }
+
+    pub fn isolation_rule_manager(&self) -> &Arc<isolation::RuleManager> {
+        &self.isolation
+    }
+
+    pub fn hotspot_rule_manager(&self) -> &Arc<hotspot::RuleManager> {
+        &self.hotspot
+    }
+
+    pub fn circuit_breaker_rule_manager(&self) -> &Arc<circuitbreaker::RuleManager> {
+        &self.circuitbreaker
+    }
+
+    pub fn system_rule_manager(&self) -> &Arc<system::RuleManager> {
+        &self.system
+    }
+
+    pub fn authority_rule_manager(&self) -> &Arc<authority::RuleManager> {
+        &self.authority
+    }
+
+    /// `set_token_client` sets the token client used by the cluster mode flow rules and hotspot rules of this instance.
+    pub fn set_token_client(&self, client: Arc<cluster::TokenClient>) {
+        self.flow.set_token_client(client.clone());
+        self.hotspot.set_token_client(client);
+    }
+
+    /// `clear_token_client` removes the token client, then the cluster mode rules fall back to the local checking.
+    pub fn clear_token_client(&self) {
+        self.flow.clear_token_client();
+        self.hotspot.clear_token_client();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn isolated_instances() {
+        let resource_name = String::from("isolated_instances");
+        let limited = Sentinel::new();
+        let unlimited = Sentinel::new();
+        limited
+            .flow_rule_manager()
+            .load_rules(vec![Arc::new(flow::Rule {
+                resource: resource_name.clone(),
+                threshold: 0.0,
+                calculate_strategy: flow::CalculateStrategy::Direct,
+                control_strategy: flow::ControlStrategy::Reject,
+                ..Default::default()
+            })]);
+
+        let err = limited.entry(resource_name.clone()).build().err().unwrap();
+        assert_eq!(
+            err.block_error().unwrap().block_type(),
+            crate::base::BlockType::Flow
+        );
+        let entry = unlimited.entry(resource_name.clone()).build().unwrap();
+        entry.exit();
+
+        assert!(flow::get_rules_of_resource(&resource_name).is_empty());
+        assert!(stat::get_resource_node(&resource_name).is_none());
+        assert!(unlimited
+            .node_storage()
+            .get_resource_node(&resource_name)
+            .is_some());
+
+        limited.set_token_client(Arc::new(cluster::TokenClient::new("127.0.0.1:0", 100)));
+        assert!(limited.flow_rule_manager().token_client().is_some());
+        assert!(limited.hotspot_rule_manager().token_client().is_some());
+        assert!(unlimited.flow_rule_manager().token_client().is_none());
+        limited.clear_token_client();
+        assert!(limited.hotspot_rule_manager().token_client().is_none());
+    }
+}
@@ -7,9 +7,13 @@
 //! For the examples, visit the [Sentinel repository](https://github.com/sentinel-group/sentinel-rust)
 
 mod base;
+mod context;
 mod init;
+mod instance;
 mod slot_chain;
 
 pub use base::*;
+pub use context::*;
 pub use init::*;
+pub use instance::*;
 pub use slot_chain::*;
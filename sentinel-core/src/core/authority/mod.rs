@@ -0,0 +1,9 @@
+//! mod authority provides implementation of the origin based access control (allow/deny list).
+
+pub mod rule;
+pub mod rule_manager;
+pub mod slot;
+
+pub use rule::*;
+pub use rule_manager::*;
+pub use slot::*;
@@ -0,0 +1,144 @@
+use crate::{base::SentinelRule, Error};
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+cfg_k8s! {
+    use schemars::JsonSchema;
+    use kube::CustomResource;
+}
+
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize, Hash, Eq)]
+pub enum Strategy {
+    /// `White` only allows the invocations from the origins in the list
+    #[default]
+    White,
+    /// `Black` rejects the invocations from the origins in the list
+    Black,
+}
+
+/// `Rule` describes the allowed or denied origins (callers) of a resource.
+#[cfg_attr(
+    feature = "ds_k8s",
+    derive(CustomResource, JsonSchema),
+    kube(
+        group = "rust.datasource.sentinel.io",
+        version = "v1alpha1",
+        kind = "AuthorityResource",
+        namespaced
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    /// `id` represents the unique ID of the rule (optional).
+    pub id: String,
+    /// `resource` represents the target resource definition
+    pub resource: String,
+    /// `strategy` indicates whether `origins` is an allow list or a deny list.
+    pub strategy: Strategy,
+    /// `origins` is the list of the origins, i.e., the callers set by `EntryBuilder::with_origin()`
+    pub origins: Vec<String>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            #[cfg(target_arch = "wasm32")]
+            id: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            id: uuid::Uuid::new_v4().to_string(),
+            resource: String::default(),
+            strategy: Strategy::default(),
+            origins: Vec::new(),
+        }
+    }
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.resource == other.resource
+            && self.strategy == other.strategy
+            && self.origins == other.origins
+    }
+}
+
+impl Eq for Rule {}
+
+impl Hash for Rule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.resource.hash(state);
+    }
+}
+
+impl Rule {
+    /// `can_pass` checks whether the invocations from the origin are allowed by the rule.
+    /// An empty origin is always allowed, since the caller is unknown.
+    pub fn can_pass(&self, origin: &str) -> bool {
+        if origin.is_empty() {
+            return true;
+        }
+        let contained = self.origins.iter().any(|o| o == origin);
+        match self.strategy {
+            Strategy::White => contained,
+            Strategy::Black => !contained,
+        }
+    }
+}
+
+impl SentinelRule for Rule {
+    fn resource_name(&self) -> String {
+        self.resource.clone()
+    }
+
+    fn is_valid(&self) -> crate::Result<()> {
+        if self.resource.is_empty() {
+            return Err(Error::msg("empty resource of authority rule"));
+        }
+        if self.origins.iter().all(|o| o.is_empty()) {
+            return Err(Error::msg("empty origins of authority rule"));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmtted = serde_json::to_string_pretty(self).unwrap();
+        write!(f, "{}", fmtted)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "empty origins of authority rule")]
+    fn invalid_origins() {
+        let rule = Rule {
+            resource: "invalid_origins".into(),
+            origins: vec!["".into()],
+            ..Default::default()
+        };
+        rule.is_valid().unwrap();
+    }
+
+    #[test]
+    fn can_pass() {
+        let mut rule = Rule {
+            resource: "can_pass".into(),
+            origins: vec!["app1".into(), "app2".into()],
+            ..Default::default()
+        };
+        assert!(rule.can_pass("app1"));
+        assert!(!rule.can_pass("app3"));
+        assert!(rule.can_pass(""));
+        rule.strategy = Strategy::Black;
+        assert!(!rule.can_pass("app1"));
+        assert!(rule.can_pass("app3"));
+        assert!(rule.can_pass(""));
+    }
+}
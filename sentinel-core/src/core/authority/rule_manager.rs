@@ -0,0 +1,319 @@
+use super::*;
+use crate::{base::SentinelRule, logging, utils};
+use crate::{Error, Result};
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+
+pub type RuleMap = HashMap<String, HashSet<Arc<Rule>>>;
+
+/// `RuleManager` holds the authority rules of a Sentinel instance.
+pub struct RuleManager {
+    rule_map: RwLock<RuleMap>,
+    current_rules: Mutex<RuleMap>,
+}
+
+impl Default for RuleManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_RULE_MANAGER: Arc<RuleManager> = Arc::new(RuleManager::new());
+}
+
+/// `default_rule_manager` returns the rule manager used by the default Sentinel instance
+pub fn default_rule_manager() -> Arc<RuleManager> {
+    DEFAULT_RULE_MANAGER.clone()
+}
+
+impl RuleManager {
+    pub fn new() -> Self {
+        RuleManager {
+            rule_map: RwLock::new(RuleMap::new()),
+            current_rules: Mutex::new(RuleMap::new()),
+        }
+    }
+
+    /// `get_rules` returns all the rules in the `rule_map`
+    // This func acquires a read lock on `rule_map`,
+    // please release the lock before calling this func
+    pub fn get_rules(&self) -> Vec<Arc<Rule>> {
+        let rule_map = self.rule_map.read().unwrap();
+        let mut rules = Vec::with_capacity(rule_map.len());
+        for r in rule_map.values() {
+            rules.append(&mut r.clone().into_iter().collect());
+        }
+        rules
+    }
+
+    /// `get_rules_of_resource` returns specific resource's rules
+    // This func acquires a read lock on `rule_map`,
+    // please release the lock before calling this func
+    pub fn get_rules_of_resource(&self, res: &String) -> Vec<Arc<Rule>> {
+        let placeholder = HashSet::new();
+        let rule_map = self.rule_map.read().unwrap();
+        let res_rules = rule_map.get(res).unwrap_or(&placeholder);
+
+        res_rules.clone().into_iter().collect()
+    }
+
+    pub fn append_rule(&self, rule: Arc<Rule>) -> bool {
+        if self
+            .rule_map
+            .read()
+            .unwrap()
+            .get(&rule.resource)
+            .unwrap_or(&HashSet::new())
+            .contains(&rule)
+        {
+            return false;
+        }
+
+        match rule.is_valid() {
+            Ok(_) => {
+                self.rule_map
+                    .write()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(Arc::clone(&rule));
+                self.current_rules
+                    .lock()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(rule);
+            }
+            Err(err) => logging::warn!(
+                "[Authority append_rule] Ignoring invalid rule {:?}, reason: {:?}",
+                rule,
+                err
+            ),
+        };
+        true
+    }
+
+    /// `load_rules` loads given authority rules to the rule manager, while all previous rules will be replaced.
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn load_rules(&self, rules: Vec<Arc<Rule>>) {
+        let mut res_rules_map = RuleMap::new();
+        for rule in rules {
+            let val = res_rules_map.entry(rule.resource.clone()).or_default();
+            val.insert(rule);
+        }
+        let mut current_rules = self.current_rules.lock().unwrap();
+        if *current_rules == res_rules_map {
+            logging::info!(
+                "[Authority] Load rules is the same with current rules, so ignore load operation."
+            );
+            return;
+        }
+
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
+        let mut valid_res_rule_map = RuleMap::with_capacity(res_rules_map.len());
+        for (res, rules) in &res_rules_map {
+            let mut valid_res_rules = HashSet::with_capacity(rules.len());
+            for rule in rules {
+                match rule.is_valid() {
+                    Ok(_) => {
+                        valid_res_rules.insert(Arc::clone(rule));
+                    }
+                    Err(err) => logging::warn!(
+                        "[Authority load_rules] Ignoring invalid authority rule {:?}, reason: {:?}",
+                        rule,
+                        err
+                    ),
+                }
+            }
+            if !valid_res_rules.is_empty() {
+                valid_res_rule_map.insert(res.clone(), valid_res_rules);
+            }
+        }
+
+        let start = utils::curr_time_nanos();
+        let mut rule_map = self.rule_map.write().unwrap();
+        *rule_map = valid_res_rule_map;
+        *current_rules = res_rules_map;
+
+        logging::debug!(
+            "[Authority load_rules] Time statistic(ns) for updating authority rule, timeCost {:?}",
+            utils::curr_time_nanos() - start
+        );
+        logging::info!(
+            "[AuthorityRuleManager] Authority rules loaded, rules {:?}",
+            rule_map
+        );
+    }
+
+    /// `load_rules` loads the given resource's authority rules to the rule manager, while all previous resource's rules will be replaced.
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn load_rules_of_resource(&self, res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+        if res.is_empty() {
+            return Err(Error::msg("empty resource"));
+        }
+        let rules: HashSet<_> = rules.into_iter().collect();
+
+        if rules.is_empty() {
+            self.clear_rules_of_resource(res);
+            logging::info!("[Authority] clear resource level rules, resource {}", res);
+            return Ok(true);
+        }
+
+        if self
+            .current_rules
+            .lock()
+            .unwrap()
+            .get(res)
+            .unwrap_or(&HashSet::new())
+            == &rules
+        {
+            logging::info!(
+                "[Authority] Load resource level rules is the same with current resource level rules, so ignore load operation."
+            );
+            return Ok(false);
+        }
+
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
+        let mut valid_res_rules = HashSet::with_capacity(rules.len());
+        for rule in &rules {
+            match rule.is_valid() {
+                Ok(_) => {
+                    valid_res_rules.insert(Arc::clone(rule));
+                }
+                Err(err) => logging::warn!(
+                    "[Authority load_rules_of_resource] Ignoring invalid authority rule {:?}, reason: {:?}",
+                    rule,
+                    err
+                ),
+            }
+        }
+
+        let valid_res_rules_string = format!("{:?}", &valid_res_rules);
+        let start = utils::curr_time_nanos();
+        if valid_res_rules.is_empty() {
+            self.rule_map.write().unwrap().remove(res);
+        } else {
+            self.rule_map
+                .write()
+                .unwrap()
+                .insert(res.clone(), valid_res_rules);
+        }
+        self.current_rules
+            .lock()
+            .unwrap()
+            .insert(res.clone(), rules);
+
+        logging::debug!(
+            "[Authority load_rules] Time statistic(ns) for updating authority rule, timeCost {:?}",
+            utils::curr_time_nanos() - start
+        );
+        logging::info!(
+            "[AuthorityRuleManager] Authority rules loaded, rules {}",
+            valid_res_rules_string
+        );
+        Ok(true)
+    }
+
+    /// `clear_rules` clear all the rules in authority module
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn clear_rules(&self) {
+        self.current_rules.lock().unwrap().clear();
+        self.rule_map.write().unwrap().clear();
+    }
+
+    /// ClearRulesOfResource clears resource level rules in authority module.
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn clear_rules_of_resource(&self, res: &String) {
+        self.current_rules.lock().unwrap().remove(res);
+        self.rule_map.write().unwrap().remove(res);
+    }
+}
+
+// The following functions operate on the default rule manager.
+pub fn get_rules() -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules()
+}
+
+pub fn get_rules_of_resource(res: &String) -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules_of_resource(res)
+}
+
+pub fn append_rule(rule: Arc<Rule>) -> bool {
+    DEFAULT_RULE_MANAGER.append_rule(rule)
+}
+
+pub fn load_rules(rules: Vec<Arc<Rule>>) {
+    DEFAULT_RULE_MANAGER.load_rules(rules)
+}
+
+pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+    DEFAULT_RULE_MANAGER.load_rules_of_resource(res, rules)
+}
+
+pub fn clear_rules() {
+    DEFAULT_RULE_MANAGER.clear_rules()
+}
+
+pub fn clear_rules_of_resource(res: &String) {
+    DEFAULT_RULE_MANAGER.clear_rules_of_resource(res)
+}
+
+#[cfg(test)]
+mod test {
+    //! Some tests cannot run in parallel, since we cannot promise that
+    //! the global data structs are not modified before assertion.
+    use super::*;
+
+    #[test]
+    fn load_rules() {
+        let rule_manager = RuleManager::new();
+        let r1 = Arc::new(Rule {
+            resource: "abc1".into(),
+            origins: vec!["app1".into()],
+            ..Default::default()
+        });
+        let r2 = Arc::new(Rule {
+            resource: "abc1".into(),
+            strategy: Strategy::Black,
+            origins: vec!["app2".into()],
+            ..Default::default()
+        });
+        let r3 = Arc::new(Rule {
+            resource: "abc2".into(),
+            ..Default::default()
+        });
+        rule_manager.load_rules(vec![r1, r2, r3]);
+        assert_eq!(2, rule_manager.get_rules().len());
+        assert_eq!(2, rule_manager.get_rules_of_resource(&"abc1".into()).len());
+        assert!(rule_manager
+            .get_rules_of_resource(&"abc2".into())
+            .is_empty());
+        assert_eq!(2, rule_manager.current_rules.lock().unwrap().len());
+
+        rule_manager.clear_rules_of_resource(&"abc1".into());
+        assert!(rule_manager.get_rules().is_empty());
+        rule_manager.clear_rules();
+        assert!(rule_manager.current_rules.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "empty resource")]
+    fn empty_resource() {
+        let r1 = Arc::new(Rule {
+            origins: vec!["app1".into()],
+            ..Default::default()
+        });
+        let result = load_rules_of_resource(&"".into(), vec![r1]);
+        assert_eq!(0, DEFAULT_RULE_MANAGER.rule_map.read().unwrap().len());
+        result.unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+use crate::base::{BaseSlot, BlockType, EntryContext, RuleCheckSlot, TokenResult};
+use lazy_static::lazy_static;
+use std::sync::Arc;
+
+const RULE_CHECK_SLOT_ORDER: u32 = 500;
+
+/// A RuleSlot for the origin based access control
+pub struct Slot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl Slot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        Slot { rule_manager }
+    }
+}
+
+impl Default for Slot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
+
+lazy_static! {
+    pub static ref DEFAULT_SLOT: Arc<Slot> = Arc::new(Slot::default());
+}
+
+pub fn default_slot() -> Arc<Slot> {
+    DEFAULT_SLOT.clone()
+}
+
+impl BaseSlot for Slot {
+    fn order(&self) -> u32 {
+        RULE_CHECK_SLOT_ORDER
+    }
+}
+
+impl RuleCheckSlot for Slot {
+    fn check(&self, ctx: &mut EntryContext) -> TokenResult {
+        let res_name = ctx.resource().name().clone();
+        if res_name.is_empty() {
+            return ctx.result().clone();
+        }
+        let origin = ctx.origin().clone();
+        for rule in self.rule_manager.get_rules_of_resource(&res_name) {
+            if !rule.can_pass(&origin) {
+                ctx.set_result(TokenResult::new_blocked_with_cause(
+                    BlockType::Authority,
+                    format!("origin {} is not authorized", origin),
+                    rule,
+                    Arc::new(origin),
+                ));
+                break;
+            }
+        }
+        ctx.result().clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{ResourceType, ResourceWrapper, TrafficType};
+
+    #[test]
+    fn check() {
+        let rule_manager = Arc::new(RuleManager::new());
+        let slot = Slot::new(rule_manager.clone());
+        let res_name = String::from("check");
+        let mut ctx = EntryContext::new();
+        ctx.set_resource(ResourceWrapper::new(
+            res_name.clone(),
+            ResourceType::Common,
+            TrafficType::Inbound,
+        ));
+        ctx.set_origin("app1".into());
+        assert!(slot.check(&mut ctx).is_pass());
+
+        rule_manager.load_rules(vec![Arc::new(Rule {
+            resource: res_name.clone(),
+            strategy: Strategy::Black,
+            origins: vec!["app1".into()],
+            ..Default::default()
+        })]);
+        let result = slot.check(&mut ctx);
+        assert!(result.is_blocked());
+        assert_eq!(
+            result.block_err().unwrap().block_type(),
+            BlockType::Authority
+        );
+
+        let mut ctx = EntryContext::new();
+        ctx.set_resource(ResourceWrapper::new(
+            res_name,
+            ResourceType::Common,
+            TrafficType::Inbound,
+        ));
+        ctx.set_origin("app2".into());
+        assert!(slot.check(&mut ctx).is_pass());
+    }
+}
@@ -10,6 +10,7 @@ impl<T: Any + fmt::Debug + utils::AsAny + Send + Sync> SnapshotTrait for T {}
 pub type Snapshot = dyn SnapshotTrait;
 
 // BlockError indicates the request was blocked by Sentinel.
+// It is returned by `EntryBuilder::build()` as `EntryError::Blocked`.
 #[derive(Debug, Clone, Default)]
 pub struct BlockError {
     block_type: BlockType,
@@ -87,6 +88,52 @@ impl fmt::Display for BlockError {
     }
 }
 
+impl std::error::Error for BlockError {}
+
+/// `EntryError` is the error returned by `EntryBuilder::build()` and `EntryBuilder::build_async()`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EntryError {
+    /// `Blocked` indicates the invocation is blocked by the rules,
+    /// the `BlockError` keeps the block type, the triggered rule and the snapshot value
+    Blocked(BlockError),
+}
+
+impl EntryError {
+    pub fn is_blocked(&self) -> bool {
+        matches!(self, EntryError::Blocked(_))
+    }
+
+    /// `block_error` returns the `BlockError` if the invocation is blocked
+    pub fn block_error(&self) -> Option<&BlockError> {
+        match self {
+            EntryError::Blocked(block_err) => Some(block_err),
+        }
+    }
+}
+
+impl From<BlockError> for EntryError {
+    fn from(block_err: BlockError) -> Self {
+        EntryError::Blocked(block_err)
+    }
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryError::Blocked(block_err) => write!(f, "{}", block_err),
+        }
+    }
+}
+
+impl std::error::Error for EntryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EntryError::Blocked(block_err) => Some(block_err),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     #![allow(clippy::vtable_address_comparisons)]
@@ -145,6 +192,16 @@ mod test {
         }
     }
 
+    #[test]
+    fn entry_error() {
+        let err = EntryError::from(BlockError::new(BlockType::CircuitBreaking));
+        assert!(err.is_blocked());
+        assert_eq!(
+            err.block_error().unwrap().block_type(),
+            BlockType::CircuitBreaking
+        );
+    }
+
     #[test]
     fn error_create() {
         testcase(BlockType::Flow, None, None, None);
@@ -1,6 +1,9 @@
 pub const TOTAL_IN_BOUND_RESOURCE_NAME: &str = "__total_inbound_traffic__";
 
 pub const DEFAULT_MAX_RESOURCE_AMOUNT: usize = 10000;
+/// the maximum amount of origin statistic nodes kept for each resource,
+/// the least recently used one is evicted when it is exceeded
+pub const DEFAULT_MAX_ORIGIN_AMOUNT: usize = 1000;
 
 pub const DEFAULT_SAMPLE_COUNT: u32 = 2;
 pub const DEFAULT_INTERVAL_MS: u32 = 1000;
@@ -13,3 +16,6 @@ pub const DEFAULT_INTERVAL_MS_TOTAL: u32 = 10000;
 pub const DEFAULT_STATISTIC_MAX_RT: u64 = 60000;
 
 pub const SLOT_INIT: usize = 8;
+
+/// the name of the invocation context, if no context is entered explicitly
+pub const DEFAULT_CONTEXT_NAME: &str = "sentinel_default_context";
@@ -1,7 +1,9 @@
 //! Context
 //!
-use super::{EntryWeakPtr, ResourceWrapper, StatNode, TokenResult};
-use crate::utils::time::curr_time_millis;
+use super::{
+    BlockError, EntryWeakPtr, ParamKey, ResourceWrapper, SentinelRule, StatNode, TokenResult,
+};
+use crate::utils::time::{curr_time_millis, sleep_for_ns};
 use crate::Error;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -22,10 +24,26 @@ pub struct EntryContext {
     // todo: is it necessary to keep using trait object here?
     // consider replacing by `crate::core::stat::ResourceNode`
     stat_node: Option<Arc<dyn StatNode>>,
+    /// the origin (caller) of the invocation, empty if unknown
+    origin: String,
+    /// the statistic node of the origin on the resource
+    origin_node: Option<Arc<dyn StatNode>>,
+    /// the name of the invocation context, i.e., the entrance of the call tree
+    context_name: String,
+    /// the statistic node of the resource under the invocation context
+    chain_node: Option<Arc<dyn StatNode>>,
     input: SentinelInput,
     /// the result of rule slots check
     rule_check_result: TokenResult,
+    /// the block errors of the rules in shadow mode, which would have blocked the invocation
+    shadow_blocks: Vec<BlockError>,
+    /// the rules of which the prioritized invocation occupies the tokens of the next bucket
+    occupied_rules: Vec<Arc<dyn SentinelRule>>,
     err: Option<Error>,
+    /// If true, the waiting time required by rule slots is accumulated in `wait_nanos`
+    /// rather than sleeping the current thread, see `SlotChain::entry_async()`
+    defer_wait: bool,
+    wait_nanos: u64,
 }
 
 impl EntryContext {
@@ -84,6 +102,38 @@ impl EntryContext {
         self.stat_node.clone()
     }
 
+    pub fn set_origin(&mut self, origin: String) {
+        self.origin = origin;
+    }
+
+    pub fn origin(&self) -> &String {
+        &self.origin
+    }
+
+    pub fn set_origin_node(&mut self, origin_node: Arc<dyn StatNode>) {
+        self.origin_node = Some(origin_node);
+    }
+
+    pub fn origin_node(&self) -> Option<Arc<dyn StatNode>> {
+        self.origin_node.clone()
+    }
+
+    pub fn set_context_name(&mut self, context_name: String) {
+        self.context_name = context_name;
+    }
+
+    pub fn context_name(&self) -> &String {
+        &self.context_name
+    }
+
+    pub fn set_chain_node(&mut self, chain_node: Arc<dyn StatNode>) {
+        self.chain_node = Some(chain_node);
+    }
+
+    pub fn chain_node(&self) -> Option<Arc<dyn StatNode>> {
+        self.chain_node.clone()
+    }
+
     pub fn set_result(&mut self, result: TokenResult) {
         self.rule_check_result = result;
     }
@@ -96,6 +146,32 @@ impl EntryContext {
         &self.rule_check_result
     }
 
+    /// add_shadow_block is called by rule slots when a rule in shadow mode would have blocked the invocation
+    pub fn add_shadow_block(&mut self, block_err: BlockError) {
+        self.shadow_blocks.push(block_err);
+    }
+
+    pub fn shadow_blocks(&self) -> &Vec<BlockError> {
+        &self.shadow_blocks
+    }
+
+    pub fn is_shadow_blocked(&self) -> bool {
+        !self.shadow_blocks.is_empty()
+    }
+
+    /// mark_occupied is called by rule slots when the prioritized invocation occupies the tokens of the next bucket of the rule
+    pub fn mark_occupied(&mut self, rule: Arc<dyn SentinelRule>) {
+        self.occupied_rules.push(rule);
+    }
+
+    pub fn is_occupied(&self) -> bool {
+        !self.occupied_rules.is_empty()
+    }
+
+    pub fn occupied_rules(&self) -> &Vec<Arc<dyn SentinelRule>> {
+        &self.occupied_rules
+    }
+
     pub fn set_err(&mut self, err: Error) {
         self.err = Some(err);
     }
@@ -103,10 +179,34 @@ impl EntryContext {
     pub fn get_err(&self) -> &Option<Error> {
         &self.err
     }
+
+    pub fn set_defer_wait(&mut self, defer_wait: bool) {
+        self.defer_wait = defer_wait;
+    }
+
+    pub fn defer_wait(&self) -> bool {
+        self.defer_wait
+    }
+
+    /// wait_for_ns is called by rule slots when a `TokenResult::Wait` is returned.
+    /// It sleeps the current thread, unless the waiting is deferred to the async slot chain.
+    pub fn wait_for_ns(&mut self, nanos: u64) {
+        if self.defer_wait {
+            self.wait_nanos += nanos;
+        } else {
+            sleep_for_ns(nanos);
+        }
+    }
+
+    /// take_wait_nanos returns the accumulated deferred waiting time and resets it
+    pub fn take_wait_nanos(&mut self) -> u64 {
+        std::mem::take(&mut self.wait_nanos)
+    }
 }
 
-pub type ParamKey = String;
+/// `ParamsList` are the args of an invocation, which are extracted by the `param_index` of hotspot rules
 pub type ParamsList = Vec<ParamKey>;
+/// `ParamsMap` are the attachments of an invocation, which are extracted by the `param_key` of hotspot rules
 pub type ParamsMap = HashMap<String, ParamKey>;
 
 /// Input of policy algorithms
@@ -114,6 +214,8 @@ pub type ParamsMap = HashMap<String, ParamKey>;
 pub struct SentinelInput {
     batch_count: u32,
     flag: i32,
+    /// prioritized invocations may occupy the tokens of the next bucket when current window is exhausted
+    prioritized: bool,
     /// following input items are used in hotspot module
     args: Option<ParamsList>,
     attachments: Option<ParamsMap>,
@@ -124,6 +226,7 @@ impl Default for SentinelInput {
         SentinelInput {
             batch_count: 1,
             flag: 0,
+            prioritized: false,
             args: None,
             attachments: None,
         }
@@ -155,6 +258,14 @@ impl SentinelInput {
         self.flag
     }
 
+    pub fn set_prioritized(&mut self, prioritized: bool) {
+        self.prioritized = prioritized;
+    }
+
+    pub fn is_prioritized(&self) -> bool {
+        self.prioritized
+    }
+
     pub fn set_args(&mut self, args: ParamsList) {
         self.args = Some(args);
     }
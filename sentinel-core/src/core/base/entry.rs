@@ -1,6 +1,8 @@
 use super::{ContextPtr, SlotChain};
 use crate::logging;
 use crate::{Error, Result};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::sync::{RwLock, Weak};
 use std::vec::Vec;
@@ -20,6 +22,8 @@ pub struct SentinelEntry {
     /// each entry traverses a slot chain,
     /// global slot chain is wrapped by Arc, thus here we use Arc
     sc: Arc<SlotChain>,
+    /// guarantee the entry exits only once
+    exited: AtomicBool,
 }
 
 impl SentinelEntry {
@@ -28,6 +32,7 @@ impl SentinelEntry {
             ctx,
             exit_handlers: Vec::new(),
             sc,
+            exited: AtomicBool::new(false),
         }
     }
 
@@ -44,7 +49,11 @@ impl SentinelEntry {
     }
 
     // todo: cleanup
+    /// exit the entry, calling it more than once takes no effects
     pub fn exit(&self) {
+        if self.exited.swap(true, Ordering::SeqCst) {
+            return;
+        }
         for handler in &self.exit_handlers {
             handler(self, self.ctx.clone()) // Rc/Arc clone
                 .map_err(|err: Error| {
@@ -75,6 +84,41 @@ impl EntryStrongPtr {
     }
 }
 
+/// EntryGuard exits the wrapped entry when it is dropped,
+/// so that the entry won't leak in case of early returns or panics.
+/// If the guard is dropped during panicking, an error is recorded on the entry before exiting.
+pub struct EntryGuard(EntryStrongPtr);
+
+impl EntryGuard {
+    pub fn new(entry: EntryStrongPtr) -> EntryGuard {
+        EntryGuard(entry)
+    }
+}
+
+impl From<EntryStrongPtr> for EntryGuard {
+    fn from(entry: EntryStrongPtr) -> Self {
+        EntryGuard::new(entry)
+    }
+}
+
+impl Deref for EntryGuard {
+    type Target = EntryStrongPtr;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for EntryGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0
+                .set_err(Error::msg("panicked while holding the sentinel entry"));
+        }
+        self.0.exit();
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -105,5 +149,32 @@ mod test {
         EXIT_FLAG.with(|f| {
             assert_eq!(*f.borrow(), 1);
         });
+        // exit twice
+        entry.read().unwrap().exit();
+        EXIT_FLAG.with(|f| {
+            assert_eq!(*f.borrow(), 1);
+        });
+    }
+
+    #[test]
+    fn guard() {
+        let sc = Arc::new(SlotChain::new());
+        let ctx = Arc::new(RwLock::new(EntryContext::new()));
+        let mut entry = SentinelEntry::new(ctx.clone(), sc);
+
+        entry.when_exit(Box::new(exit_handler_mock));
+        let entry = Arc::new(RwLock::new(entry));
+        ctx.write().unwrap().set_entry(Arc::downgrade(&entry));
+        {
+            let guard = EntryGuard::new(EntryStrongPtr::new(entry));
+            guard.set_err(Error::msg("biz error"));
+            EXIT_FLAG.with(|f| {
+                assert_eq!(*f.borrow(), 0);
+            });
+        }
+        EXIT_FLAG.with(|f| {
+            assert_eq!(*f.borrow(), 1);
+        });
+        assert!(ctx.read().unwrap().get_err().is_some());
     }
 }
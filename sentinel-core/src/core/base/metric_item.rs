@@ -23,6 +23,8 @@ pub struct MetricItem {
     pub(crate) avg_rt: u64,
     pub(crate) occupied_pass_qps: u64,
     pub(crate) concurrency: u32,
+    /// the count of invocations which would have been blocked by rules in shadow mode
+    pub(crate) shadow_block_qps: u64,
 }
 
 impl fmt::Display for MetricItem {
@@ -31,7 +33,7 @@ impl fmt::Display for MetricItem {
         let final_name = self.resource.replace(METRIC_PART_SEPARATOR, "_");
         write!(
             f,
-            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
             self.timestamp,
             time_str,
             final_name,
@@ -42,7 +44,8 @@ impl fmt::Display for MetricItem {
             self.avg_rt,
             self.occupied_pass_qps,
             self.concurrency,
-            self.resource_type as u8
+            self.resource_type as u8,
+            self.shadow_block_qps
         )
     }
 }
@@ -73,6 +76,9 @@ impl MetricItem {
                 item.concurrency = arr[9].parse::<u32>()?;
                 if arr.len() >= 11 {
                     item.resource_type = arr[10].parse::<u8>()?.into();
+                    if arr.len() >= 12 {
+                        item.shadow_block_qps = arr[11].parse::<u64>()?;
+                    }
                 }
             }
         }
@@ -100,6 +106,18 @@ mod test {
         assert_eq!(25u64, metric_item.avg_rt);
         assert_eq!("/foo/*", metric_item.resource);
         assert_eq!(1u8, metric_item.resource_type as u8);
+        assert_eq!(0u64, metric_item.shadow_block_qps);
+    }
+
+    #[test]
+    fn shadow_block() {
+        let metric_item = "1564382218000|2019-07-29 14:36:58|/foo/*|4|9|3|0|25|0|2|1|5";
+        let metric_item = MetricItem::from_string(metric_item).unwrap();
+        assert_eq!(5u64, metric_item.shadow_block_qps);
+        // the time string depends on the local timezone
+        assert!(metric_item
+            .to_string()
+            .ends_with("|/foo/*|4|9|3|0|25|0|2|1|5"));
     }
 
     #[test]
@@ -3,9 +3,12 @@ pub mod constant;
 pub mod context;
 pub mod entry;
 pub mod metric_item;
+pub mod param;
+pub mod pattern;
 pub mod resource;
 pub mod result;
 pub mod rule;
+pub mod schedule;
 pub mod slot_chain;
 pub mod stat;
 
@@ -14,8 +17,11 @@ pub use constant::*;
 pub use context::*;
 pub use entry::*;
 pub use metric_item::*;
+pub use param::*;
+pub use pattern::*;
 pub use resource::*;
 pub use result::*;
 pub use rule::*;
+pub use schedule::*;
 pub use slot_chain::*;
 pub use stat::*;
@@ -0,0 +1,349 @@
+//! Parameters of the invocations, which are checked by the hotspot rules.
+//!
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// `ParamKey` is a parameter of an invocation, see `EntryBuilder::with_args()` and `EntryBuilder::with_attachments()`.
+///
+/// The parameters equal to each other as long as they represent the same value,
+/// regardless of the variants. That is, the integers are compared by their values, e.g., `1u8` equals to `1i64`,
+/// and the strings and bytes are compared by their contents. Besides, the strings in the canonical form of an integer
+/// or a boolean, e.g., `"100"` and `"true"`, equal to the corresponding integer or boolean,
+/// so that the `specific_items` of hotspot rules loaded from the data sources, whose keys are always strings,
+/// match the typed parameters.
+///
+/// The parameters are serialized as JSON scalars. The bytes are serialized as a string, and the ones which are not
+/// valid UTF-8 are encoded in hex with the `hex:` prefix, e.g., `"hex:fffe"`.
+#[derive(Debug, Clone)]
+pub enum ParamKey {
+    Int(i64),
+    UInt(u64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Bool(bool),
+}
+
+impl Default for ParamKey {
+    fn default() -> Self {
+        ParamKey::Str(String::new())
+    }
+}
+
+/// `Canonical` is the representation of a `ParamKey` used in comparing and hashing.
+#[derive(PartialEq, Eq, Hash)]
+enum Canonical<'a> {
+    Int(i128),
+    Bool(bool),
+    Bytes(&'a [u8]),
+}
+
+/// `parse_integer` parses the bytes in the canonical form of an integer, i.e., without the plus sign and leading zeros.
+fn parse_integer(bytes: &[u8]) -> Option<i128> {
+    let digits = bytes.strip_prefix(b"-").unwrap_or(bytes);
+    // the length of u64::MAX and i64::MIN
+    if digits.is_empty() || digits.len() > 20 || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    if digits[0] == b'0' && bytes != b"0" {
+        return None;
+    }
+    let value: i128 = std::str::from_utf8(bytes).ok()?.parse().ok()?;
+    (i64::MIN as i128..=u64::MAX as i128)
+        .contains(&value)
+        .then_some(value)
+}
+
+impl ParamKey {
+    fn canonical(&self) -> Canonical<'_> {
+        let bytes = match self {
+            ParamKey::Int(v) => return Canonical::Int(*v as i128),
+            ParamKey::UInt(v) => return Canonical::Int(*v as i128),
+            ParamKey::Bool(v) => return Canonical::Bool(*v),
+            ParamKey::Str(s) => s.as_bytes(),
+            ParamKey::Bytes(b) => b.as_slice(),
+        };
+        match bytes {
+            b"true" => Canonical::Bool(true),
+            b"false" => Canonical::Bool(false),
+            _ => parse_integer(bytes).map_or(Canonical::Bytes(bytes), Canonical::Int),
+        }
+    }
+}
+
+impl PartialEq for ParamKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Eq for ParamKey {}
+
+impl Hash for ParamKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
+}
+
+impl fmt::Display for ParamKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamKey::Int(v) => write!(f, "{}", v),
+            ParamKey::UInt(v) => write!(f, "{}", v),
+            ParamKey::Str(s) => write!(f, "{}", s),
+            ParamKey::Bytes(b) => write!(f, "{}", String::from_utf8_lossy(b)),
+            ParamKey::Bool(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($variant:ident, $target:ty, $($from:ty),+) => {
+        $(
+            impl From<$from> for ParamKey {
+                fn from(v: $from) -> Self {
+                    ParamKey::$variant(v as $target)
+                }
+            }
+        )+
+    };
+}
+
+impl_from!(Int, i64, i8, i16, i32, i64, isize);
+impl_from!(UInt, u64, u8, u16, u32, u64, usize);
+
+impl From<bool> for ParamKey {
+    fn from(v: bool) -> Self {
+        ParamKey::Bool(v)
+    }
+}
+
+impl From<String> for ParamKey {
+    fn from(v: String) -> Self {
+        ParamKey::Str(v)
+    }
+}
+
+impl From<&str> for ParamKey {
+    fn from(v: &str) -> Self {
+        ParamKey::Str(v.to_owned())
+    }
+}
+
+impl From<&String> for ParamKey {
+    fn from(v: &String) -> Self {
+        ParamKey::Str(v.clone())
+    }
+}
+
+impl From<Vec<u8>> for ParamKey {
+    fn from(v: Vec<u8>) -> Self {
+        ParamKey::Bytes(v)
+    }
+}
+
+impl From<&[u8]> for ParamKey {
+    fn from(v: &[u8]) -> Self {
+        ParamKey::Bytes(v.to_vec())
+    }
+}
+
+/// the prefix of the strings encoding the bytes which are not valid UTF-8
+const HEX_PREFIX: &str = "hex:";
+
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(HEX_PREFIX.len() + bytes.len() * 2);
+    encoded.push_str(HEX_PREFIX);
+    for b in bytes {
+        encoded.push_str(&format!("{:02x}", b));
+    }
+    encoded
+}
+
+/// `decode_hex` decodes the strings produced by `encode_hex()`.
+/// The strings decoded to valid UTF-8 are never produced by `encode_hex()`, so they are left as they are.
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let digits = s.strip_prefix(HEX_PREFIX)?;
+    if digits.is_empty() || digits.len() % 2 != 0 || !digits.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    let bytes: Vec<u8> = (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect();
+    std::str::from_utf8(&bytes).is_err().then_some(bytes)
+}
+
+impl Serialize for ParamKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ParamKey::Int(v) => serializer.serialize_i64(*v),
+            ParamKey::UInt(v) => serializer.serialize_u64(*v),
+            ParamKey::Str(s) => serializer.serialize_str(s),
+            // the bytes are always serialized as a string, so that they can be the keys of maps,
+            // the ones not valid UTF-8 are encoded in hex with the `hex:` prefix
+            ParamKey::Bytes(b) => match std::str::from_utf8(b) {
+                Ok(s) => serializer.serialize_str(s),
+                Err(_) => serializer.serialize_str(&encode_hex(b)),
+            },
+            ParamKey::Bool(v) => serializer.serialize_bool(*v),
+        }
+    }
+}
+
+struct ParamKeyVisitor;
+
+impl<'de> Visitor<'de> for ParamKeyVisitor {
+    type Value = ParamKey;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer, a string, bytes or a boolean")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<ParamKey, E> {
+        Ok(ParamKey::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<ParamKey, E> {
+        Ok(ParamKey::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<ParamKey, E> {
+        Ok(ParamKey::UInt(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<ParamKey, E> {
+        Ok(decode_hex(v).map_or_else(|| ParamKey::Str(v.to_owned()), ParamKey::Bytes))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<ParamKey, E> {
+        Ok(decode_hex(&v).map_or(ParamKey::Str(v), ParamKey::Bytes))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ParamKey, E> {
+        Ok(ParamKey::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<ParamKey, E> {
+        Ok(ParamKey::Bytes(v))
+    }
+
+    // the formats without the bytes type, e.g., JSON, may represent the bytes by an array of integers
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ParamKey, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element::<u8>()? {
+            bytes.push(b);
+        }
+        Ok(ParamKey::Bytes(bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for ParamKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ParamKeyVisitor)
+    }
+}
+
+cfg_k8s! {
+    impl schemars::JsonSchema for ParamKey {
+        fn schema_name() -> String {
+            "ParamKey".into()
+        }
+
+        // the parameters are the keys of `specific_items` in the custom resources, which are always strings
+        fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+            String::json_schema(gen)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+
+    fn hash_of(key: &ParamKey) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn eq_and_hash() {
+        let groups: Vec<Vec<ParamKey>> = vec![
+            vec![100u8.into(), 100i64.into(), 100usize.into(), "100".into()],
+            vec![(-1i32).into(), "-1".into(), b"-1".as_slice().into()],
+            vec![true.into(), "true".into()],
+            vec!["abc".into(), b"abc".to_vec().into()],
+            vec![u64::MAX.into(), u64::MAX.to_string().into()],
+        ];
+        for (i, group) in groups.iter().enumerate() {
+            for a in group {
+                for b in group {
+                    assert_eq!(a, b);
+                    assert_eq!(hash_of(a), hash_of(b));
+                }
+                for other in groups.iter().skip(i + 1).flatten() {
+                    assert_ne!(a, other);
+                }
+            }
+        }
+        // not in the canonical form
+        for s in ["0100", "+1", "-0", "1.0", "True", ""] {
+            assert_ne!(ParamKey::from(s), ParamKey::from(1));
+            assert_ne!(ParamKey::from(s), ParamKey::from(0));
+            assert_ne!(ParamKey::from(s), ParamKey::from(true));
+        }
+        assert_eq!(ParamKey::from("0"), ParamKey::from(0));
+    }
+
+    #[test]
+    fn serde() {
+        let keys: Vec<ParamKey> = serde_json::from_str(r#"[1, -1, "abc", true]"#).unwrap();
+        assert!(matches!(keys[0], ParamKey::UInt(1)));
+        assert!(matches!(keys[1], ParamKey::Int(-1)));
+        assert!(matches!(&keys[2], ParamKey::Str(s) if s == "abc"));
+        assert!(matches!(keys[3], ParamKey::Bool(true)));
+        assert_eq!(
+            serde_json::to_string(&keys).unwrap(),
+            r#"[1,-1,"abc",true]"#
+        );
+
+        let items: HashMap<ParamKey, u64> =
+            serde_json::from_str(r#"{"100": 1, "abc": 2, "true": 3}"#).unwrap();
+        assert_eq!(items.get(&100.into()), Some(&1));
+        assert_eq!(items.get(&b"abc".to_vec().into()), Some(&2));
+        assert_eq!(items.get(&true.into()), Some(&3));
+        let json = serde_json::to_string(&HashMap::from([(ParamKey::from(100), 1)])).unwrap();
+        assert_eq!(json, r#"{"100":1}"#);
+    }
+
+    #[test]
+    fn serde_bytes() {
+        let invalid_utf8 = ParamKey::from(vec![0xff, 0x00, 0x9f]);
+        let json = serde_json::to_string(&invalid_utf8).unwrap();
+        assert_eq!(json, r#""hex:ff009f""#);
+        let decoded: ParamKey = serde_json::from_str(&json).unwrap();
+        assert!(matches!(&decoded, ParamKey::Bytes(b) if b == &[0xff, 0x00, 0x9f]));
+
+        let items = HashMap::from([(invalid_utf8.clone(), 1u64), ("abc".into(), 2)]);
+        let json = serde_json::to_string(&items).unwrap();
+        let decoded: HashMap<ParamKey, u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, items);
+
+        let decoded: ParamKey = serde_json::from_str("[255, 0, 159]").unwrap();
+        assert_eq!(decoded, invalid_utf8);
+        assert!(serde_json::from_str::<ParamKey>("[256]").is_err());
+
+        // the strings which are not produced by the encoding are kept
+        for s in ["hex:", "hex:6869", "hex:fff", "hex:zz", "hex"] {
+            let decoded: ParamKey = serde_json::from_str(&format!("\"{}\"", s)).unwrap();
+            assert!(matches!(&decoded, ParamKey::Str(v) if v == s));
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Pattern-based resource names in rules
+//!
+use super::DEFAULT_MAX_RESOURCE_AMOUNT;
+use crate::{logging, Error, Result};
+use lru::LruCache;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+cfg_k8s! {
+    use schemars::JsonSchema;
+}
+
+/// `MatchStrategy` indicates how the `resource` of a rule matches the resource names.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize, Hash, Eq)]
+pub enum MatchStrategy {
+    /// `Exact` matches the resource with exactly the same name.
+    #[default]
+    Exact,
+    /// `Glob` matches the resource names with a glob pattern, e.g., `/api/users/*`.
+    /// `*` and `?` never match `/`, while `**` matches any sequence of characters.
+    Glob,
+    /// `Regex` matches the resource names with a regular expression,
+    /// which has to match the whole resource name.
+    Regex,
+}
+
+impl MatchStrategy {
+    pub fn is_pattern(&self) -> bool {
+        *self != MatchStrategy::Exact
+    }
+
+    /// `compile` translates the pattern into an anchored regular expression.
+    pub fn compile(&self, pattern: &str) -> Result<Regex> {
+        let expr = match self {
+            MatchStrategy::Exact => regex::escape(pattern),
+            MatchStrategy::Glob => glob_to_regex(pattern),
+            MatchStrategy::Regex => pattern.into(),
+        };
+        Regex::new(&format!("^(?:{})$", expr)).map_err(Error::msg)
+    }
+}
+
+/// `PatternRule` is a rule whose resource may be a pattern, see `MatchStrategy`.
+pub trait PatternRule {
+    fn match_strategy(&self) -> MatchStrategy;
+    /// `resource` returns the resource name or the resource pattern of the rule
+    fn resource(&self) -> &str;
+}
+
+fn glob_to_regex(pattern: &str) -> String {
+    let mut expr = String::with_capacity(pattern.len() * 2);
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                expr.push_str(".*");
+            }
+            '*' => expr.push_str("[^/]*"),
+            '?' => expr.push_str("[^/]"),
+            _ => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expr
+}
+
+struct PatternState<R, T> {
+    /// the generation is increased whenever the rules are updated, the items resolved in previous generations are stale
+    generation: u64,
+    /// the rules with resource patterns, along with the patterns compiled when the rules are loaded
+    patterns: Vec<(Arc<R>, Regex)>,
+    /// the resolved items of each resource name, along with the generation they are resolved in
+    resolved: LruCache<String, (u64, Vec<T>)>,
+}
+
+/// `PatternCache` keeps the rules with resource patterns, and caches the items (e.g., rules or controllers)
+/// resolved from them for each resource name, so that the patterns are matched only once per new resource name.
+/// The patterns are compiled once by `load_rules()`, which has to be called whenever the rules are updated.
+///
+/// At most `DEFAULT_MAX_RESOURCE_AMOUNT` resource names are cached by default,
+/// the least recently resolved one is evicted when a new resource name is met.
+pub struct PatternCache<R, T> {
+    state: RwLock<PatternState<R, T>>,
+}
+
+impl<R, T> std::fmt::Debug for PatternCache<R, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.read().unwrap();
+        f.debug_struct("PatternCache")
+            .field("generation", &state.generation)
+            .field("patterns", &state.patterns.len())
+            .field("resolved", &state.resolved.len())
+            .finish()
+    }
+}
+
+impl<R: PatternRule, T: Clone> Default for PatternCache<R, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: PatternRule, T: Clone> PatternCache<R, T> {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_MAX_RESOURCE_AMOUNT)
+    }
+
+    /// `with_capacity` creates a cache keeping the resolved items of at most `capacity` resource names
+    pub fn with_capacity(capacity: usize) -> Self {
+        PatternCache {
+            state: RwLock::new(PatternState {
+                generation: 0,
+                patterns: Vec::new(),
+                resolved: LruCache::new(capacity),
+            }),
+        }
+    }
+
+    /// `load_rules` compiles the patterns of the given rules, the rules with exact resource names are skipped.
+    /// The items resolved from the previous rules become stale, they are handed over to the next resolving
+    /// of each resource name, so that the unchanged items (e.g., stateful controllers) can be reused.
+    pub fn load_rules<'a, I>(&self, rules: I)
+    where
+        I: IntoIterator<Item = &'a Arc<R>>,
+        R: 'a,
+    {
+        let mut patterns = Vec::new();
+        for rule in rules {
+            if !rule.match_strategy().is_pattern() {
+                continue;
+            }
+            // the patterns have been validated along with the rules
+            match rule.match_strategy().compile(rule.resource()) {
+                Ok(regex) => patterns.push((Arc::clone(rule), regex)),
+                Err(err) => logging::warn!(
+                    "[PatternCache] Ignoring invalid resource pattern {}, reason: {:?}",
+                    rule.resource(),
+                    err
+                ),
+            }
+        }
+        let mut state = self.state.write().unwrap();
+        state.generation += 1;
+        state.patterns = patterns;
+    }
+
+    /// `get_or_resolve` returns the cached items of the resource, or resolves them by `resolve`
+    /// if the resource is met for the first time since the rules are loaded.
+    /// `resolve` is called with the rules matching the resource name, and the stale items of the resource, if any.
+    pub fn get_or_resolve<F>(&self, res_name: &String, resolve: F) -> Vec<T>
+    where
+        F: FnOnce(Vec<Arc<R>>, Vec<T>) -> Vec<T>,
+    {
+        let (generation, matched, stale) = {
+            let state = self.state.read().unwrap();
+            let stale = match state.resolved.peek(res_name) {
+                Some((generation, items)) if *generation == state.generation => {
+                    return items.clone()
+                }
+                Some((_, items)) => items.clone(),
+                None => Vec::new(),
+            };
+            let matched: Vec<Arc<R>> = state
+                .patterns
+                .iter()
+                .filter(|(_, regex)| regex.is_match(res_name))
+                .map(|(rule, _)| Arc::clone(rule))
+                .collect();
+            (state.generation, matched, stale)
+        };
+        let items = if matched.is_empty() {
+            Vec::new()
+        } else {
+            resolve(matched, stale)
+        };
+        let mut state = self.state.write().unwrap();
+        if state.generation == generation {
+            // the resource may have been resolved by another invocation meanwhile
+            if let Some((resolved_generation, resolved)) = state.resolved.get(res_name) {
+                if *resolved_generation == generation {
+                    return resolved.clone();
+                }
+            }
+            state
+                .resolved
+                .put(res_name.clone(), (generation, items.clone()));
+        }
+        items
+    }
+
+    /// `clear` drops all the rules and the resolved items
+    pub fn clear(&self) {
+        self.load_rules(std::iter::empty());
+        self.state.write().unwrap().resolved.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn matches(strategy: MatchStrategy, pattern: &str, res_name: &str) -> bool {
+        strategy.compile(pattern).unwrap().is_match(res_name)
+    }
+
+    #[test]
+    fn glob() {
+        let strategy = MatchStrategy::Glob;
+        assert!(matches(strategy, "/api/users/*", "/api/users/42"));
+        assert!(!matches(strategy, "/api/users/*", "/api/users/42/orders"));
+        assert!(matches(strategy, "/api/**", "/api/users/42/orders"));
+        assert!(matches(strategy, "/api/v?/users", "/api/v1/users"));
+        assert!(!matches(strategy, "/api/users.*", "/api/usersx"));
+    }
+
+    #[test]
+    fn regex() {
+        let strategy = MatchStrategy::Regex;
+        assert!(matches(strategy, r"/api/users/\d+", "/api/users/42"));
+        assert!(!matches(
+            strategy,
+            r"/api/users/\d+",
+            "/api/users/42/orders"
+        ));
+        assert!(strategy.compile("(").is_err());
+    }
+
+    #[test]
+    fn exact() {
+        let strategy = MatchStrategy::Exact;
+        assert!(matches(strategy, "/api/*", "/api/*"));
+        assert!(!matches(strategy, "/api/*", "/api/users"));
+    }
+
+    struct TestRule(MatchStrategy, &'static str);
+
+    impl PatternRule for TestRule {
+        fn match_strategy(&self) -> MatchStrategy {
+            self.0
+        }
+
+        fn resource(&self) -> &str {
+            self.1
+        }
+    }
+
+    #[test]
+    fn cache() {
+        let cache = PatternCache::new();
+        let rules = vec![
+            Arc::new(TestRule(MatchStrategy::Glob, "/api/*")),
+            Arc::new(TestRule(MatchStrategy::Exact, "/api/*")),
+            Arc::new(TestRule(MatchStrategy::Regex, "(")),
+        ];
+        cache.load_rules(&rules);
+        let res_name = String::from("/api/users");
+        let resolve = |matched: Vec<Arc<TestRule>>, stale: Vec<usize>| {
+            assert!(stale.is_empty());
+            vec![matched.len()]
+        };
+        assert_eq!(cache.get_or_resolve(&res_name, resolve), vec![1]);
+        assert_eq!(cache.get_or_resolve(&res_name, |_, _| vec![2]), vec![1]);
+        assert!(cache
+            .get_or_resolve(&"/orders".into(), |_, _| vec![2])
+            .is_empty());
+        // the stale items are handed over to the resolving after the rules are updated
+        cache.load_rules(&rules);
+        let reuse = |_, stale: Vec<usize>| stale.into_iter().map(|i| i + 1).collect();
+        assert_eq!(cache.get_or_resolve(&res_name, reuse), vec![2]);
+        assert_eq!(cache.get_or_resolve(&res_name, |_, _| vec![3]), vec![2]);
+        cache.clear();
+        assert!(cache.get_or_resolve(&res_name, |_, _| vec![3]).is_empty());
+    }
+
+    #[test]
+    fn eviction() {
+        let cache = PatternCache::with_capacity(2);
+        cache.load_rules(&[Arc::new(TestRule(MatchStrategy::Glob, "/api/*"))]);
+        for (i, res_name) in ["/api/a", "/api/b", "/api/c"].into_iter().enumerate() {
+            assert_eq!(
+                cache.get_or_resolve(&res_name.into(), |_, _| vec![i]),
+                vec![i]
+            );
+        }
+        // the least recently resolved one is evicted, the others are kept
+        assert_eq!(
+            cache.get_or_resolve(&"/api/a".into(), |_, _| vec![3]),
+            vec![3]
+        );
+        assert_eq!(
+            cache.get_or_resolve(&"/api/c".into(), |_, _| vec![4]),
+            vec![2]
+        );
+        assert_eq!(
+            cache.get_or_resolve(&"/api/b".into(), |_, _| vec![5]),
+            vec![5]
+        );
+    }
+}
@@ -17,6 +17,7 @@ pub enum BlockType {
     CircuitBreaking,
     SystemFlow,
     HotSpotParamFlow,
+    Authority,
     Other(OtherBlockType),
 }
 
@@ -1,5 +1,9 @@
 use crate::Result;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+cfg_k8s! {
+    use schemars::JsonSchema;
+}
 
 pub trait SentinelRule: fmt::Debug + Send + Sync {
     fn resource_name(&self) -> String;
@@ -7,3 +11,15 @@ pub trait SentinelRule: fmt::Debug + Send + Sync {
         Ok(())
     }
 }
+
+/// `RuleMode` indicates how a rule takes effect on the invocations.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize, Hash, Eq)]
+pub enum RuleMode {
+    /// `Enforce` blocks the invocations violating the rule.
+    #[default]
+    Enforce,
+    /// `Shadow` (dry-run) evaluates the rule and records the invocations that would have been blocked,
+    /// but lets them pass, so that the rule can be tuned against real traffic first.
+    Shadow,
+}
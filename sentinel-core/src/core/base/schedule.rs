@@ -0,0 +1,325 @@
+//! Time-scheduled rules
+//!
+use crate::{utils, Error, Result};
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, UtcOffset};
+cfg_k8s! {
+    use schemars::JsonSchema;
+}
+
+/// `TimeWindow` is a range of time in the given weekdays, e.g., 09:00-18:00 on weekdays.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeWindow {
+    /// `weekdays` are the days of the week (0 or 7 is Sunday) when the window starts,
+    /// an empty list means every day.
+    pub weekdays: Vec<u8>,
+    /// `start` is the start time of the window in the format of "HH:MM".
+    pub start: String,
+    /// `end` is the end time (exclusive) of the window in the format of "HH:MM".
+    /// The window crosses midnight if `end` is earlier than `start`,
+    /// and lasts for the whole day if `end` equals `start`.
+    pub end: String,
+}
+
+/// `Schedule` indicates when a rule is active.
+/// The rule is active if the current time matches the `cron` expression or any of the `windows`,
+/// and it is always active if neither of them is set.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    /// `cron` is a cron expression with 5 fields, i.e., "minute hour day-of-month month day-of-week",
+    /// the rule is active during the minutes matching it, e.g., "* 0-6 * * *" from 00:00 to 06:59.
+    pub cron: String,
+    /// `windows` are the weekly time windows when the rule is active.
+    pub windows: Vec<TimeWindow>,
+    /// `utc_offset` is the timezone of the schedule, in the format of "+08:00" or "-05:30".
+    /// The schedule is in UTC if it is empty.
+    pub utc_offset: String,
+}
+
+impl Schedule {
+    pub fn is_valid(&self) -> Result<()> {
+        self.compile().map(|_| ())
+    }
+
+    /// `compile` parses the schedule, it is called once when the rule is loaded,
+    /// and the compiled schedule is kept by the controller of the rule.
+    pub fn compile(&self) -> Result<CompiledSchedule> {
+        let cron = if self.cron.trim().is_empty() {
+            None
+        } else {
+            Some(Cron::parse(&self.cron)?)
+        };
+        let windows = self
+            .windows
+            .iter()
+            .map(CompiledWindow::parse)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(CompiledSchedule {
+            cron,
+            windows,
+            offset: parse_utc_offset(&self.utc_offset)?,
+        })
+    }
+}
+
+/// `CompiledSchedule` is the parsed `Schedule`, which is checked on every invocation.
+#[derive(Debug)]
+pub struct CompiledSchedule {
+    cron: Option<Cron>,
+    windows: Vec<CompiledWindow>,
+    offset: UtcOffset,
+}
+
+impl CompiledSchedule {
+    /// `is_active` checks whether the schedule is active now
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(utils::curr_time_millis())
+    }
+
+    /// `is_active_at` checks whether the schedule is active at the timestamp (in milliseconds)
+    pub fn is_active_at(&self, ts_millis: u64) -> bool {
+        if self.cron.is_none() && self.windows.is_empty() {
+            return true;
+        }
+        let time = match OffsetDateTime::from_unix_timestamp_nanos(ts_millis as i128 * 1_000_000) {
+            Ok(time) => time.to_offset(self.offset),
+            Err(_) => return true,
+        };
+        self.cron.as_ref().is_some_and(|cron| cron.matches(&time))
+            || self.windows.iter().any(|window| window.contains(&time))
+    }
+}
+
+#[derive(Debug)]
+struct CompiledWindow {
+    // bit `i` is set if the window starts on weekday `i`, Sunday is 0
+    weekdays: u8,
+    // minutes of the day
+    start: u32,
+    end: u32,
+}
+
+const ALL_WEEKDAYS: u8 = 0x7f;
+
+impl CompiledWindow {
+    fn parse(window: &TimeWindow) -> Result<Self> {
+        let mut weekdays = 0;
+        for &day in &window.weekdays {
+            if day > 7 {
+                return Err(Error::msg("invalid weekday, valid range is [0, 7]"));
+            }
+            weekdays |= 1 << (day % 7);
+        }
+        if weekdays == 0 {
+            weekdays = ALL_WEEKDAYS;
+        }
+        Ok(CompiledWindow {
+            weekdays,
+            start: parse_minute_of_day(&window.start)?,
+            end: parse_minute_of_day(&window.end)?,
+        })
+    }
+
+    fn contains(&self, time: &OffsetDateTime) -> bool {
+        let weekday = time.weekday().number_days_from_sunday();
+        let minute = time.hour() as u32 * 60 + time.minute() as u32;
+        let starts_on = |day: u8| self.weekdays & (1 << day) != 0;
+        if self.start < self.end {
+            starts_on(weekday) && minute >= self.start && minute < self.end
+        } else if self.start > self.end {
+            // the window crosses midnight, so it may start on the day before
+            (starts_on(weekday) && minute >= self.start)
+                || (starts_on((weekday + 6) % 7) && minute < self.end)
+        } else {
+            starts_on(weekday)
+        }
+    }
+}
+
+fn parse_minute_of_day(input: &str) -> Result<u32> {
+    let err = || Error::msg(format!("invalid time {}, expected HH:MM", input));
+    let (hour, minute) = input.trim().split_once(':').ok_or_else(err)?;
+    let hour: u32 = hour.parse().map_err(|_| err())?;
+    let minute: u32 = minute.parse().map_err(|_| err())?;
+    if hour >= 24 || minute >= 60 {
+        return Err(err());
+    }
+    Ok(hour * 60 + minute)
+}
+
+fn parse_utc_offset(input: &str) -> Result<UtcOffset> {
+    let input = input.trim();
+    if input.is_empty() || input == "Z" {
+        return Ok(UtcOffset::UTC);
+    }
+    let err = || Error::msg(format!("invalid utc_offset {}, expected +HH:MM", input));
+    let (sign, rest) = match input.as_bytes()[0] {
+        b'+' => (1, &input[1..]),
+        b'-' => (-1, &input[1..]),
+        _ => return Err(err()),
+    };
+    let (hours, minutes) = rest.split_once(':').ok_or_else(err)?;
+    let hours: i8 = hours.parse().map_err(|_| err())?;
+    let minutes: i8 = minutes.parse().map_err(|_| err())?;
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).map_err(|_| err())
+}
+
+/// The bit sets of the matched values of the cron fields
+#[derive(Debug)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    // whether the fields are `*`, see the rule of day matching below
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl Cron {
+    fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(Error::msg(format!(
+                "invalid cron expression {}, expected 5 fields",
+                expr
+            )));
+        }
+        let mut days_of_week = parse_cron_field(fields[4], 0, 7)?;
+        // both 0 and 7 are Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+        Ok(Cron {
+            minutes: parse_cron_field(fields[0], 0, 59)?,
+            hours: parse_cron_field(fields[1], 0, 23)?,
+            days_of_month: parse_cron_field(fields[2], 1, 31)?,
+            months: parse_cron_field(fields[3], 1, 12)?,
+            days_of_week,
+            any_day_of_month: fields[2] == "*",
+            any_day_of_week: fields[4] == "*",
+        })
+    }
+
+    fn matches(&self, time: &OffsetDateTime) -> bool {
+        let is_set = |bits: u64, value: u8| bits & (1 << value) != 0;
+        let day_of_month = is_set(self.days_of_month, time.day());
+        let day_of_week = is_set(self.days_of_week, time.weekday().number_days_from_sunday());
+        // as the classic cron, the day matches either field if both of them are restricted
+        let day = match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+        day && is_set(self.minutes, time.minute())
+            && is_set(self.hours, time.hour())
+            && is_set(self.months, u8::from(time.month()))
+    }
+}
+
+/// `parse_cron_field` parses a cron field with lists (`,`), ranges (`-`) and steps (`/`)
+fn parse_cron_field(field: &str, min: u8, max: u8) -> Result<u64> {
+    let err = || Error::msg(format!("invalid cron field {}", field));
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u8>().map_err(|_| err())?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse().map_err(|_| err())?,
+                end.parse().map_err(|_| err())?,
+            )
+        } else {
+            let value = range.parse().map_err(|_| err())?;
+            // "a/n" means from a to the max value
+            (value, if step > 1 { max } else { value })
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(err());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 2024-01-01 00:00:00 UTC, Monday
+    const MONDAY: u64 = 1_704_067_200_000;
+    const HOUR: u64 = 60 * 60 * 1000;
+    const DAY: u64 = 24 * HOUR;
+
+    #[test]
+    fn cron() {
+        let schedule = Schedule {
+            cron: "*/30 0-6 * * 1-5".into(),
+            ..Default::default()
+        }
+        .compile()
+        .unwrap();
+        assert!(schedule.is_active_at(MONDAY));
+        assert!(schedule.is_active_at(MONDAY + HOUR / 2));
+        assert!(!schedule.is_active_at(MONDAY + HOUR / 4));
+        assert!(!schedule.is_active_at(MONDAY + 7 * HOUR));
+        // Sunday
+        assert!(!schedule.is_active_at(MONDAY + 6 * DAY));
+
+        assert!(Schedule {
+            cron: "* * *".into(),
+            ..Default::default()
+        }
+        .is_valid()
+        .is_err());
+        assert!(Schedule {
+            cron: "60 * * * *".into(),
+            ..Default::default()
+        }
+        .is_valid()
+        .is_err());
+    }
+
+    #[test]
+    fn windows() {
+        let schedule = Schedule {
+            windows: vec![TimeWindow {
+                weekdays: vec![1],
+                start: "22:00".into(),
+                end: "02:00".into(),
+            }],
+            utc_offset: "+08:00".into(),
+            ..Default::default()
+        }
+        .compile()
+        .unwrap();
+        // 08:00 on Monday in UTC+8
+        assert!(!schedule.is_active_at(MONDAY));
+        // 23:00 on Monday in UTC+8
+        assert!(schedule.is_active_at(MONDAY + 15 * HOUR));
+        // 01:00 on Tuesday in UTC+8
+        assert!(schedule.is_active_at(MONDAY + 17 * HOUR));
+        // 03:00 on Tuesday in UTC+8
+        assert!(!schedule.is_active_at(MONDAY + 19 * HOUR));
+        // 23:00 on Tuesday in UTC+8
+        assert!(!schedule.is_active_at(MONDAY + DAY + 15 * HOUR));
+
+        assert!(Schedule::default().compile().unwrap().is_active_at(MONDAY));
+        assert!(Schedule {
+            utc_offset: "08:00".into(),
+            ..Default::default()
+        }
+        .is_valid()
+        .is_err());
+    }
+}
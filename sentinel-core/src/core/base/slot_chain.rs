@@ -1,6 +1,6 @@
 use super::{BlockError, ContextPtr, EntryContext, TokenResult, SLOT_INIT};
-use crate::logging;
 use crate::utils::AsAny;
+use crate::{logging, utils};
 use std::any::Any;
 use std::sync::Arc;
 
@@ -128,32 +128,66 @@ impl SlotChain {
     /// Return the TokenResult
     pub fn entry(&self, ctx_ptr: ContextPtr) -> TokenResult {
         let mut ctx = ctx_ptr.write().unwrap();
+        self.check(&mut ctx);
+        self.on_entry(&ctx);
+        ctx.result().clone()
+    }
+
+    /// The async entrance of slot chain.
+    /// Different from `entry()`, the waiting time required by rule checking slots (e.g., throttling)
+    /// is awaited by a timer future instead of sleeping the current thread.
+    /// Return the TokenResult
+    pub async fn entry_async(&self, ctx_ptr: ContextPtr) -> TokenResult {
+        let wait_nanos = {
+            let mut ctx = ctx_ptr.write().unwrap();
+            ctx.set_defer_wait(true);
+            self.check(&mut ctx);
+            ctx.set_defer_wait(false);
+            let wait_nanos = ctx.take_wait_nanos();
+            if ctx.is_blocked() {
+                0
+            } else {
+                wait_nanos
+            }
+        };
+        // the lock guard must not be held across the await point
+        if wait_nanos > 0 {
+            utils::sleep_for_ns_async(wait_nanos).await;
+        }
+        let ctx = ctx_ptr.read().unwrap();
+        self.on_entry(&ctx);
+        ctx.result().clone()
+    }
+
+    /// execute the prepare slots and rule based checking slots
+    fn check(&self, ctx: &mut EntryContext) {
         // execute prepare slot
         for s in &self.stat_pres {
-            s.prepare(&mut ctx); // Rc/Arc clone
+            s.prepare(ctx); // Rc/Arc clone
         }
 
         // execute rule based checking slot
         ctx.reset_result_to_pass();
         for s in &self.rule_checks {
-            let res = s.check(&mut ctx);
+            let res = s.check(ctx);
             // check slot result
             if res.is_blocked() {
                 ctx.set_result(res.clone());
             }
         }
+    }
 
-        // execute statistic slot
+    /// execute statistic slot
+    fn on_entry(&self, ctx: &EntryContext) {
         for s in &self.stats {
             // indicate the result of rule based checking slot.
             if ctx.result().is_pass() {
-                s.on_entry_pass(&ctx) // Rc/Arc clone
+                s.on_entry_pass(ctx) // Rc/Arc clone
             } else if ctx.result().is_blocked() {
                 // The block error should not be none.
-                s.on_entry_blocked(&ctx, ctx.result().block_err().unwrap()) // Rc/Arc clone
+                s.on_entry_blocked(ctx, ctx.result().block_err().unwrap()) // Rc/Arc clone
             }
         }
-        ctx.result().clone()
     }
 }
 
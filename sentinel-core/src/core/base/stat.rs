@@ -23,6 +23,10 @@ pub enum MetricEvent {
     Error,
     /// request execute Round Trip Time, unit is millisecond
     Rt,
+    /// would have been blocked by the rules in shadow mode, but passed
+    ShadowBlock,
+    /// passed by occupying the tokens of the next bucket, used for prioritized invocations
+    OccupiedPass,
 }
 
 // todo: consider use the static reference, do not create Arc pointer?
@@ -63,6 +67,12 @@ pub trait ReadStat: Send + Sync + fmt::Debug {
     fn avg_rt(&self) -> f64 {
         0f64
     }
+    /// `next_bucket_sum` returns the start timestamp (in ms) of the next bucket,
+    /// and the sum of the event which still remains in the sliding window when the next bucket starts.
+    /// `None` indicates the statistic does not support occupying the next bucket.
+    fn next_bucket_sum(&self, _event: MetricEvent) -> Option<(u64, u64)> {
+        None
+    }
 }
 
 pub trait WriteStat: Send + Sync + fmt::Debug {
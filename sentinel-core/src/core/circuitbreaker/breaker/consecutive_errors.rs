@@ -0,0 +1,91 @@
+use super::*;
+use crate::logging;
+use std::sync::{atomic::Ordering, Arc};
+
+/// the statistic interval used when the rule does not set `stat_interval_ms`,
+/// since the `ConsecutiveErrors` strategy does not depend on the sliding window
+const DEFAULT_STAT_INTERVAL_MS: u32 = 1000;
+
+#[derive(Debug)]
+pub struct ConsecutiveErrorsBreaker {
+    breaker: BreakerBase,
+    consecutive_errors_threshold: u64,
+    /// the number of errors since the last successful invocation
+    consecutive_errors: AtomicU64,
+    // stat needs to be shared, so we take Arc
+    stat: Arc<CounterLeapArray>,
+}
+
+impl ConsecutiveErrorsBreaker {
+    pub fn new(rule: Arc<Rule>) -> Self {
+        let (interval, bucket_count) = match rule.stat_interval_ms {
+            0 => (DEFAULT_STAT_INTERVAL_MS, 1),
+            interval => (interval, rule.get_rule_stat_sliding_window_bucket_count()),
+        };
+        let stat = CounterLeapArray::new(bucket_count, interval).unwrap();
+        Self::new_with_stat(rule, Arc::new(stat))
+    }
+
+    pub fn new_with_stat(rule: Arc<Rule>, stat: Arc<CounterLeapArray>) -> Self {
+        let consecutive_errors_threshold = rule.threshold as u64;
+        Self {
+            breaker: BreakerBase::new(rule),
+            consecutive_errors_threshold,
+            consecutive_errors: AtomicU64::new(0),
+            stat,
+        }
+    }
+
+    pub fn consecutive_errors(&self) -> u64 {
+        self.consecutive_errors.load(Ordering::SeqCst)
+    }
+}
+
+impl CircuitBreakerTrait for ConsecutiveErrorsBreaker {
+    fn breaker(&self) -> &BreakerBase {
+        &self.breaker
+    }
+
+    fn stat(&self) -> &Arc<CounterLeapArray> {
+        &self.stat
+    }
+
+    fn reset_metric(&self) {
+        for c in self.stat.all_counter() {
+            c.value().reset()
+        }
+        self.consecutive_errors.store(0, Ordering::SeqCst);
+    }
+
+    fn on_request_complete(&self, _rt: u64, err: &Option<Error>) {
+        let counter = self.stat.current_counter();
+        if counter.is_err() {
+            logging::error!("Fail to get current counter in ConsecutiveErrorsBreaker#on_request_complete(). rule: {:?}", self.breaker.rule);
+            return;
+        }
+        let counter = counter.unwrap();
+
+        let consecutive_errors = if err.is_some() {
+            counter.value().target.fetch_add(1, Ordering::SeqCst);
+            self.consecutive_errors.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            self.consecutive_errors.store(0, Ordering::SeqCst);
+            0
+        };
+        counter.value().total.fetch_add(1, Ordering::SeqCst);
+
+        // handle state changes when threshold exceeded
+        match self.current_state() {
+            State::HalfOpen => {
+                self.on_probe_complete(err.is_none(), Arc::new(consecutive_errors));
+            }
+            State::Closed => {
+                if consecutive_errors >= self.consecutive_errors_threshold {
+                    self.breaker
+                        .from_closed_to_open(Arc::new(consecutive_errors));
+                }
+            }
+            State::Open => {}
+        }
+    }
+}
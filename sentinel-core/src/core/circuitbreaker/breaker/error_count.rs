@@ -1,9 +1,6 @@
 use super::*;
 use crate::logging;
-use std::sync::{
-    atomic::{AtomicU64, Ordering},
-    Arc, Mutex,
-};
+use std::sync::{atomic::Ordering, Arc};
 
 #[derive(Debug)]
 pub struct ErrorCountBreaker {
@@ -23,16 +20,10 @@ impl ErrorCountBreaker {
     }
 
     pub fn new_with_stat(rule: Arc<Rule>, stat: Arc<CounterLeapArray>) -> Self {
-        let retry_timeout_ms = rule.retry_timeout_ms;
         let min_request_amount = rule.min_request_amount;
         let error_count_threshold = rule.threshold as u64;
         Self {
-            breaker: BreakerBase {
-                rule,
-                retry_timeout_ms,
-                next_retry_timestamp_ms: AtomicU64::new(0),
-                state: Arc::new(Mutex::new(State::default())),
-            },
+            breaker: BreakerBase::new(rule),
             min_request_amount,
             error_count_threshold,
             stat,
@@ -73,12 +64,7 @@ impl CircuitBreakerTrait for ErrorCountBreaker {
         // handle state changes when threshold exceeded
         match self.current_state() {
             State::HalfOpen => {
-                if err.is_none() {
-                    self.breaker.from_half_open_to_closed();
-                    self.reset_metric();
-                } else {
-                    self.breaker.from_half_open_to_open(Arc::new(1));
-                }
+                self.on_probe_complete(err.is_none(), Arc::new(1));
             }
             State::Closed => {
                 if total_count >= self.min_request_amount
@@ -1,9 +1,6 @@
 use super::*;
 use crate::logging;
-use std::sync::{
-    atomic::{AtomicU64, Ordering},
-    Arc, Mutex,
-};
+use std::sync::{atomic::Ordering, Arc};
 
 #[derive(Debug)]
 pub struct ErrorRatioBreaker {
@@ -22,16 +19,10 @@ impl ErrorRatioBreaker {
     }
 
     pub fn new_with_stat(rule: Arc<Rule>, stat: Arc<CounterLeapArray>) -> Self {
-        let retry_timeout_ms = rule.retry_timeout_ms;
         let min_request_amount = rule.min_request_amount;
         let error_ratio_threshold = rule.threshold;
         Self {
-            breaker: BreakerBase {
-                rule,
-                retry_timeout_ms,
-                next_retry_timestamp_ms: AtomicU64::new(0),
-                state: Arc::new(Mutex::new(State::default())),
-            },
+            breaker: BreakerBase::new(rule),
             min_request_amount,
             error_ratio_threshold,
             stat,
@@ -73,12 +64,7 @@ impl CircuitBreakerTrait for ErrorRatioBreaker {
         // handle state changes when threshold exceeded
         match self.current_state() {
             State::HalfOpen => {
-                if err.is_none() {
-                    self.breaker.from_half_open_to_closed();
-                    self.reset_metric();
-                } else {
-                    self.breaker.from_half_open_to_open(Arc::new(1));
-                }
+                self.on_probe_complete(err.is_none(), Arc::new(1));
             }
             State::Closed => {
                 if total_count >= self.min_request_amount
@@ -0,0 +1,93 @@
+use super::*;
+use crate::logging;
+use std::sync::{atomic::Ordering, Arc};
+
+#[derive(Debug)]
+pub struct LatencyPercentileBreaker {
+    breaker: BreakerBase,
+    max_allowed_rt: u64,
+    percentile: f64,
+    min_request_amount: u64,
+    // stat needs to be shared, so we take Arc
+    stat: Arc<CounterLeapArray>,
+}
+
+impl LatencyPercentileBreaker {
+    pub fn new(rule: Arc<Rule>) -> Self {
+        let interval = rule.stat_interval_ms;
+        let bucket_count = rule.get_rule_stat_sliding_window_bucket_count();
+        let stat = CounterLeapArray::new(bucket_count, interval).unwrap();
+        Self::new_with_stat(rule, Arc::new(stat))
+    }
+
+    pub fn new_with_stat(rule: Arc<Rule>, stat: Arc<CounterLeapArray>) -> Self {
+        let max_allowed_rt = rule.max_allowed_rt_ms;
+        let percentile = rule.threshold;
+        let min_request_amount = rule.min_request_amount;
+        Self {
+            breaker: BreakerBase::new(rule),
+            max_allowed_rt,
+            percentile,
+            min_request_amount,
+            stat,
+        }
+    }
+
+    /// `current_percentile_rt` returns the round trip time at the percentile of the rule in current statistic window.
+    pub fn current_percentile_rt(&self) -> Option<u64> {
+        let counters = self.stat.all_counter();
+        RtHistogram::percentile_of(
+            counters.iter().filter_map(|c| c.value().histogram.get()),
+            self.percentile,
+        )
+    }
+}
+
+impl CircuitBreakerTrait for LatencyPercentileBreaker {
+    fn breaker(&self) -> &BreakerBase {
+        &self.breaker
+    }
+
+    fn stat(&self) -> &Arc<CounterLeapArray> {
+        &self.stat
+    }
+
+    fn on_request_complete(&self, rt: u64, _err: &Option<Error>) {
+        let counter = self.stat.current_counter();
+        if counter.is_err() {
+            logging::error!("Fail to get current counter in LatencyPercentileBreaker#on_request_complete(). rule: {:?}", self.breaker.rule);
+            return;
+        }
+        let counter = counter.unwrap();
+
+        counter.value().histogram().record(rt);
+        if rt > self.max_allowed_rt {
+            counter.value().target.fetch_add(1, Ordering::SeqCst);
+        }
+        counter.value().total.fetch_add(1, Ordering::SeqCst);
+
+        // handle state changes when threshold exceeded
+        match self.current_state() {
+            State::HalfOpen => {
+                self.on_probe_complete(rt <= self.max_allowed_rt, Arc::new(rt));
+            }
+            State::Closed => {
+                let total_count: u64 = self
+                    .stat
+                    .all_counter()
+                    .iter()
+                    .map(|c| c.value().total.load(Ordering::SeqCst))
+                    .sum();
+                if total_count < self.min_request_amount {
+                    return;
+                }
+                if let Some(percentile_rt) = self.current_percentile_rt() {
+                    if percentile_rt > self.max_allowed_rt {
+                        self.breaker.from_closed_to_open(Arc::new(percentile_rt));
+                    }
+                }
+            }
+            State::Open => {}
+        }
+    }
+}
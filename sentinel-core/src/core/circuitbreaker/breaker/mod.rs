@@ -13,19 +13,29 @@
 //!     |                |                   |                +---------------->|                |
 //!     +----------------+                   +----------------+                 +----------------+
 //!
+//! In HalfOpen state, at most `probe_num` probes are permitted. The circuit breaker turns to Closed
+//! once enough probes succeed to reach `probe_success_ratio`, and turns back to Open
+//! once the ratio can no longer be reached.
+//!
 
 #![allow(clippy::wrong_self_convention)]
 
+/// Consecutive errors
+pub mod consecutive_errors;
 /// Error count
 pub mod error_count;
 /// Error ratio
 pub mod error_ratio;
+/// Percentile of round trip time
+pub mod latency_percentile;
 /// Slow round trip time
 pub mod slow_request;
 pub mod stat;
 
+pub use consecutive_errors::*;
 pub use error_count::*;
 pub use error_ratio::*;
+pub use latency_percentile::*;
 pub use slow_request::*;
 pub use stat::*;
 
@@ -58,6 +68,10 @@ pub enum BreakerStrategy {
     ErrorRatio,
     /// `ErrorCount` strategy changes the circuit breaker state based on error amount
     ErrorCount,
+    /// `ConsecutiveErrors` strategy changes the circuit breaker state based on the number of consecutive errors
+    ConsecutiveErrors,
+    /// `LatencyPercentile` strategy changes the circuit breaker state based on the percentile of round trip time
+    LatencyPercentile,
     #[serde(skip)]
     Custom(u8),
 }
@@ -102,6 +116,16 @@ pub trait StateChangeListener: Sync + Send {
     /// Copying rule has a performance penalty and avoids invalid listeners as much as possible
     fn on_transform_to_half_open(&self, prev: State, rule: Arc<Rule>) {}
 
+    /// `on_retry_timeout_updated` is triggered when circuit breaker transformed to Open,
+    /// `retry_timeout_ms` is the timeout of current open period, which grows with the backoff policy of the rule.
+    /// Argument rule is copy from circuit breaker's rule, any changes of rule don't take effect for circuit breaker
+    fn on_retry_timeout_updated(&self, rule: Arc<Rule>, retry_timeout_ms: u64) {}
+
+    /// `on_state_overridden` is triggered when circuit breaker state is pinned by an override rule, see `OverrideRule`.
+    /// It is triggered after `on_transform_to_open` or `on_transform_to_closed`, if the state is changed by the override.
+    /// Argument rule is copy from circuit breaker's rule, any changes of rule don't take effect for circuit breaker
+    fn on_state_overridden(&self, prev: State, rule: Arc<Rule>, override_rule: Arc<OverrideRule>) {}
+
     /// `on_circuit_breaker_drop` is triggered when circuit breaker is drop.
     /// Argument rule is copy from circuit breaker's rule, any changes of rule don't take effect for circuit breaker
     /// Copying rule has a performance penalty and avoids invalid listeners as much as possible
@@ -126,7 +150,32 @@ pub trait CircuitBreakerTrait: Send + Sync {
             State::Open => {
                 self.breaker().retry_timeout_arrived() && self.breaker().from_open_to_half_open(ctx)
             }
-            State::HalfOpen => false,
+            State::HalfOpen => self.breaker().try_acquire_probe(ctx),
+        }
+    }
+
+    /// `on_probe_complete` records the result of a probe in HalfOpen state,
+    /// and turns the circuit breaker to Closed or Open once the result of probing is determined.
+    fn on_probe_complete(&self, success: bool, snapshot: Arc<Snapshot>) {
+        match self.breaker().record_probe(success) {
+            ProbeResult::Succeeded => {
+                if self.from_half_open_to_closed() {
+                    self.reset_metric();
+                }
+            }
+            ProbeResult::Failed => {
+                self.from_half_open_to_open(snapshot);
+            }
+            ProbeResult::Pending => {}
+        }
+    }
+
+    /// `force_state` pins the circuit breaker to the state of the override rule,
+    /// the statistic is reset if the circuit breaker is forced to Closed.
+    fn force_state(&self, override_rule: Arc<OverrideRule>) {
+        let state = override_rule.state.into();
+        if self.breaker().force_state(state, override_rule) && state == State::Closed {
+            self.reset_metric();
         }
     }
 
@@ -189,6 +238,26 @@ pub trait CircuitBreakerTrait: Send + Sync {
     }
 }
 
+/// `ProbeResult` is the result of probing in HalfOpen state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProbeResult {
+    /// `Succeeded` means enough probes have succeeded, the circuit breaker should be closed.
+    Succeeded,
+    /// `Failed` means the success ratio can no longer be reached, the circuit breaker should be opened.
+    Failed,
+    /// `Pending` means more probes are needed.
+    Pending,
+}
+
+/// `Probes` counts the probes in current HalfOpen period.
+#[derive(Debug, Default)]
+struct Probes {
+    /// the number of the permitted probes
+    permitted: u32,
+    success: u32,
+    failure: u32,
+}
+
 /// BreakerBase encompasses the common fields of circuit breaker.
 #[derive(Debug)]
 pub struct BreakerBase {
@@ -196,15 +265,28 @@ pub struct BreakerBase {
     /// retry_timeout_ms represents recovery timeout (in milliseconds) before the circuit breaker opens.
     /// During the open period, no requests are permitted until the timeout has elapsed.
     /// After that, the circuit breaker will transform to half-open state for trying a few "trial" requests.
-    retry_timeout_ms: u32,
+    /// It grows when the probes fail, if the rule has a backoff policy.
+    retry_timeout_ms: AtomicU64,
     /// next_retry_timestamp_ms is the time circuit breaker could probe
     next_retry_timestamp_ms: AtomicU64,
     /// state is the state machine of circuit breaker
     // todo: test `AtomicPtr`
     state: Arc<Mutex<State>>,
+    probes: Arc<Mutex<Probes>>,
 }
 
 impl BreakerBase {
+    pub fn new(rule: Arc<Rule>) -> Self {
+        let retry_timeout_ms = rule.retry_timeout_ms;
+        BreakerBase {
+            rule,
+            retry_timeout_ms: AtomicU64::new(retry_timeout_ms as u64),
+            next_retry_timestamp_ms: AtomicU64::new(0),
+            state: Arc::new(Mutex::new(State::default())),
+            probes: Arc::new(Mutex::new(Probes::default())),
+        }
+    }
+
     pub fn bound_rule(&self) -> &Arc<Rule> {
         &self.rule
     }
@@ -221,11 +303,42 @@ impl BreakerBase {
         utils::curr_time_millis() >= self.next_retry_timestamp_ms.load(Ordering::SeqCst)
     }
 
-    pub fn update_next_retry_timestamp(&self) {
+    pub fn current_retry_timeout_ms(&self) -> u64 {
+        self.retry_timeout_ms.load(Ordering::SeqCst)
+    }
+
+    /// `update_next_retry_timestamp` starts a new open period, and returns its retry timeout,
+    /// which is randomized by the jitter of the backoff policy.
+    pub fn update_next_retry_timestamp(&self) -> u64 {
+        let mut retry_timeout_ms = self.current_retry_timeout_ms();
+        if let Some(backoff) = &self.rule.backoff {
+            if backoff.jitter > 0.0 {
+                // a random factor in [-1.0, 1.0]
+                let factor = rand::random::<f64>() * 2.0 - 1.0;
+                retry_timeout_ms =
+                    (retry_timeout_ms as f64 * (1.0 + backoff.jitter * factor)).round() as u64;
+            }
+        }
         self.next_retry_timestamp_ms.store(
-            utils::curr_time_millis() + self.retry_timeout_ms as u64,
+            utils::curr_time_millis() + retry_timeout_ms,
             Ordering::SeqCst,
         );
+        retry_timeout_ms
+    }
+
+    /// `backoff_retry_timeout` grows the retry timeout according to the backoff policy of the rule.
+    fn backoff_retry_timeout(&self) {
+        if let Some(backoff) = &self.rule.backoff {
+            let retry_timeout_ms = (self.current_retry_timeout_ms() as f64 * backoff.multiplier)
+                .min(backoff.max_retry_timeout_ms as f64);
+            self.retry_timeout_ms
+                .store(retry_timeout_ms as u64, Ordering::SeqCst);
+        }
+    }
+
+    fn reset_retry_timeout(&self) {
+        self.retry_timeout_ms
+            .store(self.rule.retry_timeout_ms as u64, Ordering::SeqCst);
     }
 
     /// from_closed_to_open updates circuit breaker state machine from closed to open.
@@ -234,7 +347,7 @@ impl BreakerBase {
         let mut state = self.state.lock().unwrap();
         if *state == State::Closed {
             *state = State::Open;
-            self.update_next_retry_timestamp();
+            let retry_timeout_ms = self.update_next_retry_timestamp();
             let listeners = state_change_listeners().lock().unwrap();
             for listener in &*listeners {
                 listener.on_transform_to_open(
@@ -242,6 +355,7 @@ impl BreakerBase {
                     Arc::clone(&self.rule),
                     Some(Arc::clone(&snapshot)),
                 );
+                listener.on_retry_timeout_updated(Arc::clone(&self.rule), retry_timeout_ms);
             }
 
             #[cfg(feature = "exporter")]
@@ -252,12 +366,68 @@ impl BreakerBase {
         }
     }
 
+    /// `try_acquire_probe` permits another probe in HalfOpen state, until there are `probe_num` probes.
+    /// The probe is released if the invocation is blocked by the following slots.
+    pub fn try_acquire_probe(&self, ctx: &EntryContext) -> bool {
+        {
+            let state = self.state.lock().unwrap();
+            if *state != State::HalfOpen {
+                return false;
+            }
+            let mut probes = self.probes.lock().unwrap();
+            if probes.permitted >= self.rule.get_probe_num() {
+                return false;
+            }
+            probes.permitted += 1;
+        }
+        if let Some(entry) = ctx.entry() {
+            let entry = entry.upgrade().unwrap();
+            let probes = Arc::clone(&self.probes);
+            let mut entry = entry.write().unwrap();
+            entry.when_exit(Box::new(
+                move |_entry: &SentinelEntry, ctx: ContextPtr| -> Result<()> {
+                    if ctx.read().unwrap().is_blocked() {
+                        let mut probes = probes.lock().unwrap();
+                        probes.permitted = probes.permitted.saturating_sub(1);
+                    }
+                    Ok(())
+                },
+            ))
+        }
+        true
+    }
+
+    /// `record_probe` records the result of a probe, and tells whether the result of probing is determined.
+    pub fn record_probe(&self, success: bool) -> ProbeResult {
+        let probe_num = self.rule.get_probe_num();
+        let required = ((probe_num as f64 * self.rule.get_probe_success_ratio()).ceil() as u32)
+            .clamp(1, probe_num);
+        let mut probes = self.probes.lock().unwrap();
+        if success {
+            probes.success += 1;
+        } else {
+            probes.failure += 1;
+        }
+        if probes.success >= required {
+            ProbeResult::Succeeded
+        } else if probes.failure > probe_num - required {
+            ProbeResult::Failed
+        } else {
+            ProbeResult::Pending
+        }
+    }
+
     /// from_open_to_half_open updates circuit breaker state machine from open to half-open.
     /// Return true only if current goroutine successfully accomplished the transformation.
     pub fn from_open_to_half_open(&self, ctx: &EntryContext) -> bool {
         let mut state = self.state.lock().unwrap();
         if *state == State::Open {
             *state = State::HalfOpen;
+            // current invocation is the first probe
+            *self.probes.lock().unwrap() = Probes {
+                permitted: 1,
+                ..Default::default()
+            };
             let listeners = state_change_listeners().lock().unwrap();
             for listener in &*listeners {
                 listener.on_transform_to_half_open(State::Open, Arc::clone(&self.rule));
@@ -310,7 +480,8 @@ impl BreakerBase {
         let mut state = self.state.lock().unwrap();
         if *state == State::HalfOpen {
             *state = State::Open;
-            self.update_next_retry_timestamp();
+            self.backoff_retry_timeout();
+            let retry_timeout_ms = self.update_next_retry_timestamp();
             let listeners = state_change_listeners().lock().unwrap();
             for listener in &*listeners {
                 listener.on_transform_to_open(
@@ -318,6 +489,7 @@ impl BreakerBase {
                     Arc::clone(&self.rule),
                     Some(Arc::clone(&snapshot)),
                 );
+                listener.on_retry_timeout_updated(Arc::clone(&self.rule), retry_timeout_ms);
             }
 
             #[cfg(feature = "exporter")]
@@ -334,6 +506,7 @@ impl BreakerBase {
         let mut state = self.state.lock().unwrap();
         if *state == State::HalfOpen {
             *state = State::Closed;
+            self.reset_retry_timeout();
             let listeners = state_change_listeners().lock().unwrap();
             for listener in &*listeners {
                 listener.on_transform_to_closed(State::HalfOpen, Arc::clone(&self.rule));
@@ -346,6 +519,46 @@ impl BreakerBase {
             false
         }
     }
+
+    /// `force_state` updates circuit breaker state machine to the state pinned by the override rule.
+    /// A circuit breaker forced to Open probes as soon as the override is lifted.
+    /// Return true only if the state is changed.
+    pub fn force_state(&self, target: State, override_rule: Arc<OverrideRule>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let prev = *state;
+        *state = target;
+        let changed = prev != target;
+        match target {
+            State::Open => self
+                .next_retry_timestamp_ms
+                .store(utils::curr_time_millis(), Ordering::SeqCst),
+            _ => self.reset_retry_timeout(),
+        }
+        let listeners = state_change_listeners().lock().unwrap();
+        for listener in &*listeners {
+            if changed {
+                match target {
+                    State::Open => listener.on_transform_to_open(
+                        prev,
+                        Arc::clone(&self.rule),
+                        Some(Arc::clone(&override_rule) as Arc<Snapshot>),
+                    ),
+                    _ => listener.on_transform_to_closed(prev, Arc::clone(&self.rule)),
+                }
+            }
+            listener.on_state_overridden(prev, Arc::clone(&self.rule), Arc::clone(&override_rule));
+        }
+
+        #[cfg(feature = "exporter")]
+        if changed {
+            crate::exporter::add_state_change_counter(
+                &self.rule.resource,
+                &format!("{:?}", prev),
+                &format!("{:?}", target),
+            );
+        }
+        changed
+    }
 }
 
 impl Drop for BreakerBase {
@@ -597,6 +810,110 @@ pub(crate) mod test {
         assert_eq!(breaker.current_state(), State::HalfOpen);
     }
 
+    #[test]
+    #[ignore]
+    fn half_open_probes() {
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::ErrorCount,
+            retry_timeout_ms: 3000,
+            min_request_amount: 10,
+            stat_interval_ms: 10000,
+            threshold: 1.0,
+            probe_num: 3,
+            probe_success_ratio: 0.6,
+            ..Default::default()
+        });
+        let breaker = ErrorCountBreaker::new(rule);
+        let sc = Arc::new(SlotChain::new());
+        let mut ctx = EntryContext::new();
+        let res = ResourceWrapper::new("abc".into(), ResourceType::Common, TrafficType::Inbound);
+        ctx.set_resource(res);
+        let ctx = Arc::new(RwLock::new(ctx));
+        let entry = Arc::new(RwLock::new(SentinelEntry::new(
+            Arc::clone(&ctx),
+            Arc::clone(&sc),
+        )));
+        ctx.write().unwrap().set_entry(Arc::downgrade(&entry));
+
+        // 2 of the 3 probes must succeed
+        breaker.set_state(State::Open);
+        for _ in 0..3 {
+            assert!(breaker.try_pass(&ctx.read().unwrap()));
+            assert_eq!(breaker.current_state(), State::HalfOpen);
+        }
+        assert!(!breaker.try_pass(&ctx.read().unwrap()));
+        breaker.on_request_complete(0, &None);
+        breaker.on_request_complete(0, &Some(Error::msg("biz error")));
+        assert_eq!(breaker.current_state(), State::HalfOpen);
+        breaker.on_request_complete(0, &None);
+        assert_eq!(breaker.current_state(), State::Closed);
+
+        // opens as soon as the success ratio can no longer be reached
+        breaker.set_state(State::Open);
+        assert!(breaker.try_pass(&ctx.read().unwrap()));
+        breaker.on_request_complete(0, &Some(Error::msg("biz error")));
+        assert_eq!(breaker.current_state(), State::HalfOpen);
+        breaker.on_request_complete(0, &Some(Error::msg("biz error")));
+        assert_eq!(breaker.current_state(), State::Open);
+    }
+
+    #[test]
+    #[ignore]
+    fn backoff_retry_timeout() {
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::ErrorCount,
+            retry_timeout_ms: 1000,
+            min_request_amount: 1,
+            stat_interval_ms: 10000,
+            threshold: 1.0,
+            backoff: Some(BackoffPolicy {
+                multiplier: 2.0,
+                max_retry_timeout_ms: 3000,
+                jitter: 0.0,
+            }),
+            ..Default::default()
+        });
+        let breaker = ErrorCountBreaker::new(rule);
+        let err = Some(Error::msg("biz error"));
+        breaker.on_request_complete(0, &err);
+        assert_eq!(breaker.current_state(), State::Open);
+        assert_eq!(breaker.breaker().current_retry_timeout_ms(), 1000);
+        for expected in [2000, 3000, 3000] {
+            breaker.set_state(State::HalfOpen);
+            breaker.on_request_complete(0, &err);
+            assert_eq!(breaker.current_state(), State::Open);
+            assert_eq!(breaker.breaker().current_retry_timeout_ms(), expected);
+            let next_retry = breaker.next_retry_timestamp_ms() - utils::curr_time_millis();
+            assert!(next_retry > expected - 100 && next_retry <= expected);
+        }
+        // reset when closed
+        breaker.set_state(State::HalfOpen);
+        breaker.on_request_complete(0, &None);
+        assert_eq!(breaker.current_state(), State::Closed);
+        assert_eq!(breaker.breaker().current_retry_timeout_ms(), 1000);
+    }
+
+    #[test]
+    fn backoff_jitter() {
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            retry_timeout_ms: 1000,
+            backoff: Some(BackoffPolicy {
+                multiplier: 2.0,
+                max_retry_timeout_ms: 3000,
+                jitter: 0.5,
+            }),
+            ..Default::default()
+        });
+        let breaker = BreakerBase::new(rule);
+        for _ in 0..100 {
+            let retry_timeout_ms = breaker.update_next_retry_timestamp();
+            assert!((500..=1500).contains(&retry_timeout_ms));
+        }
+    }
+
     #[test]
     #[ignore]
     fn error_ratio_on_request_complete() {
@@ -701,6 +1018,82 @@ pub(crate) mod test {
         assert_eq!(breaker.current_state(), State::Closed);
     }
 
+    #[test]
+    #[ignore]
+    fn consecutive_errors_on_request_complete() {
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::ConsecutiveErrors,
+            retry_timeout_ms: 3000,
+            threshold: 3.0,
+            ..Default::default()
+        });
+        let breaker = ConsecutiveErrorsBreaker::new(rule);
+        let err = Some(Error::msg("biz error"));
+
+        // a successful invocation breaks the streak
+        breaker.on_request_complete(0, &err);
+        breaker.on_request_complete(0, &err);
+        breaker.on_request_complete(0, &None);
+        assert_eq!(breaker.consecutive_errors(), 0);
+        breaker.on_request_complete(0, &err);
+        breaker.on_request_complete(0, &err);
+        assert_eq!(breaker.current_state(), State::Closed);
+        breaker.on_request_complete(0, &err);
+        assert_eq!(breaker.current_state(), State::Open);
+
+        // probe fails
+        breaker.set_state(State::HalfOpen);
+        breaker.on_request_complete(0, &err);
+        assert_eq!(breaker.current_state(), State::Open);
+
+        // probe succeeds
+        breaker.set_state(State::HalfOpen);
+        breaker.on_request_complete(0, &None);
+        assert_eq!(breaker.current_state(), State::Closed);
+        assert_eq!(breaker.consecutive_errors(), 0);
+    }
+
+    #[test]
+    #[ignore]
+    fn latency_percentile_on_request_complete() {
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::LatencyPercentile,
+            retry_timeout_ms: 3000,
+            min_request_amount: 100,
+            stat_interval_ms: 10000,
+            max_allowed_rt_ms: 50,
+            threshold: 0.95,
+            ..Default::default()
+        });
+        let breaker = LatencyPercentileBreaker::new(rule);
+
+        // the p95 is below max_allowed_rt_ms though 4% of the requests are slow
+        for i in 0..100 {
+            breaker.on_request_complete(if i % 25 == 0 { 200 } else { 10 }, &None);
+        }
+        assert_eq!(breaker.current_percentile_rt(), Some(10));
+        assert_eq!(breaker.current_state(), State::Closed);
+        // the tail latency grows
+        for _ in 0..2 {
+            breaker.on_request_complete(200, &None);
+        }
+        assert!(breaker.current_percentile_rt().unwrap() >= 200);
+        assert_eq!(breaker.current_state(), State::Open);
+
+        // probe fails
+        breaker.set_state(State::HalfOpen);
+        breaker.on_request_complete(100, &None);
+        assert_eq!(breaker.current_state(), State::Open);
+
+        // probe succeeds
+        breaker.set_state(State::HalfOpen);
+        breaker.on_request_complete(10, &None);
+        assert_eq!(breaker.current_state(), State::Closed);
+        assert_eq!(breaker.current_percentile_rt(), None);
+    }
+
     #[test]
     #[ignore]
     fn error_count_closed_to_open() {
@@ -1,9 +1,6 @@
 use super::*;
 use crate::logging;
-use std::sync::{
-    atomic::{AtomicU64, Ordering},
-    Arc, Mutex,
-};
+use std::sync::{atomic::Ordering, Arc};
 
 #[derive(Debug)]
 pub struct SlowRtBreaker {
@@ -23,17 +20,11 @@ impl SlowRtBreaker {
     }
 
     pub fn new_with_stat(rule: Arc<Rule>, stat: Arc<CounterLeapArray>) -> Self {
-        let retry_timeout_ms = rule.retry_timeout_ms;
         let max_allowed_rt = rule.max_allowed_rt_ms;
         let max_slow_request_ratio = rule.threshold;
         let min_request_amount = rule.min_request_amount;
         Self {
-            breaker: BreakerBase {
-                rule,
-                retry_timeout_ms,
-                next_retry_timestamp_ms: AtomicU64::new(0),
-                state: Arc::new(Mutex::new(State::default())),
-            },
+            breaker: BreakerBase::new(rule),
             max_allowed_rt,
             max_slow_request_ratio,
             min_request_amount,
@@ -79,14 +70,7 @@ impl CircuitBreakerTrait for SlowRtBreaker {
         // handle state changes when threshold exceeded
         match self.current_state() {
             State::HalfOpen => {
-                if rt > self.max_allowed_rt {
-                    // fail to probe
-                    self.breaker.from_half_open_to_open(Arc::new(1.0));
-                } else {
-                    // succeed to probe
-                    self.breaker.from_half_open_to_closed();
-                    self.reset_metric();
-                }
+                self.on_probe_complete(rt <= self.max_allowed_rt, Arc::new(1.0));
             }
             State::Closed => {
                 if total_count >= self.min_request_amount
@@ -4,19 +4,123 @@ use crate::{
 };
 use std::sync::{
     atomic::{AtomicU64, Ordering},
-    Arc,
+    Arc, OnceLock,
 };
 
+/// the round trip times below it are recorded exactly in the histogram
+const RT_HISTOGRAM_LINEAR_BOUND: u64 = 16;
+/// each power of two above `RT_HISTOGRAM_LINEAR_BOUND` is divided into `2^RT_HISTOGRAM_SUB_BUCKET_BITS` buckets
+const RT_HISTOGRAM_SUB_BUCKET_BITS: u32 = 3;
+/// the round trip times not less than `2^(RT_HISTOGRAM_MAX_EXPONENT + 1)` ms are recorded in the last bucket
+const RT_HISTOGRAM_MAX_EXPONENT: u32 = 20;
+const RT_HISTOGRAM_MIN_EXPONENT: u32 = RT_HISTOGRAM_LINEAR_BOUND.trailing_zeros();
+const RT_HISTOGRAM_SIZE: usize = RT_HISTOGRAM_LINEAR_BOUND as usize
+    + ((RT_HISTOGRAM_MAX_EXPONENT - RT_HISTOGRAM_MIN_EXPONENT + 1) << RT_HISTOGRAM_SUB_BUCKET_BITS)
+        as usize;
+
+/// `RtHistogram` is a log-linear histogram of the round trip times (in ms) with bounded memory.
+/// The round trip times below 16 ms are recorded exactly, and the larger ones are recorded
+/// with a relative error of at most 12.5%.
+#[derive(Debug)]
+pub struct RtHistogram {
+    counts: [AtomicU64; RT_HISTOGRAM_SIZE],
+}
+
+impl Default for RtHistogram {
+    fn default() -> Self {
+        RtHistogram {
+            counts: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+impl RtHistogram {
+    fn index_of(rt: u64) -> usize {
+        if rt < RT_HISTOGRAM_LINEAR_BOUND {
+            return rt as usize;
+        }
+        let exponent = 63 - rt.leading_zeros();
+        if exponent > RT_HISTOGRAM_MAX_EXPONENT {
+            return RT_HISTOGRAM_SIZE - 1;
+        }
+        let sub_bucket = (rt >> (exponent - RT_HISTOGRAM_SUB_BUCKET_BITS))
+            & ((1 << RT_HISTOGRAM_SUB_BUCKET_BITS) - 1);
+        RT_HISTOGRAM_LINEAR_BOUND as usize
+            + (((exponent - RT_HISTOGRAM_MIN_EXPONENT) << RT_HISTOGRAM_SUB_BUCKET_BITS) as usize)
+            + sub_bucket as usize
+    }
+
+    /// `upper_bound_of` returns the max round trip time recorded in the bucket of `index`.
+    fn upper_bound_of(index: usize) -> u64 {
+        if index < RT_HISTOGRAM_LINEAR_BOUND as usize {
+            return index as u64;
+        }
+        let offset = (index - RT_HISTOGRAM_LINEAR_BOUND as usize) as u32;
+        let exponent = (offset >> RT_HISTOGRAM_SUB_BUCKET_BITS) + RT_HISTOGRAM_MIN_EXPONENT;
+        let sub_bucket = (offset & ((1 << RT_HISTOGRAM_SUB_BUCKET_BITS) - 1)) as u64;
+        let width = 1u64 << (exponent - RT_HISTOGRAM_SUB_BUCKET_BITS);
+        (1u64 << exponent) + (sub_bucket + 1) * width - 1
+    }
+
+    pub fn record(&self, rt: u64) {
+        self.counts[Self::index_of(rt)].fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn reset(&self) {
+        for count in &self.counts {
+            count.store(0, Ordering::SeqCst);
+        }
+    }
+
+    /// `percentile_of` merges the histograms, and returns the round trip time at the `percentile` (in (0.0, 1.0]),
+    /// which is rounded up to the upper bound of its bucket. It returns `None` if nothing is recorded.
+    pub fn percentile_of<'a>(
+        histograms: impl IntoIterator<Item = &'a RtHistogram>,
+        percentile: f64,
+    ) -> Option<u64> {
+        let mut merged = [0u64; RT_HISTOGRAM_SIZE];
+        for histogram in histograms {
+            for (sum, count) in merged.iter_mut().zip(&histogram.counts) {
+                *sum += count.load(Ordering::SeqCst);
+            }
+        }
+        let total: u64 = merged.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((total as f64 * percentile).ceil() as u64).clamp(1, total);
+        let mut accumulated = 0;
+        for (index, count) in merged.iter().enumerate() {
+            accumulated += count;
+            if accumulated >= rank {
+                return Some(Self::upper_bound_of(index));
+            }
+        }
+        None
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Counter {
     pub(crate) target: AtomicU64,
     pub(crate) total: AtomicU64,
+    /// the histogram is only allocated by the strategies on round trip time percentiles
+    pub(crate) histogram: OnceLock<RtHistogram>,
+}
+
+impl Counter {
+    pub fn histogram(&self) -> &RtHistogram {
+        self.histogram.get_or_init(RtHistogram::default)
+    }
 }
 
 impl MetricTrait for Counter {
     fn reset(&self) {
         self.target.store(0, Ordering::SeqCst);
         self.total.store(0, Ordering::SeqCst);
+        if let Some(histogram) = self.histogram.get() {
+            histogram.reset();
+        }
     }
 }
 
@@ -45,9 +149,45 @@ mod test {
         let counter = Counter {
             target: AtomicU64::new(5),
             total: AtomicU64::new(10),
+            ..Default::default()
         };
+        counter.histogram().record(5);
         counter.reset();
         assert_eq!(counter.target.load(Ordering::SeqCst), 0);
         assert_eq!(counter.total.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            RtHistogram::percentile_of(counter.histogram.get(), 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn histogram_buckets() {
+        for rt in [0, 15, 16, 17, 100, 1000, 123456, (1 << 21) - 1] {
+            let index = RtHistogram::index_of(rt);
+            let upper = RtHistogram::upper_bound_of(index);
+            assert!(upper >= rt && (upper - rt) as f64 <= rt as f64 / 8.0);
+            if index > 0 {
+                assert!(RtHistogram::upper_bound_of(index - 1) < rt);
+            }
+        }
+        assert_eq!(RtHistogram::index_of(u64::MAX), RT_HISTOGRAM_SIZE - 1);
+    }
+
+    #[test]
+    fn histogram_percentile() {
+        let h1 = RtHistogram::default();
+        let h2 = RtHistogram::default();
+        for rt in 1..=90 {
+            h1.record(rt % 10);
+        }
+        for _ in 0..10 {
+            h2.record(1000);
+        }
+        assert_eq!(RtHistogram::percentile_of([&h1, &h2], 0.5), Some(5));
+        assert_eq!(RtHistogram::percentile_of([&h1, &h2], 0.9), Some(9));
+        let p99 = RtHistogram::percentile_of([&h1, &h2], 0.99).unwrap();
+        assert!((1000..1125).contains(&p99));
+        assert_eq!(RtHistogram::percentile_of([&h1], 1.0), Some(9));
     }
 }
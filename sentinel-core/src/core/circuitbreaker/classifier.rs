@@ -0,0 +1,332 @@
+//! Error classification for the circuit breakers.
+//!
+//! By default, all the errors recorded by `trace_error()` or `EntryContext::set_err()` are counted by the circuit breakers.
+//! A rule can ignore some errors, such as business errors, in two ways:
+//!
+//!  1. Register an `ErrorClassifier` by the rule id or the resource name, with `register_error_classifier()`.
+//!  2. Declare `ignore_errors` or `record_errors` in the rule.
+//!
+//! The classifier registered by the rule id takes precedence over the one registered by the resource name,
+//! and the declarative lists only take effect when there is no classifier for the rule.
+//!
+//! The items in the declarative lists match the messages of the errors, or the names of the error types
+//! registered by `register_error_type()`. The common error types of `std`, e.g., `std::io::Error`, are registered by default.
+//!
+//! The classifiers and the error types are kept by the `RuleManager` of each Sentinel instance,
+//! the free functions here work on the default instance.
+
+use super::{default_rule_manager, Rule};
+use crate::api::TracedError;
+use crate::Error;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::sync::{Arc, RwLock};
+
+/// `ErrorClassifier` tells whether the error should be recorded by the circuit breaker.
+pub type ErrorClassifier = dyn Fn(&Error) -> bool + Send + Sync;
+
+/// `ErrorType` is a type of errors which can be matched by the name in the declarative lists.
+struct ErrorType {
+    name: String,
+    is_type: fn(&(dyn StdError + 'static)) -> bool,
+}
+
+fn is_type<T: StdError + 'static>(err: &(dyn StdError + 'static)) -> bool {
+    err.is::<T>()
+}
+
+macro_rules! error_type {
+    ($name:literal, $type:ty) => {
+        ErrorType {
+            name: $name.into(),
+            is_type: is_type::<$type>,
+        }
+    };
+}
+
+/// `ErrorClassification` keeps the error classifiers and the registered error types of a circuit breaker `RuleManager`.
+pub struct ErrorClassification {
+    classifiers: RwLock<HashMap<String, Arc<ErrorClassifier>>>,
+    error_types: RwLock<Vec<ErrorType>>,
+}
+
+impl Default for ErrorClassification {
+    fn default() -> Self {
+        ErrorClassification {
+            classifiers: RwLock::new(HashMap::new()),
+            // the paths of `std::any::type_name()` are private for the std types, so they are named by their public paths
+            error_types: RwLock::new(vec![
+                error_type!("std::io::Error", std::io::Error),
+                error_type!("std::fmt::Error", std::fmt::Error),
+                error_type!("std::num::ParseIntError", std::num::ParseIntError),
+                error_type!("std::num::ParseFloatError", std::num::ParseFloatError),
+                error_type!("std::str::ParseBoolError", std::str::ParseBoolError),
+                error_type!("std::str::Utf8Error", std::str::Utf8Error),
+                error_type!("std::string::FromUtf8Error", std::string::FromUtf8Error),
+            ]),
+        }
+    }
+}
+
+impl ErrorClassification {
+    /// `register_classifier` registers the classifier for the rules with id `key`,
+    /// or for the rules of the resource `key`. The existing classifier of the `key` is replaced.
+    pub fn register_classifier(&self, key: String, classifier: Arc<ErrorClassifier>) {
+        self.classifiers.write().unwrap().insert(key, classifier);
+    }
+
+    pub fn remove_classifier(&self, key: &str) {
+        self.classifiers.write().unwrap().remove(key);
+    }
+
+    pub fn clear_classifiers(&self) {
+        self.classifiers.write().unwrap().clear();
+    }
+
+    /// `register_error_type` makes the errors of type `T` matched by its name, see `register_error_type()`.
+    pub fn register_error_type<T: StdError + 'static>(&self) {
+        let name = std::any::type_name::<T>();
+        let mut error_types = self.error_types.write().unwrap();
+        if error_types.iter().all(|error_type| error_type.name != name) {
+            error_types.push(ErrorType {
+                name: name.into(),
+                is_type: is_type::<T>,
+            });
+        }
+    }
+
+    /// `error_matches` checks whether the error matches the pattern, see `error_matches()`.
+    pub fn error_matches(&self, err: &Error, pattern: &str) -> bool {
+        self.matches(&ErrorCauses::new(err), pattern)
+    }
+
+    fn matches(&self, causes: &ErrorCauses, pattern: &str) -> bool {
+        if causes.messages.iter().any(|msg| msg.contains(pattern)) {
+            return true;
+        }
+        if causes
+            .err
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<TracedError>())
+            .any(|traced| name_matches(traced.type_name(), pattern))
+        {
+            return true;
+        }
+        self.error_types
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|error_type| name_matches(&error_type.name, pattern))
+            .any(|error_type| causes.err.chain().any(error_type.is_type))
+    }
+
+    /// `is_recorded_error` checks whether the error should be recorded by the circuit breaker of the rule.
+    pub fn is_recorded_error(&self, rule: &Rule, err: &Error) -> bool {
+        let classifier = {
+            let classifiers = self.classifiers.read().unwrap();
+            classifiers
+                .get(&rule.id)
+                .or_else(|| classifiers.get(&rule.resource))
+                .cloned()
+        };
+        if let Some(classifier) = classifier {
+            return classifier(err);
+        }
+        if rule.ignore_errors.is_empty() && rule.record_errors.is_empty() {
+            return true;
+        }
+        // the causes are formatted once for all the patterns
+        let causes = ErrorCauses::new(err);
+        if rule
+            .ignore_errors
+            .iter()
+            .any(|pattern| self.matches(&causes, pattern))
+        {
+            return false;
+        }
+        rule.record_errors.is_empty()
+            || rule
+                .record_errors
+                .iter()
+                .any(|pattern| self.matches(&causes, pattern))
+    }
+}
+
+/// `ErrorCauses` is the error along with the messages of its causes
+struct ErrorCauses<'a> {
+    err: &'a Error,
+    messages: Vec<String>,
+}
+
+impl<'a> ErrorCauses<'a> {
+    fn new(err: &'a Error) -> Self {
+        ErrorCauses {
+            err,
+            messages: err.chain().map(|cause| cause.to_string()).collect(),
+        }
+    }
+}
+
+/// `register_error_classifier` registers the classifier for the rules with id `key`,
+/// or for the rules of the resource `key` on the default Sentinel instance. The existing classifier of the `key` is replaced.
+pub fn register_error_classifier<S: Into<String>>(key: S, classifier: Arc<ErrorClassifier>) {
+    default_rule_manager().register_error_classifier(key, classifier)
+}
+
+pub fn remove_error_classifier(key: &str) {
+    default_rule_manager().remove_error_classifier(key)
+}
+
+pub fn clear_error_classifiers() {
+    default_rule_manager().clear_error_classifiers()
+}
+
+/// `register_error_type` makes the errors of type `T` matched by its name in `ignore_errors` and `record_errors`,
+/// i.e., `std::any::type_name::<T>()`, or its suffix at a `::` boundary, e.g., `BizError` for `my_crate::error::BizError`.
+pub fn register_error_type<T: StdError + 'static>() {
+    default_rule_manager().register_error_type::<T>()
+}
+
+/// `name_matches` checks whether the pattern is the type name, or its suffix at a `::` boundary
+fn name_matches(name: &str, pattern: &str) -> bool {
+    name.strip_suffix(pattern)
+        .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with("::"))
+}
+
+/// `error_matches` checks whether the error matches the pattern, i.e., the message of the error (or any of its causes)
+/// contains the pattern, or the error (or any of its causes) is of the registered type named by the pattern,
+/// see `register_error_type()`. The errors traced by `run()` are matched by the type names of the original errors,
+/// see `TracedError`.
+pub fn error_matches(err: &Error, pattern: &str) -> bool {
+    default_rule_manager().error_matches(err, pattern)
+}
+
+/// `is_recorded_error` checks whether the error should be recorded by the circuit breaker of the rule.
+pub fn is_recorded_error(rule: &Rule, err: &Error) -> bool {
+    default_rule_manager().is_recorded_error(rule, err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn declarative() {
+        let classification = ErrorClassification::default();
+        let mut rule = Rule {
+            id: "declarative".into(),
+            resource: "declarative".into(),
+            ..Default::default()
+        };
+        let not_found = Error::msg("404 not found");
+        let timeout = Error::new(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "connection timed out",
+        ));
+        let parse_err = Error::new("abc".parse::<u32>().unwrap_err());
+        assert!(classification.is_recorded_error(&rule, &not_found));
+
+        rule.ignore_errors = vec!["404".into()];
+        assert!(!classification.is_recorded_error(&rule, &not_found));
+        assert!(classification.is_recorded_error(&rule, &timeout));
+
+        rule.record_errors = vec!["timed out".into(), "ParseIntError".into()];
+        assert!(classification.is_recorded_error(&rule, &timeout));
+        assert!(classification.is_recorded_error(&rule, &parse_err));
+        assert!(!classification.is_recorded_error(&rule, &Error::msg("500 internal error")));
+        // ignore_errors takes precedence
+        rule.ignore_errors.push("ParseIntError".into());
+        assert!(!classification.is_recorded_error(&rule, &parse_err));
+    }
+
+    #[derive(Debug)]
+    enum BizError {
+        NotFound,
+        Forbidden,
+    }
+
+    impl std::fmt::Display for BizError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                BizError::NotFound => write!(f, "not found"),
+                BizError::Forbidden => write!(f, "forbidden"),
+            }
+        }
+    }
+
+    impl StdError for BizError {}
+
+    #[test]
+    fn error_types() {
+        let classification = ErrorClassification::default();
+        let io_err = Error::new(std::io::Error::other("connection reset"))
+            .context("failed to call the dependency");
+        assert!(classification.error_matches(&io_err, "std::io::Error"));
+        assert!(classification.error_matches(&io_err, "io::Error"));
+        assert!(!classification.error_matches(&io_err, "Custom"));
+        assert!(!classification.error_matches(&io_err, "ParseIntError"));
+        let os_err = Error::new(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(classification.error_matches(&os_err, "io::Error"));
+        assert!(!classification.error_matches(&os_err, "Simple"));
+
+        let not_found = Error::new(BizError::NotFound);
+        let forbidden = Error::new(BizError::Forbidden);
+        // the variants are not the type names
+        assert!(!classification.error_matches(&not_found, "NotFound"));
+        assert!(!classification.error_matches(&not_found, "BizError"));
+        classification.register_error_type::<BizError>();
+        assert!(classification.error_matches(&not_found, "BizError"));
+        assert!(classification.error_matches(&forbidden, "test::BizError"));
+        assert!(!classification.error_matches(&forbidden, "izError"));
+        assert!(!classification.error_matches(&io_err, "BizError"));
+    }
+
+    #[test]
+    fn traced_errors() {
+        let classification = ErrorClassification::default();
+        // the traced errors are matched by the type names of the original errors, even if not registered
+        let traced = Error::new(TracedError::new(&BizError::Forbidden));
+        assert!(classification.error_matches(&traced, "forbidden"));
+        assert!(classification.error_matches(&traced, "BizError"));
+        assert!(classification.error_matches(&traced, "test::BizError"));
+        assert!(!classification.error_matches(&traced, "izError"));
+        assert!(!classification.error_matches(&traced, "TracedError"));
+    }
+
+    #[test]
+    fn context() {
+        let classification = ErrorClassification::default();
+        let err = Error::msg("connection refused").context("failed to call the dependency");
+        assert!(classification.error_matches(&err, "refused"));
+        assert!(classification.error_matches(&err, "dependency"));
+        assert!(!classification.error_matches(&err, "timeout"));
+    }
+
+    #[test]
+    fn classifier() {
+        let classification = ErrorClassification::default();
+        let rule = Rule {
+            id: "classifier".into(),
+            resource: "classifier_res".into(),
+            ignore_errors: vec!["biz".into()],
+            ..Default::default()
+        };
+        let err = Error::msg("biz error");
+        assert!(!classification.is_recorded_error(&rule, &err));
+
+        classification.register_classifier("classifier_res".into(), Arc::new(|_: &Error| true));
+        assert!(classification.is_recorded_error(&rule, &err));
+        // the classifier of the rule id takes precedence
+        classification.register_classifier(
+            "classifier".into(),
+            Arc::new(|err: &Error| !err.to_string().starts_with("biz")),
+        );
+        assert!(!classification.is_recorded_error(&rule, &err));
+        assert!(classification.is_recorded_error(&rule, &Error::msg("timeout")));
+
+        classification.remove_classifier("classifier");
+        assert!(classification.is_recorded_error(&rule, &err));
+        classification.clear_classifiers();
+        assert!(!classification.is_recorded_error(&rule, &err));
+    }
+}
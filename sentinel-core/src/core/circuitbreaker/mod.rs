@@ -1,7 +1,7 @@
 //! `circuitbreaker` module implements the circuit breaker pattern, which provides
 //! stability and prevents cascading failures in distributed systems.
 //!
-//! Sentinel circuit breaker module supports three strategies:
+//! Sentinel circuit breaker module supports five strategies:
 //!
 //!  1. SlowRequestRatio: the ratio of slow response time entry(entry's response time is great than max slow response time) exceeds the threshold. The following entry to resource will be broken.
 //!                       In SlowRequestRatio strategy, user must set max response time.
@@ -10,6 +10,12 @@
 //!  
 //!  3. ErrorCount: the number of error entry exceeds the threshold. The following entry to resource will be broken.
 //!
+//!  4. ConsecutiveErrors: the number of consecutive error entries reaches the threshold, regardless of the statistic interval.
+//!                        The following entry to resource will be broken.
+//!
+//!  5. LatencyPercentile: the percentile (e.g., p99) of response time in the statistic interval exceeds the max response time.
+//!                        The following entry to resource will be broken. In LatencyPercentile strategy, user must set max response time.
+//!
 //! Sentinel converts each circuit breaking Rule into a CircuitBreaker. Each CircuitBreaker has its own statistical structure.
 //!
 //! Sentinel circuit breaker is implemented based on state machines. There are three states:
@@ -21,15 +27,24 @@
 //!  3. Half-Open: the circuit breaker is in a temporary state of probing, only one entry is allowed to access resource, others are blocked.
 //!
 //! Sentinel circuit breaker provides the listeners with trait `StateChangeListener` to observe events of state changes.
+//!
+//! The errors counted by the circuit breakers can be filtered by an `ErrorClassifier`, or by the `ignore_errors`
+//! and `record_errors` of the rule, see the `classifier` mod.
+//!
+//! The circuit breakers of a resource can be pinned to Open or Closed manually by an `OverrideRule`, see the `state_override` mod.
 
 pub mod breaker;
+pub mod classifier;
 pub mod rule;
 pub mod rule_manager;
 pub mod slot;
 pub mod stat_slot;
+pub mod state_override;
 
 pub use breaker::*;
+pub use classifier::*;
 pub use rule::*;
 pub use rule_manager::*;
 pub use slot::*;
 pub use stat_slot::*;
+pub use state_override::*;
@@ -1,5 +1,8 @@
 use super::*;
-use crate::{base::SentinelRule, logging, Error};
+use crate::{
+    base::{MatchStrategy, PatternRule, RuleMode, SentinelRule},
+    logging, Error,
+};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fmt;
@@ -9,6 +12,22 @@ cfg_k8s! {
     use kube::CustomResource;
 }
 
+/// `BackoffPolicy` grows the retry timeout of the circuit breaker exponentially,
+/// each time a probe fails in HalfOpen state. The retry timeout is reset to `retry_timeout_ms`
+/// when the circuit breaker turns to Closed.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackoffPolicy {
+    /// `multiplier` is the factor the retry timeout grows by, it must be no less than 1.0.
+    pub multiplier: f64,
+    /// `max_retry_timeout_ms` is the upper bound of the retry timeout (in ms).
+    pub max_retry_timeout_ms: u32,
+    /// `jitter` randomizes each retry timeout within `[timeout * (1 - jitter), timeout * (1 + jitter)]`,
+    /// so that the instances do not probe the recovering dependency at the same time, valid range: [0.0, 1.0].
+    pub jitter: f64,
+}
+
 /// Rule encompasses the fields of circuit breaking rule.
 #[cfg_attr(
     feature = "ds_k8s",
@@ -27,6 +46,9 @@ pub struct Rule {
     pub id: String,
     /// resource name
     pub resource: String,
+    /// `match_strategy` indicates how `resource` matches the resource names,
+    /// a glob or regex pattern makes the rule cover a family of resources.
+    pub match_strategy: MatchStrategy,
     pub strategy: BreakerStrategy,
     /// `retry_timeout_ms` represents recovery timeout (in milliseconds) before the circuit breaker opens.
     /// During the open period, no requests are permitted until the timeout has elapsed.
@@ -37,6 +59,7 @@ pub struct Rule {
     pub min_request_amount: u64,
     /// stat_interval_ms represents statistic time interval of the internal circuit breaker (in ms).
     /// Currently the statistic interval is collected by sliding window.
+    /// It is optional for the `ConsecutiveErrors` strategy, which does not depend on the statistic interval.
     pub stat_interval_ms: u32,
     /// `stat_sliding_window_bucket_count` represents the bucket count of statistic sliding window.
     /// The statistic will be more precise as the bucket count increases, but the memory cost increases too.
@@ -46,13 +69,36 @@ pub struct Rule {
     pub stat_sliding_window_bucket_count: u32,
     /// `max_allowed_rt_ms` indicates that any invocation whose response time exceeds this value (in ms)
     /// will be recorded as a slow request.
-    /// `max_allowed_rt_ms` only takes effect for `SlowRequestRatio` strategy
+    /// `max_allowed_rt_ms` only takes effect for `SlowRequestRatio` and `LatencyPercentile` strategies
     pub max_allowed_rt_ms: u64,
     /// `threshold` represents the threshold of circuit breaker.
     /// for `SlowRequestRatio`, it represents the max slow request ratio
     /// for `ErrorRatio`, it represents the max error request ratio
     /// for `ErrorCount`, it represents the max error request count
+    /// for `ConsecutiveErrors`, it represents the number of consecutive errors to open the circuit breaker
+    /// for `LatencyPercentile`, it represents the percentile of response time, e.g., 0.99 for p99,
+    /// the circuit breaker opens when the percentile exceeds `max_allowed_rt_ms`
     pub threshold: f64,
+    /// `backoff` indicates how the retry timeout grows when the probes fail,
+    /// the retry timeout is always `retry_timeout_ms` if it is `None`.
+    pub backoff: Option<BackoffPolicy>,
+    /// `probe_num` is the max number of probes permitted in HalfOpen state.
+    /// If it is not set, default value 1 will be used.
+    pub probe_num: u32,
+    /// `probe_success_ratio` is the ratio of `probe_num` probes that must succeed
+    /// before the circuit breaker turns to Closed, valid range: [0.0, 1.0].
+    /// The circuit breaker turns back to Open as soon as the ratio can no longer be reached.
+    /// If it is not set, all the probes must succeed.
+    pub probe_success_ratio: f64,
+    /// `ignore_errors` lists the errors not counted by the `ErrorRatio`, `ErrorCount` and `ConsecutiveErrors` strategies,
+    /// an error is matched if its message contains an item, or its type is named by an item, see `register_error_type()`.
+    pub ignore_errors: Vec<String>,
+    /// `record_errors` lists the errors counted by the `ErrorRatio`, `ErrorCount` and `ConsecutiveErrors` strategies,
+    /// all the errors not ignored are counted if it is empty.
+    pub record_errors: Vec<String>,
+    /// `mode` indicates whether the invocations are blocked when the circuit breaker is open,
+    /// or only recorded in shadow (dry-run) mode.
+    pub mode: RuleMode,
 }
 
 impl Default for Rule {
@@ -63,6 +109,7 @@ impl Default for Rule {
             #[cfg(not(target_arch = "wasm32"))]
             id: uuid::Uuid::new_v4().to_string(),
             resource: String::default(),
+            match_strategy: MatchStrategy::default(),
             strategy: BreakerStrategy::default(),
             retry_timeout_ms: 0,
             min_request_amount: 0,
@@ -70,6 +117,12 @@ impl Default for Rule {
             stat_sliding_window_bucket_count: 0,
             max_allowed_rt_ms: 0,
             threshold: 0.0,
+            backoff: None,
+            probe_num: 0,
+            probe_success_ratio: 0.0,
+            ignore_errors: Vec::new(),
+            record_errors: Vec::new(),
+            mode: RuleMode::default(),
         }
     }
 }
@@ -89,6 +142,28 @@ impl Rule {
         }
         bucket_count
     }
+
+    pub fn get_probe_num(&self) -> u32 {
+        self.probe_num.max(1)
+    }
+
+    pub fn get_probe_success_ratio(&self) -> f64 {
+        if self.probe_success_ratio == 0.0 {
+            1.0
+        } else {
+            self.probe_success_ratio
+        }
+    }
+}
+
+impl PatternRule for Rule {
+    fn match_strategy(&self) -> MatchStrategy {
+        self.match_strategy
+    }
+
+    fn resource(&self) -> &str {
+        &self.resource
+    }
 }
 
 impl SentinelRule for Rule {
@@ -100,7 +175,10 @@ impl SentinelRule for Rule {
         if self.resource.is_empty() {
             return Err(Error::msg("empty resource name"));
         }
-        if self.stat_interval_ms == 0 {
+        if self.match_strategy.is_pattern() {
+            self.match_strategy.compile(&self.resource)?;
+        }
+        if self.stat_interval_ms == 0 && self.strategy != BreakerStrategy::ConsecutiveErrors {
             return Err(Error::msg("invalid stat_interval_ms"));
         }
         if self.retry_timeout_ms == 0 {
@@ -109,12 +187,46 @@ impl SentinelRule for Rule {
         if self.threshold < 0.0 {
             return Err(Error::msg("invalid threshold"));
         }
-        if self.strategy != BreakerStrategy::ErrorCount && self.threshold > 1.0 {
+        if self.strategy == BreakerStrategy::ConsecutiveErrors && self.threshold < 1.0 {
+            return Err(Error::msg(
+                "invalid ConsecutiveErrors threshold (should be >= 1)",
+            ));
+        }
+        if self.strategy == BreakerStrategy::LatencyPercentile && self.threshold == 0.0 {
+            return Err(Error::msg(
+                "invalid LatencyPercentile percentile threshold (valid range: (0.0, 1.0])",
+            ));
+        }
+        if !matches!(
+            self.strategy,
+            BreakerStrategy::ErrorCount | BreakerStrategy::ConsecutiveErrors
+        ) && self.threshold > 1.0
+        {
             return Err(Error::msg(format!(
                 "invalid {:?} ratio threshold (valid range: [0.0, 1.0])",
                 self.strategy
             )));
         }
+        if let Some(backoff) = &self.backoff {
+            if backoff.multiplier < 1.0 {
+                return Err(Error::msg("invalid backoff multiplier (should be >= 1.0)"));
+            }
+            if backoff.max_retry_timeout_ms < self.retry_timeout_ms {
+                return Err(Error::msg(
+                    "invalid backoff max_retry_timeout_ms (should be >= retry_timeout_ms)",
+                ));
+            }
+            if !(0.0..=1.0).contains(&backoff.jitter) {
+                return Err(Error::msg(
+                    "invalid backoff jitter (valid range: [0.0, 1.0])",
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.probe_success_ratio) {
+            return Err(Error::msg(
+                "invalid probe_success_ratio (valid range: [0.0, 1.0])",
+            ));
+        }
         if self.stat_sliding_window_bucket_count != 0
             && self.stat_interval_ms % self.stat_sliding_window_bucket_count != 0
         {
@@ -127,13 +239,20 @@ impl SentinelRule for Rule {
 impl PartialEq for Rule {
     fn eq(&self, other: &Self) -> bool {
         self.resource == other.resource
+            && self.match_strategy == other.match_strategy
             && self.strategy == other.strategy
             && self.retry_timeout_ms == other.retry_timeout_ms
             && self.min_request_amount == other.min_request_amount
             && self.stat_interval_ms == other.stat_interval_ms
             && self.stat_sliding_window_bucket_count == other.stat_sliding_window_bucket_count
+            && self.backoff == other.backoff
+            && self.probe_num == other.probe_num
+            && self.probe_success_ratio == other.probe_success_ratio
+            && self.ignore_errors == other.ignore_errors
+            && self.record_errors == other.record_errors
+            && self.mode == other.mode
             && match self.strategy {
-                BreakerStrategy::SlowRequestRatio => {
+                BreakerStrategy::SlowRequestRatio | BreakerStrategy::LatencyPercentile => {
                     self.max_allowed_rt_ms == other.max_allowed_rt_ms
                         && self.threshold == other.threshold
                 }
@@ -162,6 +281,79 @@ impl fmt::Display for Rule {
 mod test {
     use super::*;
 
+    #[test]
+    fn probe_params() {
+        let mut rule = Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::ErrorCount,
+            retry_timeout_ms: 3000,
+            stat_interval_ms: 10000,
+            threshold: 1.0,
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_ok());
+        assert_eq!(rule.get_probe_num(), 1);
+        assert_eq!(rule.get_probe_success_ratio(), 1.0);
+        rule.probe_success_ratio = 1.5;
+        assert!(rule.is_valid().is_err());
+    }
+
+    #[test]
+    fn consecutive_errors_params() {
+        let mut rule = Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::ConsecutiveErrors,
+            retry_timeout_ms: 3000,
+            threshold: 5.0,
+            ..Default::default()
+        };
+        // stat_interval_ms is optional
+        assert!(rule.is_valid().is_ok());
+        rule.threshold = 0.5;
+        assert!(rule.is_valid().is_err());
+    }
+
+    #[test]
+    fn latency_percentile_params() {
+        let mut rule = Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::LatencyPercentile,
+            retry_timeout_ms: 3000,
+            stat_interval_ms: 10000,
+            max_allowed_rt_ms: 100,
+            threshold: 0.99,
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_ok());
+        rule.threshold = 0.0;
+        assert!(rule.is_valid().is_err());
+        rule.threshold = 99.0;
+        assert!(rule.is_valid().is_err());
+    }
+
+    #[test]
+    fn backoff_params() {
+        let mut rule = Rule {
+            resource: "abc".into(),
+            strategy: BreakerStrategy::ErrorCount,
+            retry_timeout_ms: 3000,
+            stat_interval_ms: 10000,
+            threshold: 1.0,
+            backoff: Some(BackoffPolicy {
+                multiplier: 2.0,
+                max_retry_timeout_ms: 60000,
+                jitter: 0.1,
+            }),
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_ok());
+        rule.backoff.as_mut().unwrap().multiplier = 0.5;
+        assert!(rule.is_valid().is_err());
+        rule.backoff.as_mut().unwrap().multiplier = 2.0;
+        rule.backoff.as_mut().unwrap().max_retry_timeout_ms = 1000;
+        assert!(rule.is_valid().is_err());
+    }
+
     #[test]
     fn test_reusable() {
         let rules = vec![
@@ -1,5 +1,8 @@
 use super::*;
-use crate::{base::rule::SentinelRule, logging, utils, Error, Result};
+use crate::{
+    base::{rule::SentinelRule, MatchStrategy, PatternCache},
+    logging, utils, Error, Result,
+};
 use lazy_static::lazy_static;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, RwLock};
@@ -18,20 +21,71 @@ lazy_static! {
         );
         gen_fun_map.insert(BreakerStrategy::ErrorCount, Box::new(gen_error_count));
         gen_fun_map.insert(BreakerStrategy::ErrorRatio, Box::new(gen_error_ratio));
+        gen_fun_map.insert(
+            BreakerStrategy::ConsecutiveErrors,
+            Box::new(gen_consecutive_errors),
+        );
+        gen_fun_map.insert(
+            BreakerStrategy::LatencyPercentile,
+            Box::new(gen_latency_percentile),
+        );
         RwLock::new(gen_fun_map)
     };
+    // the listeners are shared by the circuit breakers of all the Sentinel instances
     pub static ref STATE_CHANGE_LISTERNERS: Mutex<Vec<Arc<dyn StateChangeListener>>> =
         Mutex::new(Vec::new());
-    pub static ref BREAKER_MAP: RwLock<HashMap<String, Vec<Arc<dyn CircuitBreakerTrait>>>> =
-        RwLock::new(HashMap::new());
-    pub static ref CURRENT_RULES: Mutex<RuleMap> = Mutex::new(HashMap::new());
-    pub static ref BREAKER_RULES: RwLock<RuleMap> = RwLock::new(HashMap::new());
+    static ref DEFAULT_RULE_MANAGER: Arc<RuleManager> = Arc::new(RuleManager::new());
+}
+
+/// `RuleManager` holds the circuit breaking rules and the circuit breakers of a Sentinel instance.
+pub struct RuleManager {
+    breaker_map: RwLock<HashMap<String, Vec<Arc<dyn CircuitBreakerTrait>>>>,
+    current_rules: Mutex<RuleMap>,
+    breaker_rules: RwLock<RuleMap>,
+    /// the circuit breakers resolved from the rules with resource patterns, for each resource name
+    pattern_breakers: PatternCache<Rule, Arc<dyn CircuitBreakerTrait>>,
+    /// the active overrides of the circuit breaker state, for each resource name
+    overrides: RwLock<HashMap<String, ActiveOverride>>,
+    /// the error classifiers and the error types telling which errors are recorded by the circuit breakers
+    classification: ErrorClassification,
+}
+
+impl Default for RuleManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `default_rule_manager` returns the rule manager used by the default Sentinel instance
+pub fn default_rule_manager() -> Arc<RuleManager> {
+    DEFAULT_RULE_MANAGER.clone()
 }
 
 pub fn state_change_listeners() -> &'static Mutex<Vec<Arc<dyn StateChangeListener>>> {
     &STATE_CHANGE_LISTERNERS
 }
 
+/// `resolve_pattern_breakers` builds the circuit breakers for the resource,
+/// with the rules whose resource patterns match the resource name.
+/// The circuit breakers resolved from the previous rules are reused if the rules are unchanged.
+fn resolve_pattern_breakers(
+    resource: &String,
+    pattern_rules: Vec<Arc<Rule>>,
+    old_cbs: &mut Vec<Arc<dyn CircuitBreakerTrait>>,
+) -> Vec<Arc<dyn CircuitBreakerTrait>> {
+    let rules: HashSet<Arc<Rule>> = pattern_rules
+        .into_iter()
+        .map(|rule| {
+            Arc::new(Rule {
+                resource: resource.clone(),
+                match_strategy: MatchStrategy::Exact,
+                ..(*rule).clone()
+            })
+        })
+        .collect();
+    build_resource_circuit_breaker(resource, &rules, old_cbs)
+}
+
 use gen_fns::*;
 mod gen_fns {
     use super::*;
@@ -74,264 +128,575 @@ mod gen_fns {
             }
         }
     }
-}
 
-/// `get_rules_of_resource` returns specific resource's rules
-// This func acquires read locks on global `BREAKER_RULES`,
-// please release your write locks on them before calling this func
-pub fn get_rules_of_resource(res: &String) -> Vec<Arc<Rule>> {
-    let breaker_rules = BREAKER_RULES.read().unwrap();
-    let placeholder = HashSet::new();
-    let res_rules = breaker_rules.get(res).unwrap_or(&placeholder);
-    let mut rules = Vec::with_capacity(res_rules.len());
-    for r in res_rules {
-        rules.push(Arc::clone(r));
-    }
-    rules
+    pub(super) fn gen_consecutive_errors(
+        rule: Arc<Rule>,
+        stat: Option<Arc<CounterLeapArray>>,
+    ) -> Arc<dyn CircuitBreakerTrait> {
+        match stat {
+            Some(stat) => Arc::new(ConsecutiveErrorsBreaker::new_with_stat(rule, stat)),
+            None => {
+                logging::warn!("[CircuitBreakerTrait RuleManager] Expect to generate circuit breaker with reuse statistic, but fail to do type casting, expect: CounterLeapArray");
+                Arc::new(ConsecutiveErrorsBreaker::new(rule))
+            }
+        }
+    }
+
+    pub(super) fn gen_latency_percentile(
+        rule: Arc<Rule>,
+        stat: Option<Arc<CounterLeapArray>>,
+    ) -> Arc<dyn CircuitBreakerTrait> {
+        match stat {
+            Some(stat) => Arc::new(LatencyPercentileBreaker::new_with_stat(rule, stat)),
+            None => {
+                logging::warn!("[CircuitBreakerTrait RuleManager] Expect to generate circuit breaker with reuse statistic, but fail to do type casting, expect: CounterLeapArray");
+                Arc::new(LatencyPercentileBreaker::new(rule))
+            }
+        }
+    }
 }
 
-/// `get_rules` returns all the rules
-// This func acquires read locks on global `BREAKER_RULES`,
-// please release your write locks on them before calling this func
-pub fn get_rules() -> Vec<Arc<Rule>> {
-    let mut rules = Vec::new();
-    let breaker_rules = BREAKER_RULES.read().unwrap();
-    for res_rules in (*breaker_rules).values() {
+impl RuleManager {
+    pub fn new() -> Self {
+        RuleManager {
+            breaker_map: RwLock::new(HashMap::new()),
+            current_rules: Mutex::new(HashMap::new()),
+            breaker_rules: RwLock::new(HashMap::new()),
+            pattern_breakers: PatternCache::new(),
+            overrides: RwLock::new(HashMap::new()),
+            classification: ErrorClassification::default(),
+        }
+    }
+
+    /// `get_rules_of_resource` returns specific resource's rules
+    // This func acquires read locks on `breaker_rules`,
+    // please release your write locks on them before calling this func
+    pub fn get_rules_of_resource(&self, res: &String) -> Vec<Arc<Rule>> {
+        let breaker_rules = self.breaker_rules.read().unwrap();
+        let placeholder = HashSet::new();
+        let res_rules = breaker_rules.get(res).unwrap_or(&placeholder);
+        let mut rules = Vec::with_capacity(res_rules.len());
         for r in res_rules {
             rules.push(Arc::clone(r));
         }
+        rules
     }
-    rules
-}
 
-/// `clear_rules` clear all the previous rules.
-// This func acquires locks on global `BREAKER_RULES`, `CURRENT_RULES` and `BREAKER_MAP`,
-// please release your locks on them before calling this func
-pub fn clear_rules() {
-    CURRENT_RULES.lock().unwrap().clear();
-    BREAKER_RULES.write().unwrap().clear();
-    BREAKER_MAP.write().unwrap().clear();
-}
+    /// `get_rules` returns all the rules
+    // This func acquires read locks on `breaker_rules`,
+    // please release your write locks on them before calling this func
+    pub fn get_rules(&self) -> Vec<Arc<Rule>> {
+        let mut rules = Vec::new();
+        let breaker_rules = self.breaker_rules.read().unwrap();
+        for res_rules in (*breaker_rules).values() {
+            for r in res_rules {
+                rules.push(Arc::clone(r));
+            }
+        }
+        rules
+    }
 
-pub fn append_rule(rule: Arc<Rule>) -> bool {
-    if CURRENT_RULES
-        .lock()
-        .unwrap()
-        .get(&rule.resource)
-        .unwrap_or(&HashSet::new())
-        .contains(&rule)
-    {
-        return false;
-    }
-    match rule.is_valid() {
-        Ok(_) => {
-            CURRENT_RULES
-                .lock()
+    /// `clear_rules` clear all the previous rules.
+    // This func acquires locks on `breaker_rules`, `current_rules` and `breaker_map`,
+    // please release your locks on them before calling this func
+    pub fn clear_rules(&self) {
+        self.current_rules.lock().unwrap().clear();
+        self.breaker_rules.write().unwrap().clear();
+        self.breaker_map.write().unwrap().clear();
+        self.pattern_breakers.clear();
+    }
+
+    pub fn append_rule(&self, rule: Arc<Rule>) -> bool {
+        if self
+            .current_rules
+            .lock()
+            .unwrap()
+            .get(&rule.resource)
+            .unwrap_or(&HashSet::new())
+            .contains(&rule)
+        {
+            return false;
+        }
+        match rule.is_valid() {
+            Ok(_) => {
+                self.current_rules
+                    .lock()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(Arc::clone(&rule));
+                self.breaker_rules
+                    .write()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(Arc::clone(&rule));
+            }
+            Err(err) => logging::warn!(
+                "[Hot Spot append_rule] Ignoring invalid flow rule {:?}, reason: {:?}",
+                rule,
+                err
+            ),
+        }
+        let mut placeholder = Vec::new();
+        let new_tcs_of_res = build_resource_circuit_breaker(
+            &rule.resource,
+            self.breaker_rules
+                .read()
                 .unwrap()
-                .entry(rule.resource.clone())
-                .or_default()
-                .insert(Arc::clone(&rule));
-            BREAKER_RULES
+                .get(&rule.resource)
+                .unwrap(),
+            self.breaker_map
+                .write()
+                .unwrap()
+                .get_mut(&rule.resource)
+                .unwrap_or(&mut placeholder),
+        );
+        if !new_tcs_of_res.is_empty() {
+            self.breaker_map
                 .write()
                 .unwrap()
                 .entry(rule.resource.clone())
                 .or_default()
-                .insert(Arc::clone(&rule));
-        }
-        Err(err) => logging::warn!(
-            "[Hot Spot append_rule] Ignoring invalid flow rule {:?}, reason: {:?}",
-            rule,
-            err
-        ),
-    }
-    let mut placeholder = Vec::new();
-    let new_tcs_of_res = build_resource_circuit_breaker(
-        &rule.resource,
-        BREAKER_RULES.read().unwrap().get(&rule.resource).unwrap(),
-        BREAKER_MAP
-            .write()
-            .unwrap()
-            .get_mut(&rule.resource)
-            .unwrap_or(&mut placeholder),
-    );
-    if !new_tcs_of_res.is_empty() {
-        BREAKER_MAP
-            .write()
-            .unwrap()
-            .entry(rule.resource.clone())
-            .or_default()
-            .push(Arc::clone(&new_tcs_of_res[0]));
+                .push(Arc::clone(&new_tcs_of_res[0]));
+        }
+        self.load_pattern_rules(&self.breaker_rules.read().unwrap());
+        true
     }
-    true
-}
 
-/// load_rules replaces old rules with the given circuit breaking rules.
-/// returned `bool` indicate whether the internal map has been changed
-// This func acquires locks on global `CURRENT_RULES`, `BREAKER_RULES` and `BREAKER_MAP`,
-// please release your locks on them before calling this func
-pub fn load_rules(rules: Vec<Arc<Rule>>) -> bool {
-    let mut rule_map: RuleMap = HashMap::new();
-    // todo: validate rules here,
-    // neglect invalid rules,
-    // instead of dealing with them in
-    // `on_rule_update`
-    for rule in rules {
-        let entry = rule_map.entry(rule.resource.clone()).or_default();
-        entry.insert(rule);
-    }
-
-    let mut global_rule_map = CURRENT_RULES.lock().unwrap();
-    if *global_rule_map == rule_map {
-        logging::info!(
-            "[CircuitBreakerTrait] Loaded rules is the same with current rules, so ignore load operation."
+    /// load_rules replaces old rules with the given circuit breaking rules.
+    /// returned `bool` indicate whether the internal map has been changed
+    // This func acquires locks on `current_rules`, `breaker_rules` and `breaker_map`,
+    // please release your locks on them before calling this func
+    pub fn load_rules(&self, rules: Vec<Arc<Rule>>) -> bool {
+        let mut rule_map: RuleMap = HashMap::new();
+        // todo: validate rules here,
+        // neglect invalid rules,
+        // instead of dealing with them in
+        // `on_rule_update`
+        for rule in rules {
+            let entry = rule_map.entry(rule.resource.clone()).or_default();
+            entry.insert(rule);
+        }
+
+        let mut global_rule_map = self.current_rules.lock().unwrap();
+        if *global_rule_map == rule_map {
+            logging::info!(
+                "[CircuitBreakerTrait] Loaded rules is the same with current rules, so ignore load operation."
+            );
+            return false;
+        }
+
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
+        let mut valid_rules_map = HashMap::with_capacity(rule_map.len());
+        for (res, rules) in &rule_map {
+            let mut valid_rules = HashSet::new();
+            for rule in rules {
+                match rule.is_valid() {
+                    Ok(_) => {
+                        valid_rules.insert(Arc::clone(rule));
+                    }
+                    Err(err) => logging::warn!(
+                        "[Flow load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
+                        rule,
+                        err
+                    ),
+                }
+            }
+            if !valid_rules.is_empty() {
+                valid_rules_map.insert(res.clone(), valid_rules);
+            }
+        }
+
+        let start = utils::curr_time_nanos();
+        let mut global_breaker_map = self.breaker_map.write().unwrap();
+        let mut valid_breaker_map = HashMap::with_capacity(valid_rules_map.len());
+
+        // build global_breaker_map according to valid rules
+        for (res, rules) in valid_rules_map.iter() {
+            let mut placeholder = Vec::new();
+            let new_cbs_of_res = build_resource_circuit_breaker(
+                res,
+                rules,
+                global_breaker_map.get_mut(res).unwrap_or(&mut placeholder),
+            );
+            if !new_cbs_of_res.is_empty() {
+                valid_breaker_map.insert(res.clone(), new_cbs_of_res);
+            }
+        }
+
+        if valid_rules_map.is_empty() {
+            logging::info!("[Circuit Breaker] Circuit breaking rules were cleared")
+        } else {
+            logging::info!(
+                "[Circuit Breaker] Circuit breaking rules were loaded: {:?}",
+                valid_rules_map.values()
+            )
+        }
+
+        self.load_pattern_rules(&valid_rules_map);
+        *self.breaker_rules.write().unwrap() = valid_rules_map;
+        *global_breaker_map = valid_breaker_map;
+        *global_rule_map = rule_map;
+        drop(global_rule_map);
+        drop(global_breaker_map);
+        logging::debug!(
+            "[CircuitBreakerTrait load_rules] Time statistic(ns) for updating flow rule, time cost {}",
+            utils::curr_time_nanos() - start
         );
-        return false;
+
+        true
     }
 
-    // when rule_map is different with global one, update the global one
-    // ignore invalid rules
-    let mut valid_rules_map = HashMap::with_capacity(rule_map.len());
-    for (res, rules) in &rule_map {
-        let mut valid_rules = HashSet::new();
-        for rule in rules {
+    /// load_rulesOfResource loads the given resource's circuitBreaker rules to the rule manager, while all previous resource's rules will be replaced.
+    /// the first returned value indicates whether do real load operation, if the rules is the same with previous resource's rules, return false
+    // This func acquires locks on `current_rules`, `breaker_rules` and `breaker_map`,
+    // please release your locks on them before calling this func
+    pub fn load_rules_of_resource(&self, res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+        if res.is_empty() {
+            return Err(Error::msg("empty resource"));
+        }
+        let rules: HashSet<_> = rules.into_iter().collect();
+        let mut global_rule_map = self.current_rules.lock().unwrap();
+        let mut global_breaker_map = self.breaker_map.write().unwrap();
+        // clear resource rules
+        if rules.is_empty() {
+            global_rule_map.remove(res);
+            global_breaker_map.remove(res);
+            let mut breaker_rules = self.breaker_rules.write().unwrap();
+            breaker_rules.remove(res);
+            self.load_pattern_rules(&breaker_rules);
+            logging::info!(
+                "[CircuitBreakerTrait] clear resource level rules, resource {}",
+                res
+            );
+            return Ok(true);
+        }
+        // load resource level rules
+        if global_rule_map.get(res).unwrap_or(&HashSet::new()) == &rules {
+            logging::info!("[CircuitBreakerTrait] Load resource level rules is the same with current resource level rules, so ignore load operation.");
+            return Ok(false);
+        }
+
+        let mut valid_res_rules = HashSet::with_capacity(res.len());
+        for rule in &rules {
             match rule.is_valid() {
-                Ok(_) => {
-                    valid_rules.insert(Arc::clone(rule));
-                }
+                Ok(_) => {valid_res_rules.insert(Arc::clone(rule));},
                 Err(err) => logging::warn!(
-                    "[Flow load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
+                    "CircuitBreakerTrait onResourceRuleUpdate] Ignoring invalid circuitBreaker rule {:?}, reason: {:?}",
                     rule,
                     err
                 ),
             }
         }
-        if !valid_rules.is_empty() {
-            valid_rules_map.insert(res.clone(), valid_rules);
-        }
-    }
+        // the `res` related rules changes, have to update
+        let start = utils::curr_time_nanos();
+        let mut placeholder = Vec::new();
+        let old_res_tcs = global_breaker_map.get_mut(res).unwrap_or(&mut placeholder);
 
-    let start = utils::curr_time_nanos();
-    let mut global_breaker_map = BREAKER_MAP.write().unwrap();
-    let mut valid_breaker_map = HashMap::with_capacity(valid_rules_map.len());
+        let valid_res_rules_string = format!("{:?}", &valid_res_rules);
+        let new_res_tcs = build_resource_circuit_breaker(res, &valid_res_rules, old_res_tcs);
 
-    // build global_breaker_map according to valid rules
-    for (res, rules) in valid_rules_map.iter() {
-        let mut placeholder = Vec::new();
-        let new_cbs_of_res = build_resource_circuit_breaker(
+        if new_res_tcs.is_empty() {
+            global_breaker_map.remove(res);
+            self.breaker_rules.write().unwrap().remove(res);
+        } else {
+            global_breaker_map.insert(res.clone(), new_res_tcs);
+            self.breaker_rules
+                .write()
+                .unwrap()
+                .insert(res.clone(), valid_res_rules);
+        }
+
+        global_rule_map.insert(res.clone(), rules);
+        self.load_pattern_rules(&self.breaker_rules.read().unwrap());
+        logging::debug!(
+            "[CircuitBreakerTrait onResourceRuleUpdate] Time statistics(ns) for updating circuit breaker rule, timeCost: {}",
+            utils::curr_time_nanos() - start
+        );
+        logging::info!(
+            "[CircuitBreakerTrait] load resource level rules, resource: {}, valid_res_rules: {}",
             res,
-            rules,
-            global_breaker_map.get_mut(res).unwrap_or(&mut placeholder),
+            valid_res_rules_string
         );
-        if !new_cbs_of_res.is_empty() {
-            valid_breaker_map.insert(res.clone(), new_cbs_of_res);
-        }
+
+        Ok(true)
     }
 
-    if valid_rules_map.is_empty() {
-        logging::info!("[Circuit Breaker] Circuit breaking rules were cleared")
-    } else {
-        logging::info!(
-            "[Circuit Breaker] Circuit breaking rules were loaded: {:?}",
-            valid_rules_map.values()
-        )
+    /// `get_breakers_of_resource` returns the circuit breakers taking effect on the resource,
+    /// including the ones resolved from the rules with resource patterns.
+    // This func acquires read locks on `breaker_map`,
+    // please release your write locks on them before calling this func
+    pub fn get_breakers_of_resource(&self, resource: &String) -> Vec<Arc<dyn CircuitBreakerTrait>> {
+        // the circuit breakers of pattern rules are kept as templates
+        let has_pattern = self
+            .breaker_rules
+            .read()
+            .unwrap()
+            .get(resource)
+            .is_some_and(|rules| rules.iter().any(|rule| rule.match_strategy.is_pattern()));
+        let mut breakers = Vec::new();
+        if let Some(res_cbs) = self.breaker_map.read().unwrap().get(resource) {
+            for b in res_cbs {
+                if !has_pattern || !b.bound_rule().match_strategy.is_pattern() {
+                    breakers.push(Arc::clone(b));
+                }
+            }
+        }
+        breakers.append(
+            &mut self
+                .pattern_breakers
+                .get_or_resolve(resource, |rules, mut old_cbs| {
+                    resolve_pattern_breakers(resource, rules, &mut old_cbs)
+                }),
+        );
+        breakers
     }
 
-    *BREAKER_RULES.write().unwrap() = valid_rules_map;
-    *global_breaker_map = valid_breaker_map;
-    *global_rule_map = rule_map;
-    drop(global_rule_map);
-    drop(global_breaker_map);
-    logging::debug!(
-        "[CircuitBreakerTrait load_rules] Time statistic(ns) for updating flow rule, time cost {}",
-        utils::curr_time_nanos() - start
-    );
+    /// `clear_rules_of_resource` clears resource level rules in circuitBreaker module.
+    pub fn clear_rules_of_resource(&self, res: &String) {
+        let mut breaker_rules = self.breaker_rules.write().unwrap();
+        breaker_rules.remove(res);
+        self.load_pattern_rules(&breaker_rules);
+        drop(breaker_rules);
+        self.current_rules.lock().unwrap().remove(res);
+        self.breaker_map.write().unwrap().remove(res);
+    }
 
-    true
-}
+    /// `load_pattern_rules` compiles the resource patterns of the rules,
+    /// whose circuit breakers are resolved for each resource name.
+    fn load_pattern_rules(&self, rule_map: &RuleMap) {
+        self.pattern_breakers
+            .load_rules(rule_map.values().flatten());
+    }
 
-/// load_rulesOfResource loads the given resource's circuitBreaker rules to the rule manager, while all previous resource's rules will be replaced.
-/// the first returned value indicates whether do real load operation, if the rules is the same with previous resource's rules, return false
-// This func acquires locks on global `CURRENT_RULES`, `BREAKER_RULES` and `BREAKER_MAP`,
-// please release your locks on them before calling this func
-pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
-    if res.is_empty() {
-        return Err(Error::msg("empty resource"));
-    }
-    let rules: HashSet<_> = rules.into_iter().collect();
-    let mut global_rule_map = CURRENT_RULES.lock().unwrap();
-    let mut global_breaker_map = BREAKER_MAP.write().unwrap();
-    // clear resource rules
-    if rules.is_empty() {
-        global_rule_map.remove(res);
-        global_breaker_map.remove(res);
-        BREAKER_RULES.write().unwrap().remove(res);
+    /// `load_override_rules` replaces all the previous overrides with the given override rules,
+    /// the circuit breakers of the resources are forced to the state of the rules at once.
+    /// The duration of an override is not renewed if the same rule is loaded again.
+    /// returned `bool` indicate whether the overrides have been changed
+    pub fn load_override_rules(&self, rules: Vec<Arc<OverrideRule>>) -> bool {
+        let now = utils::curr_time_millis();
+        let mut overrides = self.overrides.write().unwrap();
+        let mut new_overrides = HashMap::with_capacity(rules.len());
+        for rule in rules {
+            if let Err(err) = rule.is_valid() {
+                logging::warn!(
+                    "[CircuitBreaker load_override_rules] Ignoring invalid override rule {:?}, reason: {:?}",
+                    rule,
+                    err
+                );
+                continue;
+            }
+            let active = match overrides.get(&rule.resource) {
+                Some(old) if old.rule == rule && !old.is_expired(now) => old.clone(),
+                _ => ActiveOverride::new(rule, now),
+            };
+            new_overrides.insert(active.rule.resource.clone(), active);
+        }
+        let changed = overrides.len() != new_overrides.len()
+            || new_overrides.iter().any(|(res, active)| {
+                overrides
+                    .get(res)
+                    .is_none_or(|old| !Arc::ptr_eq(&old.rule, &active.rule))
+            });
+        if !changed {
+            logging::info!(
+                "[CircuitBreaker] Loaded override rules are the same with current override rules, so ignore load operation."
+            );
+            return false;
+        }
+        let forced: Vec<_> = new_overrides
+            .values()
+            .filter(|active| {
+                overrides
+                    .get(&active.rule.resource)
+                    .is_none_or(|old| !Arc::ptr_eq(&old.rule, &active.rule))
+            })
+            .map(|active| Arc::clone(&active.rule))
+            .collect();
+        *overrides = new_overrides;
+        drop(overrides);
         logging::info!(
-            "[CircuitBreakerTrait] clear resource level rules, resource {}",
-            res
+            "[CircuitBreaker] Circuit breaker override rules were loaded: {:?}",
+            forced
         );
-        return Ok(true);
+        for rule in forced {
+            self.apply_override(rule);
+        }
+        true
     }
-    // load resource level rules
-    if global_rule_map.get(res).unwrap_or(&HashSet::new()) == &rules {
-        logging::info!("[CircuitBreakerTrait] Load resource level rules is the same with current resource level rules, so ignore load operation.");
-        return Ok(false);
+
+    /// `force_state` pins the circuit breakers of the resource to the state for `duration_ms` milliseconds,
+    /// 0 means until the override is cleared. The previous override of the resource is replaced.
+    pub fn force_state(&self, res: &str, state: OverrideState, duration_ms: u64) {
+        let rule = Arc::new(OverrideRule {
+            resource: res.into(),
+            state,
+            duration_ms,
+            ..Default::default()
+        });
+        self.overrides.write().unwrap().insert(
+            rule.resource.clone(),
+            ActiveOverride::new(Arc::clone(&rule), utils::curr_time_millis()),
+        );
+        logging::info!(
+            "[CircuitBreaker] Circuit breakers were overridden: {:?}",
+            rule
+        );
+        self.apply_override(rule);
     }
 
-    let mut valid_res_rules = HashSet::with_capacity(res.len());
-    for rule in &rules {
-        match rule.is_valid() {
-            Ok(_) => {valid_res_rules.insert(Arc::clone(rule));},
-            Err(err) => logging::warn!(
-                "CircuitBreakerTrait onResourceRuleUpdate] Ignoring invalid circuitBreaker rule {:?}, reason: {:?}",
-                rule,
-                err
-            ),
+    fn apply_override(&self, rule: Arc<OverrideRule>) {
+        for breaker in self.get_breakers_of_resource(&rule.resource) {
+            breaker.force_state(Arc::clone(&rule));
         }
     }
-    // the `res` related rules changes, have to update
-    let start = utils::curr_time_nanos();
-    let mut placeholder = Vec::new();
-    let old_res_tcs = global_breaker_map.get_mut(res).unwrap_or(&mut placeholder);
 
-    let valid_res_rules_string = format!("{:?}", &valid_res_rules);
-    let new_res_tcs = build_resource_circuit_breaker(res, &valid_res_rules, old_res_tcs);
+    /// `get_override_of_resource` returns the active override rule of the resource, the expired one is removed.
+    // This func acquires locks on `overrides`,
+    // please release your locks on them before calling this func
+    pub fn get_override_of_resource(&self, res: &str) -> Option<Arc<OverrideRule>> {
+        let now = utils::curr_time_millis();
+        {
+            let overrides = self.overrides.read().unwrap();
+            match overrides.get(res) {
+                None => return None,
+                Some(active) if !active.is_expired(now) => return Some(Arc::clone(&active.rule)),
+                _ => {}
+            }
+        }
+        let mut overrides = self.overrides.write().unwrap();
+        if overrides
+            .get(res)
+            .is_some_and(|active| active.is_expired(now))
+        {
+            overrides.remove(res);
+            logging::info!("[CircuitBreaker] Override of resource {} expired", res);
+        }
+        None
+    }
 
-    if new_res_tcs.is_empty() {
-        global_breaker_map.remove(res);
-        BREAKER_RULES.write().unwrap().remove(res);
-    } else {
-        global_breaker_map.insert(res.clone(), new_res_tcs);
-        BREAKER_RULES
-            .write()
+    /// `get_override_rules` returns all the active override rules
+    pub fn get_override_rules(&self) -> Vec<Arc<OverrideRule>> {
+        let now = utils::curr_time_millis();
+        self.overrides
+            .read()
             .unwrap()
-            .insert(res.clone(), valid_res_rules);
+            .values()
+            .filter(|active| !active.is_expired(now))
+            .map(|active| Arc::clone(&active.rule))
+            .collect()
+    }
+
+    /// `clear_override` lifts the override of the resource,
+    /// the circuit breakers resume from the forced state.
+    pub fn clear_override(&self, res: &str) {
+        self.overrides.write().unwrap().remove(res);
+    }
+
+    /// `clear_overrides` lifts all the overrides.
+    pub fn clear_overrides(&self) {
+        self.overrides.write().unwrap().clear();
+    }
+
+    /// `register_error_classifier` registers the classifier for the rules with id `key`,
+    /// or for the rules of the resource `key`, see `classifier` for details.
+    pub fn register_error_classifier<S: Into<String>>(
+        &self,
+        key: S,
+        classifier: Arc<ErrorClassifier>,
+    ) {
+        self.classification
+            .register_classifier(key.into(), classifier)
+    }
+
+    pub fn remove_error_classifier(&self, key: &str) {
+        self.classification.remove_classifier(key)
+    }
+
+    pub fn clear_error_classifiers(&self) {
+        self.classification.clear_classifiers()
+    }
+
+    /// `register_error_type` makes the errors of type `T` matched by its name in `ignore_errors` and `record_errors`
+    pub fn register_error_type<T: std::error::Error + 'static>(&self) {
+        self.classification.register_error_type::<T>()
     }
 
-    global_rule_map.insert(res.clone(), rules);
-    logging::debug!(
-        "[CircuitBreakerTrait onResourceRuleUpdate] Time statistics(ns) for updating circuit breaker rule, timeCost: {}",
-        utils::curr_time_nanos() - start
-    );
-    logging::info!(
-        "[CircuitBreakerTrait] load resource level rules, resource: {}, valid_res_rules: {}",
-        res,
-        valid_res_rules_string
-    );
+    pub fn error_matches(&self, err: &Error, pattern: &str) -> bool {
+        self.classification.error_matches(err, pattern)
+    }
 
-    Ok(true)
+    /// `is_recorded_error` checks whether the error should be recorded by the circuit breaker of the rule
+    pub fn is_recorded_error(&self, rule: &Rule, err: &Error) -> bool {
+        self.classification.is_recorded_error(rule, err)
+    }
+}
+
+// The following functions operate on the default rule manager.
+pub fn get_rules_of_resource(res: &String) -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules_of_resource(res)
+}
+
+pub fn get_rules() -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules()
+}
+
+pub fn clear_rules() {
+    DEFAULT_RULE_MANAGER.clear_rules()
+}
+
+pub fn append_rule(rule: Arc<Rule>) -> bool {
+    DEFAULT_RULE_MANAGER.append_rule(rule)
+}
+
+pub fn load_rules(rules: Vec<Arc<Rule>>) -> bool {
+    DEFAULT_RULE_MANAGER.load_rules(rules)
+}
+
+pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+    DEFAULT_RULE_MANAGER.load_rules_of_resource(res, rules)
 }
 
-// This func acquires read locks on global `BREAKER_MAP`,
-// please release your write locks on them before calling this func
 pub fn get_breakers_of_resource(resource: &String) -> Vec<Arc<dyn CircuitBreakerTrait>> {
-    let breakers_map = BREAKER_MAP.read().unwrap();
-    let placeholder = Vec::new();
-    let res_cbs = breakers_map.get(resource).unwrap_or(&placeholder);
-    let mut breakers = Vec::with_capacity(res_cbs.len());
-    for b in res_cbs {
-        breakers.push(Arc::clone(b));
-    }
-    breakers
+    DEFAULT_RULE_MANAGER.get_breakers_of_resource(resource)
+}
+
+pub fn clear_rules_of_resource(res: &String) {
+    DEFAULT_RULE_MANAGER.clear_rules_of_resource(res)
+}
+
+pub fn load_override_rules(rules: Vec<Arc<OverrideRule>>) -> bool {
+    DEFAULT_RULE_MANAGER.load_override_rules(rules)
+}
+
+/// `force_open` pins the circuit breakers of the resource to Open for `duration_ms` milliseconds,
+/// 0 means until the override is cleared.
+pub fn force_open(res: &str, duration_ms: u64) {
+    DEFAULT_RULE_MANAGER.force_state(res, OverrideState::Open, duration_ms)
+}
+
+/// `force_close` pins the circuit breakers of the resource to Closed for `duration_ms` milliseconds,
+/// 0 means until the override is cleared.
+pub fn force_close(res: &str, duration_ms: u64) {
+    DEFAULT_RULE_MANAGER.force_state(res, OverrideState::Closed, duration_ms)
+}
+
+pub fn get_override_of_resource(res: &str) -> Option<Arc<OverrideRule>> {
+    DEFAULT_RULE_MANAGER.get_override_of_resource(res)
+}
+
+pub fn get_override_rules() -> Vec<Arc<OverrideRule>> {
+    DEFAULT_RULE_MANAGER.get_override_rules()
+}
+
+pub fn clear_override(res: &str) {
+    DEFAULT_RULE_MANAGER.clear_override(res)
+}
+
+pub fn clear_overrides() {
+    DEFAULT_RULE_MANAGER.clear_overrides()
 }
 
 /// register_state_change_listeners registers the global state change listener for all circuit breakers
@@ -379,13 +744,6 @@ pub fn remove_circuit_breaker_generator(s: &BreakerStrategy) -> Result<()> {
     }
 }
 
-/// `clear_rules_of_resource` clears resource level rules in circuitBreaker module.
-pub fn clear_rules_of_resource(res: &String) {
-    BREAKER_RULES.write().unwrap().remove(res);
-    CURRENT_RULES.lock().unwrap().remove(res);
-    BREAKER_MAP.write().unwrap().remove(res);
-}
-
 pub fn calculate_reuse_index_for(
     r: &Arc<Rule>,
     old_res_cbs: &[Arc<dyn CircuitBreakerTrait>],
@@ -516,7 +874,7 @@ mod test {
             ..Default::default()
         })]);
 
-        let breaker_map = BREAKER_MAP.write().unwrap();
+        let breaker_map = DEFAULT_RULE_MANAGER.breaker_map.write().unwrap();
 
         assert!(GEN_FUN_MAP.read().unwrap().contains_key(&key));
         assert!(!breaker_map[&resource].is_empty());
@@ -560,7 +918,7 @@ mod test {
         });
         let sucess = load_rules(vec![Arc::clone(&r0), Arc::clone(&r1), Arc::clone(&r2)]);
         assert!(sucess);
-        let breaker_map = BREAKER_MAP.read().unwrap();
+        let breaker_map = DEFAULT_RULE_MANAGER.breaker_map.read().unwrap();
         let _b2 = &breaker_map["abc"][1];
         assert_eq!(breaker_map.len(), 1);
         assert_eq!(breaker_map["abc"].len(), 3);
@@ -611,7 +969,7 @@ mod test {
             Arc::clone(&r6),
         ]);
         assert!(sucess);
-        let breaker_map = BREAKER_MAP.read().unwrap();
+        let breaker_map = DEFAULT_RULE_MANAGER.breaker_map.read().unwrap();
         let _b2 = &breaker_map["abc"][1];
         assert_eq!(breaker_map.len(), 1);
         assert_eq!(breaker_map["abc"].len(), 4);
@@ -691,9 +1049,9 @@ mod test {
         assert!(success.unwrap());
         let success = load_rules_of_resource(&"abc2".into(), vec![Arc::clone(&r2)]);
         assert!(success.unwrap());
-        let breaker_map = BREAKER_MAP.read().unwrap();
-        let breaker_rules = BREAKER_RULES.read().unwrap();
-        let current_rules = CURRENT_RULES.lock().unwrap();
+        let breaker_map = DEFAULT_RULE_MANAGER.breaker_map.read().unwrap();
+        let breaker_rules = DEFAULT_RULE_MANAGER.breaker_rules.read().unwrap();
+        let current_rules = DEFAULT_RULE_MANAGER.current_rules.lock().unwrap();
         assert_eq!(2, breaker_map["abc1"].len());
         assert_eq!(2, breaker_rules["abc1"].len());
         assert_eq!(2, current_rules["abc1"].len());
@@ -708,15 +1066,36 @@ mod test {
         let success =
             load_rules_of_resource(&"abc1".into(), vec![Arc::clone(&r0), Arc::clone(&r1)]);
         assert!(!success.unwrap());
-        assert_eq!(2, BREAKER_MAP.read().unwrap()["abc1"].len());
-        assert_eq!(2, BREAKER_RULES.read().unwrap()["abc1"].len());
-        assert_eq!(2, CURRENT_RULES.lock().unwrap()["abc1"].len());
+        assert_eq!(
+            2,
+            DEFAULT_RULE_MANAGER.breaker_map.read().unwrap()["abc1"].len()
+        );
+        assert_eq!(
+            2,
+            DEFAULT_RULE_MANAGER.breaker_rules.read().unwrap()["abc1"].len()
+        );
+        assert_eq!(
+            2,
+            DEFAULT_RULE_MANAGER.current_rules.lock().unwrap()["abc1"].len()
+        );
 
         let success = load_rules_of_resource(&"abc1".into(), Vec::new());
         assert!(success.unwrap());
-        assert!(!BREAKER_MAP.read().unwrap().contains_key("abc1"));
-        assert!(!BREAKER_RULES.read().unwrap().contains_key("abc1"));
-        assert!(!CURRENT_RULES.lock().unwrap().contains_key("abc1"));
+        assert!(!DEFAULT_RULE_MANAGER
+            .breaker_map
+            .read()
+            .unwrap()
+            .contains_key("abc1"));
+        assert!(!DEFAULT_RULE_MANAGER
+            .breaker_rules
+            .read()
+            .unwrap()
+            .contains_key("abc1"));
+        assert!(!DEFAULT_RULE_MANAGER
+            .current_rules
+            .lock()
+            .unwrap()
+            .contains_key("abc1"));
 
         clear_rules();
     }
@@ -802,9 +1181,9 @@ mod test {
         assert!(success);
 
         clear_rules_of_resource(&"abc1".into());
-        let breaker_map = BREAKER_MAP.read().unwrap();
-        let breaker_rules = BREAKER_RULES.read().unwrap();
-        let current_rules = CURRENT_RULES.lock().unwrap();
+        let breaker_map = DEFAULT_RULE_MANAGER.breaker_map.read().unwrap();
+        let breaker_rules = DEFAULT_RULE_MANAGER.breaker_rules.read().unwrap();
+        let current_rules = DEFAULT_RULE_MANAGER.current_rules.lock().unwrap();
         assert_eq!(0, breaker_map.get("abc1").unwrap_or(&Vec::new()).len());
         assert_eq!(
             0,
@@ -1,15 +1,31 @@
 use super::*;
-use crate::base::{BaseSlot, BlockType, EntryContext, RuleCheckSlot, TokenResult};
+use crate::base::{
+    BaseSlot, BlockError, BlockType, EntryContext, RuleCheckSlot, RuleMode, TokenResult,
+};
 use lazy_static::lazy_static;
 use std::sync::Arc;
 
 const RULE_CHECK_SLOT_ORDER: u32 = 5000;
 
 /// A RuleSlot for flow related metrics
-pub struct Slot {}
+pub struct Slot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl Slot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        Slot { rule_manager }
+    }
+}
+
+impl Default for Slot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
 
 lazy_static! {
-    pub static ref DEFAULT_SLOT: Arc<Slot> = Arc::new(Slot {});
+    pub static ref DEFAULT_SLOT: Arc<Slot> = Arc::new(Slot::default());
 }
 
 pub fn default_slot() -> Arc<Slot> {
@@ -28,10 +44,25 @@ impl RuleCheckSlot for Slot {
         if res.is_empty() {
             return ctx.result().clone();
         }
-        if can_pass_check(ctx, &res).is_some() {
-            ctx.set_result(TokenResult::new_blocked_with_msg(
+        // the override of the resource takes precedence over the breakers
+        if let Some(override_rule) = self.rule_manager.get_override_of_resource(&res) {
+            if override_rule.state == OverrideState::Open {
+                let state = override_rule.state;
+                ctx.set_result(TokenResult::new_blocked_with_cause(
+                    BlockType::CircuitBreaking,
+                    "circuit breaker forced open".into(),
+                    override_rule,
+                    Arc::new(state),
+                ));
+            }
+            return ctx.result().clone();
+        }
+        if let Some((rule, state)) = can_pass_check(&self.rule_manager, ctx, &res) {
+            ctx.set_result(TokenResult::new_blocked_with_cause(
                 BlockType::CircuitBreaking,
                 "circuit breaker check blocked".into(),
+                rule,
+                Arc::new(state),
             ));
         }
         return ctx.result().clone();
@@ -39,12 +70,27 @@ impl RuleCheckSlot for Slot {
 }
 
 /// `None` indicates it passes
-/// `Some(rule)` indicates it is broke by the rule
-fn can_pass_check(ctx: &EntryContext, res: &String) -> Option<Arc<Rule>> {
-    let breakers = get_breakers_of_resource(res);
+/// `Some((rule, state))` indicates it is broke by the rule, and the state is the one of the breaker
+/// Breakers whose rules are in shadow mode only record the shadow blocks in the context.
+fn can_pass_check(
+    rule_manager: &RuleManager,
+    ctx: &mut EntryContext,
+    res: &String,
+) -> Option<(Arc<Rule>, State)> {
+    let breakers = rule_manager.get_breakers_of_resource(res);
     for breaker in breakers {
         if !breaker.try_pass(ctx) {
-            return Some(Arc::clone(breaker.bound_rule()));
+            let rule = breaker.bound_rule();
+            if rule.mode == RuleMode::Shadow {
+                ctx.add_shadow_block(BlockError::new_with_cause(
+                    BlockType::CircuitBreaking,
+                    "circuit breaker check blocked".into(),
+                    rule.clone(),
+                    Arc::new(breaker.current_state()),
+                ));
+                continue;
+            }
+            return Some((Arc::clone(rule), breaker.current_state()));
         }
     }
     None
@@ -53,7 +99,7 @@ fn can_pass_check(ctx: &EntryContext, res: &String) -> Option<Arc<Rule>> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::base::{EntryContext, ResourceType, ResourceWrapper, TrafficType};
+    use crate::base::{EntryContext, ResourceType, ResourceWrapper, StatSlot, TrafficType};
 
     #[test]
     #[ignore]
@@ -87,6 +133,7 @@ mod test {
                     };
                     breaker.expect_try_pass().return_const(false);
                     breaker.expect_bound_rule().return_const(Arc::new(rule));
+                    breaker.expect_current_state().return_const(State::Open);
                     Arc::new(breaker)
                 },
             ),
@@ -96,12 +143,22 @@ mod test {
         let res_name = String::from("abc");
         assert_eq!(get_breakers_of_resource(&res_name).len(), 1);
 
-        let slot = Slot {};
+        let slot = Slot::default();
         let mut ctx = EntryContext::new();
         let res = ResourceWrapper::new(res_name, ResourceType::Common, TrafficType::Inbound);
         ctx.set_resource(res);
         let token = slot.check(&mut ctx);
         assert!(token.is_blocked());
+        // the triggered rule and the state of the breaker are kept in the block error
+        let block_err = token.block_err().unwrap();
+        assert_eq!(block_err.block_type(), BlockType::CircuitBreaking);
+        let rule = block_err.triggered_rule().unwrap();
+        assert_eq!(rule.resource_name(), "abc");
+        let state = block_err.triggered_value().unwrap();
+        assert_eq!(
+            (*state).as_any().downcast_ref::<State>(),
+            Some(&State::Open)
+        );
         clear_rules();
     }
 
@@ -135,7 +192,7 @@ mod test {
         let res_name = String::from("abc");
         assert_eq!(get_breakers_of_resource(&res_name).len(), 1);
 
-        let slot = Slot {};
+        let slot = Slot::default();
         let mut ctx = EntryContext::new();
         let res = ResourceWrapper::new(res_name, ResourceType::Common, TrafficType::Inbound);
         ctx.set_resource(res);
@@ -144,4 +201,101 @@ mod test {
         assert!(ctx.result().is_pass());
         clear_rules();
     }
+
+    #[test]
+    fn check_shadow() {
+        let rule_manager = Arc::new(RuleManager::new());
+        let res_name = String::from("check_shadow");
+        let rule = Arc::new(Rule {
+            resource: res_name.clone(),
+            strategy: BreakerStrategy::ErrorCount,
+            retry_timeout_ms: 3000,
+            min_request_amount: 1,
+            stat_interval_ms: 10000,
+            threshold: 1.0,
+            mode: RuleMode::Shadow,
+            ..Default::default()
+        });
+        rule_manager.load_rules(vec![rule.clone()]);
+        let breaker = rule_manager
+            .get_breakers_of_resource(&res_name)
+            .pop()
+            .unwrap();
+        breaker.on_request_complete(0, &Some(crate::Error::msg("biz error")));
+        assert_eq!(breaker.current_state(), State::Open);
+
+        let slot = Slot::new(rule_manager);
+        let mut ctx = EntryContext::new();
+        let res = ResourceWrapper::new(res_name, ResourceType::Common, TrafficType::Inbound);
+        ctx.set_resource(res);
+        assert!(slot.check(&mut ctx).is_pass());
+        // the shadow block is attributed to the rule
+        assert_eq!(ctx.shadow_blocks().len(), 1);
+        let block_err = &ctx.shadow_blocks()[0];
+        assert_eq!(block_err.block_type(), BlockType::CircuitBreaking);
+        let triggered_rule = block_err.triggered_rule().unwrap();
+        assert_eq!(triggered_rule.resource_name(), "check_shadow");
+        assert!(format!("{:?}", triggered_rule).contains(&rule.id));
+    }
+
+    #[test]
+    #[ignore]
+    fn check_override() {
+        clear_rules();
+        clear_overrides();
+        let res_name = String::from("check_override");
+        load_rules(vec![Arc::new(Rule {
+            resource: res_name.clone(),
+            strategy: BreakerStrategy::ErrorCount,
+            retry_timeout_ms: 3000,
+            min_request_amount: 1,
+            stat_interval_ms: 10000,
+            threshold: 1.0,
+            ..Default::default()
+        })]);
+        let breaker = get_breakers_of_resource(&res_name).pop().unwrap();
+        let slot = Slot::default();
+        let new_ctx = || {
+            let mut ctx = EntryContext::new();
+            let res =
+                ResourceWrapper::new(res_name.clone(), ResourceType::Common, TrafficType::Inbound);
+            ctx.set_resource(res);
+            ctx
+        };
+        let check = || slot.check(&mut new_ctx());
+
+        force_open(&res_name, 0);
+        assert_eq!(breaker.current_state(), State::Open);
+        assert!(check().is_blocked());
+        assert_eq!(
+            get_override_of_resource(&res_name).unwrap().state,
+            OverrideState::Open
+        );
+
+        force_close(&res_name, 100);
+        assert_eq!(breaker.current_state(), State::Closed);
+        // the errors are not recorded while the breakers are pinned
+        let mut ctx = new_ctx();
+        ctx.set_err(crate::Error::msg("biz error"));
+        MetricStatSlot::default().on_completed(&mut ctx);
+        assert_eq!(breaker.current_state(), State::Closed);
+        assert!(check().is_pass());
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        assert!(get_override_of_resource(&res_name).is_none());
+
+        // the same rules loaded again do not renew the override
+        let rule = Arc::new(OverrideRule {
+            resource: res_name.clone(),
+            ..Default::default()
+        });
+        assert!(load_override_rules(vec![Arc::clone(&rule)]));
+        assert!(!load_override_rules(vec![rule]));
+        assert_eq!(get_override_rules().len(), 1);
+        assert!(check().is_blocked());
+        // the breaker probes once the override is lifted
+        assert!(load_override_rules(Vec::new()));
+        assert!(check().is_pass());
+        assert_eq!(breaker.current_state(), State::HalfOpen);
+        clear_rules();
+    }
 }
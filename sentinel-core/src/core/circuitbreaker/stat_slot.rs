@@ -7,10 +7,25 @@ const STAT_SLOT_ORDER: u32 = 5000;
 
 /// MetricStatSlot records metrics for circuit breaker on invocation completed.
 /// MetricStatSlot must be filled into slot chain if circuit breaker is alive.
-pub struct MetricStatSlot {}
+pub struct MetricStatSlot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl MetricStatSlot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        MetricStatSlot { rule_manager }
+    }
+}
+
+impl Default for MetricStatSlot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
 
 lazy_static! {
-    pub static ref DEFAULT_METRIC_STAT_SLOT: Arc<MetricStatSlot> = Arc::new(MetricStatSlot {});
+    pub static ref DEFAULT_METRIC_STAT_SLOT: Arc<MetricStatSlot> =
+        Arc::new(MetricStatSlot::default());
 }
 
 pub fn default_metric_stat_slot() -> Arc<MetricStatSlot> {
@@ -31,8 +46,21 @@ impl StatSlot for MetricStatSlot {
     fn on_completed(&self, ctx: &mut EntryContext) {
         let res = ctx.resource().name();
         let rt = ctx.round_trip();
-        for cb in get_breakers_of_resource(res) {
-            cb.on_request_complete(rt, ctx.get_err());
+        let err = ctx.get_err();
+        // the invocations are not recorded while the circuit breakers are pinned
+        if self.rule_manager.get_override_of_resource(res).is_some() {
+            return;
+        }
+        for cb in self.rule_manager.get_breakers_of_resource(res) {
+            if err
+                .as_ref()
+                .is_some_and(|err| !self.rule_manager.is_recorded_error(cb.bound_rule(), err))
+            {
+                // the ignored errors are regarded as successful invocations
+                cb.on_request_complete(rt, &None);
+            } else {
+                cb.on_request_complete(rt, err);
+            }
         }
     }
 }
@@ -0,0 +1,169 @@
+//! Manual override of the circuit breaker state.
+//!
+//! During an incident, operators may pin the circuit breakers of a resource to Open, i.e., reject all the invocations,
+//! or pin them to Closed after a fix, i.e., permit all the invocations regardless of the statistic.
+//! An override is described by an `OverrideRule`, which can be loaded from the data sources,
+//! or be created by `force_open()` and `force_close()` at runtime.
+//!
+//! The circuit breakers of the resource are transformed to the forced state at once, and the listeners are notified by
+//! `StateChangeListener::on_state_overridden()`. While the override is active, the invocations are not recorded by the circuit breakers.
+//! Once the override expires or is cleared, the circuit breakers resume from the forced state,
+//! i.e., a circuit breaker forced to Open probes on the next invocation.
+
+use super::State;
+use crate::{base::SentinelRule, Error};
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+cfg_k8s! {
+    use schemars::JsonSchema;
+    use kube::CustomResource;
+}
+
+/// `OverrideState` is the state the circuit breakers are pinned to.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize, Hash, Eq)]
+pub enum OverrideState {
+    /// `Open` rejects all the invocations on the resource
+    #[default]
+    Open,
+    /// `Closed` permits all the invocations on the resource
+    Closed,
+}
+
+impl From<OverrideState> for State {
+    fn from(state: OverrideState) -> Self {
+        match state {
+            OverrideState::Open => State::Open,
+            OverrideState::Closed => State::Closed,
+        }
+    }
+}
+
+/// `OverrideRule` pins the circuit breakers of a resource to the given state for a duration.
+#[cfg_attr(
+    feature = "ds_k8s",
+    derive(CustomResource, JsonSchema),
+    kube(
+        group = "rust.datasource.sentinel.io",
+        version = "v1alpha1",
+        kind = "CircuitBreakerOverrideResource",
+        namespaced
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverrideRule {
+    /// `id` represents the unique ID of the rule (optional).
+    pub id: String,
+    /// `resource` represents the target resource definition
+    pub resource: String,
+    /// `state` is the state the circuit breakers of the resource are pinned to
+    pub state: OverrideState,
+    /// `duration_ms` is the duration of the override since it is loaded,
+    /// 0 means the override lasts until it is cleared
+    pub duration_ms: u64,
+}
+
+impl Default for OverrideRule {
+    fn default() -> Self {
+        OverrideRule {
+            #[cfg(target_arch = "wasm32")]
+            id: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            id: uuid::Uuid::new_v4().to_string(),
+            resource: String::default(),
+            state: OverrideState::default(),
+            duration_ms: 0,
+        }
+    }
+}
+
+impl PartialEq for OverrideRule {
+    fn eq(&self, other: &Self) -> bool {
+        self.resource == other.resource
+            && self.state == other.state
+            && self.duration_ms == other.duration_ms
+    }
+}
+
+impl Eq for OverrideRule {}
+
+impl Hash for OverrideRule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.resource.hash(state);
+    }
+}
+
+impl SentinelRule for OverrideRule {
+    fn resource_name(&self) -> String {
+        self.resource.clone()
+    }
+
+    fn is_valid(&self) -> crate::Result<()> {
+        if self.resource.is_empty() {
+            return Err(Error::msg(
+                "empty resource of circuit breaker override rule",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for OverrideRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmtted = serde_json::to_string_pretty(self).unwrap();
+        write!(f, "{}", fmtted)
+    }
+}
+
+/// `ActiveOverride` is an override taking effect on a resource.
+#[derive(Debug, Clone)]
+pub(crate) struct ActiveOverride {
+    pub(crate) rule: Arc<OverrideRule>,
+    /// the time the override expires at, 0 means never
+    pub(crate) expire_at_ms: u64,
+}
+
+impl ActiveOverride {
+    pub(crate) fn new(rule: Arc<OverrideRule>, now: u64) -> Self {
+        let expire_at_ms = match rule.duration_ms {
+            0 => 0,
+            duration_ms => now + duration_ms,
+        };
+        ActiveOverride { rule, expire_at_ms }
+    }
+
+    pub(crate) fn is_expired(&self, now: u64) -> bool {
+        self.expire_at_ms > 0 && now >= self.expire_at_ms
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "empty resource of circuit breaker override rule")]
+    fn invalid_resource() {
+        let rule = OverrideRule::default();
+        rule.is_valid().unwrap();
+    }
+
+    #[test]
+    fn expiration() {
+        let forever = ActiveOverride::new(Arc::new(OverrideRule::default()), 1000);
+        assert!(!forever.is_expired(u64::MAX));
+        let rule = Arc::new(OverrideRule {
+            resource: "abc".into(),
+            duration_ms: 500,
+            ..Default::default()
+        });
+        let active = ActiveOverride::new(rule, 1000);
+        assert!(!active.is_expired(1499));
+        assert!(active.is_expired(1500));
+    }
+}
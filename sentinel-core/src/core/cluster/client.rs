@@ -0,0 +1,391 @@
+use super::{encode_message, read_message, TokenRequest, TokenResponse};
+use crate::{base::ParamKey, logging, utils, Error, Result};
+use std::collections::HashMap;
+use std::io::{BufReader, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// the interval to retry connecting to the token server after a failure, so that
+/// the invocations fall back to the local checking at once when the server is unreachable
+const DEFAULT_RECONNECT_INTERVAL_MS: u64 = 1000;
+
+type PendingMap = HashMap<u64, mpsc::SyncSender<TokenResponse>>;
+
+/// `Connection` pipelines the requests to the token server.
+/// The requests are written by a writer thread in the order they are sent,
+/// and the responses are dispatched to the waiting requests by the reader thread, according to their ids.
+/// Both threads exit once the connection is broken or dropped.
+#[derive(Debug)]
+struct Connection {
+    lines: mpsc::Sender<Vec<u8>>,
+    pending: Arc<Mutex<PendingMap>>,
+    broken: Arc<AtomicBool>,
+    /// used to shut down the connection, see `close()`
+    stream: TcpStream,
+}
+
+impl Connection {
+    fn open(addr: &str, timeout: Duration) -> Result<Self> {
+        let socket_addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::msg(format!("invalid token server address {}", addr)))?;
+        let mut stream = TcpStream::connect_timeout(&socket_addr, timeout)?;
+        stream.set_write_timeout(Some(timeout))?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let shutdown_stream = stream.try_clone()?;
+        let (lines, receiver) = mpsc::channel::<Vec<u8>>();
+        let pending = Arc::new(Mutex::new(PendingMap::new()));
+        let broken = Arc::new(AtomicBool::new(false));
+
+        {
+            let pending = pending.clone();
+            let broken = broken.clone();
+            thread::spawn(move || {
+                while let Ok(Some(response)) = read_message::<_, TokenResponse>(&mut reader) {
+                    // the requests timed out have been removed, so their responses are dropped
+                    if let Some(sender) = pending.lock().unwrap().remove(&response.id) {
+                        let _ = sender.try_send(response);
+                    }
+                }
+                let mut pending = pending.lock().unwrap();
+                broken.store(true, Ordering::SeqCst);
+                // wakes up the waiting requests
+                pending.clear();
+            });
+        }
+        {
+            let broken = broken.clone();
+            thread::spawn(move || {
+                for line in receiver {
+                    if stream.write_all(&line).is_err() {
+                        broken.store(true, Ordering::SeqCst);
+                        break;
+                    }
+                }
+                // stops the reader thread
+                let _ = stream.shutdown(Shutdown::Both);
+            });
+        }
+        Ok(Connection {
+            lines,
+            pending,
+            broken,
+            stream: shutdown_stream,
+        })
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::SeqCst)
+    }
+
+    /// `close` marks the connection as broken and shuts it down,
+    /// the other waiting requests are woken up once the reader thread exits.
+    fn close(&self) {
+        self.broken.store(true, Ordering::SeqCst);
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    fn send(&self, request: &TokenRequest, timeout: Duration) -> Result<TokenResponse> {
+        let line = encode_message(request)?;
+        let (sender, receiver) = mpsc::sync_channel(1);
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if self.is_broken() {
+                return Err(Error::msg("connection closed by the token server"));
+            }
+            pending.insert(request.id, sender);
+        }
+        if self.lines.send(line).is_err() {
+            self.pending.lock().unwrap().remove(&request.id);
+            return Err(Error::msg("connection closed by the token server"));
+        }
+        match receiver.recv_timeout(timeout) {
+            Ok(response) => Ok(response),
+            Err(RecvTimeoutError::Timeout) => {
+                self.pending.lock().unwrap().remove(&request.id);
+                // the token server accepting the requests without replying is as unavailable as an unreachable one,
+                // so the connection is dropped, and the invocations fall back to the local checking within the reconnect interval
+                self.close();
+                Err(Error::msg("token request timed out"))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(Error::msg("connection closed by the token server"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ConnectionState {
+    conn: Option<Arc<Connection>>,
+    /// whether a request is establishing the connection
+    connecting: bool,
+    retry_at_ms: u64,
+}
+
+/// `TokenClient` requests tokens from the token server, all the requests are pipelined on a single connection,
+/// so that the concurrent invocations do not wait for each other.
+#[derive(Debug)]
+pub struct TokenClient {
+    addr: String,
+    timeout: Duration,
+    reconnect_interval_ms: u64,
+    next_id: AtomicU64,
+    state: Mutex<ConnectionState>,
+}
+
+impl TokenClient {
+    /// `new` creates a client of the token server at `addr`, the connection is established lazily.
+    /// `timeout_ms` bounds the time of connecting and of each request,
+    /// i.e., the longest time a cluster mode rule blocks the calling thread.
+    pub fn new<S: Into<String>>(addr: S, timeout_ms: u64) -> Self {
+        TokenClient {
+            addr: addr.into(),
+            timeout: Duration::from_millis(timeout_ms),
+            reconnect_interval_ms: DEFAULT_RECONNECT_INTERVAL_MS,
+            next_id: AtomicU64::new(0),
+            state: Mutex::new(ConnectionState::default()),
+        }
+    }
+
+    pub fn with_reconnect_interval_ms(mut self, reconnect_interval_ms: u64) -> Self {
+        self.reconnect_interval_ms = reconnect_interval_ms;
+        self
+    }
+
+    pub fn addr(&self) -> &String {
+        &self.addr
+    }
+
+    /// `request_token` requests `acquire_count` tokens of the flow rule with `flow_id`,
+    /// it fails immediately if the token server was unreachable, or a request timed out, within the reconnect interval.
+    pub fn request_token(&self, flow_id: u64, acquire_count: u32) -> Result<TokenResponse> {
+        self.send(TokenRequest {
+            flow_id,
+            acquire_count,
+            ..Default::default()
+        })
+    }
+
+    /// `request_param_token` requests `acquire_count` tokens of the `param` for the hotspot rule with `flow_id`,
+    /// see `request_token()`.
+    pub fn request_param_token(
+        &self,
+        flow_id: u64,
+        acquire_count: u32,
+        param: ParamKey,
+    ) -> Result<TokenResponse> {
+        self.send(TokenRequest {
+            flow_id,
+            acquire_count,
+            param: Some(param),
+            ..Default::default()
+        })
+    }
+
+    /// `connection` returns the established connection, or establishes one.
+    /// The lock on the state is never held while connecting, and the other requests fall back
+    /// to the local checking at once rather than waiting for the connection.
+    fn connection(&self) -> Result<Arc<Connection>> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(conn) = &state.conn {
+                if !conn.is_broken() {
+                    return Ok(conn.clone());
+                }
+                state.conn = None;
+                state.retry_at_ms = utils::curr_time_millis() + self.reconnect_interval_ms;
+            }
+            if state.connecting || utils::curr_time_millis() < state.retry_at_ms {
+                return Err(Error::msg("token server is unreachable"));
+            }
+            state.connecting = true;
+        }
+        let conn = Connection::open(&self.addr, self.timeout);
+        let mut state = self.state.lock().unwrap();
+        state.connecting = false;
+        match conn {
+            Ok(conn) => {
+                let conn = Arc::new(conn);
+                state.conn = Some(conn.clone());
+                Ok(conn)
+            }
+            Err(err) => {
+                logging::warn!(
+                    "[TokenClient] Failed to connect to the token server {}, reason: {:?}",
+                    self.addr,
+                    err
+                );
+                state.retry_at_ms = utils::curr_time_millis() + self.reconnect_interval_ms;
+                Err(err)
+            }
+        }
+    }
+
+    fn send(&self, mut request: TokenRequest) -> Result<TokenResponse> {
+        let conn = self.connection()?;
+        request.id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let response = conn.send(&request, self.timeout);
+        if conn.is_broken() {
+            // the reconnect interval applies at once, rather than on the next request
+            let mut state = self.state.lock().unwrap();
+            if state.conn.as_ref().is_some_and(|c| Arc::ptr_eq(c, &conn)) {
+                state.conn = None;
+                state.retry_at_ms = utils::curr_time_millis() + self.reconnect_interval_ms;
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{write_message, ClusterConfig, TokenServer, TokenService, TokenStatus};
+    use super::*;
+    use crate::{flow, hotspot};
+    use std::net::TcpListener;
+
+    #[test]
+    fn request_token() {
+        let service = Arc::new(TokenService::new());
+        service.load_flow_rules(vec![Arc::new(flow::Rule {
+            resource: "abc".into(),
+            threshold: 2.0,
+            cluster_mode: true,
+            cluster_config: ClusterConfig { flow_id: 1 },
+            ..Default::default()
+        })]);
+        service.load_hotspot_rules(vec![Arc::new(hotspot::Rule {
+            resource: "abc".into(),
+            metric_type: hotspot::MetricType::QPS,
+            threshold: 1,
+            duration_in_sec: 1,
+            cluster_mode: true,
+            cluster_config: ClusterConfig { flow_id: 1 },
+            ..Default::default()
+        })]);
+        let server = TokenServer::start("127.0.0.1:0", service).unwrap();
+        let client =
+            TokenClient::new(server.local_addr().to_string(), 1000).with_reconnect_interval_ms(0);
+        let another = TokenClient::new(server.local_addr().to_string(), 1000);
+
+        assert_eq!(client.request_token(1, 1).unwrap().status, TokenStatus::Ok);
+        assert_eq!(another.request_token(1, 1).unwrap().status, TokenStatus::Ok);
+        // the threshold is shared by the clients
+        assert_eq!(
+            client.request_token(1, 1).unwrap().status,
+            TokenStatus::Blocked
+        );
+        assert_eq!(
+            another.request_token(2, 1).unwrap().status,
+            TokenStatus::NoRuleExists
+        );
+        assert_eq!(
+            client.request_param_token(1, 1, "a".into()).unwrap().status,
+            TokenStatus::Ok
+        );
+        assert_eq!(
+            another
+                .request_param_token(1, 1, "a".into())
+                .unwrap()
+                .status,
+            TokenStatus::Blocked
+        );
+
+        server.stop();
+        assert!(client.request_token(1, 1).is_err());
+        assert!(client.request_token(1, 1).is_err());
+    }
+
+    #[test]
+    fn unreachable() {
+        // reserves a port without listening on it
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = TokenClient::new(addr.to_string(), 100);
+        assert!(client.request_token(1, 1).is_err());
+        // fails fast within the reconnect interval
+        let start = utils::curr_time_millis();
+        assert!(client.request_token(1, 1).is_err());
+        assert!(utils::curr_time_millis() - start < 50);
+    }
+
+    #[test]
+    fn silent_server() {
+        // the token server accepts the connections and reads the requests, but never replies
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                thread::spawn(move || {
+                    while let Ok(Some(_)) = read_message::<_, TokenRequest>(&mut reader) {}
+                });
+            }
+        });
+        let client = TokenClient::new(addr.to_string(), 100).with_reconnect_interval_ms(300);
+        assert!(client.request_token(1, 1).is_err());
+        // the connection is dropped, and the requests fail fast within the reconnect interval
+        let start = utils::curr_time_millis();
+        for _ in 0..10 {
+            assert!(client.request_token(1, 1).is_err());
+        }
+        assert!(utils::curr_time_millis() - start < 50);
+        assert!(client.state.lock().unwrap().conn.is_none());
+
+        // reconnects after the interval
+        utils::sleep_for_ms(300);
+        assert!(client.request_token(1, 1).is_err());
+        assert!(client.state.lock().unwrap().retry_at_ms > start + 300);
+    }
+
+    #[test]
+    fn slow_server() {
+        // the token server replies each request after 200ms, without keeping the order of the requests
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let writer = Arc::new(Mutex::new(stream.try_clone().unwrap()));
+            let mut reader = BufReader::new(stream);
+            while let Ok(Some(request)) = read_message::<_, TokenRequest>(&mut reader) {
+                let writer = writer.clone();
+                thread::spawn(move || {
+                    utils::sleep_for_ms(200);
+                    let response = TokenResponse {
+                        id: request.id,
+                        ..TokenResponse::new(TokenStatus::Ok, request.acquire_count as u64)
+                    };
+                    let _ = write_message(&mut *writer.lock().unwrap(), &response);
+                });
+            }
+        });
+        let client = Arc::new(TokenClient::new(addr.to_string(), 1000));
+        assert_eq!(client.request_token(1, 1).unwrap().remaining, 1);
+
+        let start = utils::curr_time_millis();
+        let handles: Vec<_> = (1..=8)
+            .map(|acquire_count| {
+                let client = client.clone();
+                thread::spawn(move || {
+                    let response = client.request_token(1, acquire_count).unwrap();
+                    // each request gets its own response
+                    assert_eq!(response.remaining, acquire_count as u64);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        // the requests are pipelined, rather than waiting for each other
+        assert!(utils::curr_time_millis() - start < 600);
+    }
+}
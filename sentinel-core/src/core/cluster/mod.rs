@@ -0,0 +1,62 @@
+//! mod cluster provides the cluster flow control, so that the rules limit the invocations of all the instances as a whole.
+//!
+//! The `TokenServer` owns the global statistic of the cluster mode rules, which are keyed by the `flow_id` in their `ClusterConfig`,
+//! and the `TokenClient` in each instance requests tokens from the token server, instead of checking the local statistic.
+//! Both the flow rules and the hotspot rules are supported, and the tokens of hotspot rules are counted for each parameter.
+//! The `flow_id` of flow rules and hotspot rules are in separate namespaces.
+//! The token server can be embedded in one of the instances, or deployed independently.
+//!
+//! The client and the server talk in a lightweight protocol over TCP, see `protocol` for details.
+//! The token client is set on the flow and hotspot rule managers of each Sentinel instance,
+//! and the free functions here operate on the default instance.
+//! When there is no token client, or the token server is unreachable, the rules fall back to the local checking.
+//!
+//! Note that the tokens are requested by a blocking round trip inside the rule checking slots,
+//! so each cluster mode rule blocks the calling thread for up to the timeout of the `TokenClient`,
+//! even in `EntryBuilder::build_async()`. Keep the timeout short when the cluster mode rules protect async services.
+//! Once a request times out, the connection is dropped and the rules fall back to the local checking at once
+//! within the reconnect interval, so an unresponsive token server costs at most one timeout per interval.
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+pub use client::*;
+pub use protocol::*;
+pub use server::*;
+
+use crate::{flow, hotspot};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+cfg_k8s! {
+    use schemars::JsonSchema;
+}
+
+/// `ClusterConfig` describes how a rule is checked in the cluster mode.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusterConfig {
+    /// `flow_id` is the globally unique id of the rule in the cluster,
+    /// the rules sharing the same `flow_id` in different instances share the same global statistic.
+    pub flow_id: u64,
+}
+
+/// `set_token_client` sets the token client used by the cluster mode rules of the default Sentinel instance,
+/// see `Sentinel::set_token_client()` for the other instances.
+pub fn set_token_client(client: Arc<TokenClient>) {
+    flow::default_rule_manager().set_token_client(client.clone());
+    hotspot::default_rule_manager().set_token_client(client);
+}
+
+/// `clear_token_client` removes the token client of the default Sentinel instance,
+/// then the cluster mode rules fall back to the local checking.
+pub fn clear_token_client() {
+    flow::default_rule_manager().clear_token_client();
+    hotspot::default_rule_manager().clear_token_client();
+}
+
+/// `token_client` returns the token client of the default Sentinel instance.
+pub fn token_client() -> Option<Arc<TokenClient>> {
+    flow::default_rule_manager().token_client()
+}
@@ -0,0 +1,128 @@
+//! The protocol between the token client and the token server.
+//!
+//! Each message is a single line of JSON. The client may send several `TokenRequest`s
+//! on the same connection without waiting for the responses, and the token server replies
+//! each of them with a `TokenResponse` carrying the same `id`.
+
+use crate::{base::ParamKey, Error, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenRequest {
+    /// `id` identifies the request on the connection, it is assigned by the token client.
+    #[serde(default)]
+    pub id: u64,
+    pub flow_id: u64,
+    pub acquire_count: u32,
+    /// `param` is the hot parameter of the hotspot rules, it is `None` for the flow rules.
+    /// The bytes which are not valid UTF-8 are encoded in hex with the `hex:` prefix, see `ParamKey`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub param: Option<ParamKey>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenStatus {
+    /// `Ok` means the tokens are acquired.
+    Ok,
+    /// `Blocked` means the global threshold of the rule is exceeded.
+    Blocked,
+    /// `NoRuleExists` means the token server has no rule with the `flow_id`.
+    NoRuleExists,
+    /// `BadRequest` means the request is invalid, e.g., acquiring no tokens.
+    BadRequest,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenResponse {
+    /// `id` is the id of the request replied.
+    #[serde(default)]
+    pub id: u64,
+    pub status: TokenStatus,
+    /// `remaining` is the number of the tokens left in current statistic interval.
+    pub remaining: u64,
+}
+
+impl TokenResponse {
+    pub fn new(status: TokenStatus, remaining: u64) -> Self {
+        TokenResponse {
+            id: 0,
+            status,
+            remaining,
+        }
+    }
+}
+
+/// `encode_message` encodes the message as a line of JSON.
+pub(crate) fn encode_message<T: Serialize>(msg: &T) -> Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(msg)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// `write_message` writes the message as a line of JSON.
+pub(crate) fn write_message<W: Write, T: Serialize>(writer: &mut W, msg: &T) -> Result<()> {
+    writer.write_all(&encode_message(msg)?)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `read_message` reads a line of JSON as the message, it returns `None` if the connection is closed.
+pub(crate) fn read_message<R: BufRead, T: DeserializeOwned>(reader: &mut R) -> Result<Option<T>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(line.trim_end())
+        .map(Some)
+        .map_err(Error::msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn codec() {
+        let request = TokenRequest {
+            id: 1,
+            flow_id: 1,
+            acquire_count: 2,
+            param: None,
+        };
+        let param_request = TokenRequest {
+            id: 2,
+            param: Some("user".into()),
+            ..request.clone()
+        };
+        let mut buf = Vec::new();
+        write_message(&mut buf, &request).unwrap();
+        write_message(&mut buf, &param_request).unwrap();
+        let mut reader = Cursor::new(buf);
+        let decoded: Option<TokenRequest> = read_message(&mut reader).unwrap();
+        assert_eq!(decoded, Some(request));
+        let decoded: Option<TokenRequest> = read_message(&mut reader).unwrap();
+        assert_eq!(decoded, Some(param_request));
+        let decoded: Option<TokenRequest> = read_message(&mut reader).unwrap();
+        assert!(decoded.is_none());
+
+        let bytes_request = TokenRequest {
+            id: 3,
+            flow_id: 1,
+            acquire_count: 2,
+            param: Some(vec![0xff, 0xfe].into()),
+        };
+        let mut buf = Vec::new();
+        write_message(&mut buf, &bytes_request).unwrap();
+        let mut reader = Cursor::new(buf);
+        let decoded: Option<TokenRequest> = read_message(&mut reader).unwrap();
+        assert!(matches!(
+            decoded.and_then(|r| r.param),
+            Some(ParamKey::Bytes(b)) if b == [0xff, 0xfe]
+        ));
+
+        let mut reader = Cursor::new(b"{\"flow_id\":1}\n".to_vec());
+        assert!(read_message::<_, TokenRequest>(&mut reader).is_err());
+    }
+}
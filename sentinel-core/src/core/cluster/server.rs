@@ -0,0 +1,404 @@
+use super::{read_message, write_message, TokenRequest, TokenResponse, TokenStatus};
+use crate::{
+    base::{MetricEvent, ParamKey, WriteStat},
+    flow, hotspot, logging,
+    stat::BucketLeapArray,
+    utils, Result,
+};
+use lru::LruCache;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+const DEFAULT_STAT_INTERVAL_MS: u32 = 1000;
+const DEFAULT_SAMPLE_COUNT: u32 = 10;
+
+/// `ClusterFlow` holds the global statistic of a cluster mode rule.
+#[derive(Debug)]
+struct ClusterFlow {
+    rule: Arc<flow::Rule>,
+    stat: BucketLeapArray,
+    // makes the checking and the counting atomic, since the requests come from the whole cluster
+    lock: Mutex<()>,
+}
+
+impl ClusterFlow {
+    fn new(rule: Arc<flow::Rule>) -> Result<Self> {
+        let interval_ms = match rule.stat_interval_ms {
+            0 => DEFAULT_STAT_INTERVAL_MS,
+            interval_ms => interval_ms,
+        };
+        let sample_count = if interval_ms % DEFAULT_SAMPLE_COUNT == 0 {
+            DEFAULT_SAMPLE_COUNT
+        } else {
+            1
+        };
+        Ok(ClusterFlow {
+            rule,
+            stat: BucketLeapArray::new(sample_count, interval_ms)?,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn acquire(&self, acquire_count: u32) -> TokenResponse {
+        let _guard = self.lock.lock().unwrap();
+        let threshold = self.rule.threshold as u64;
+        let passed = self.stat.count(MetricEvent::Pass);
+        let acquire_count = acquire_count as u64;
+        if passed + acquire_count > threshold {
+            return TokenResponse::new(TokenStatus::Blocked, threshold.saturating_sub(passed));
+        }
+        self.stat.add_count(MetricEvent::Pass, acquire_count);
+        TokenResponse::new(TokenStatus::Ok, threshold - passed - acquire_count)
+    }
+}
+
+/// `ClusterParamFlow` holds the global statistic of each parameter for a cluster mode hotspot rule,
+/// the tokens are counted in fixed windows of `duration_in_sec`.
+#[derive(Debug)]
+struct ClusterParamFlow {
+    rule: Arc<hotspot::Rule>,
+    // the start time of current window and the passed count in it
+    counters: Mutex<LruCache<ParamKey, (u64, u64)>>,
+}
+
+impl ClusterParamFlow {
+    fn new(rule: Arc<hotspot::Rule>) -> Self {
+        let capacity = if rule.params_max_capacity > 0 {
+            rule.params_max_capacity
+        } else {
+            min(
+                hotspot::PARAMS_MAX_CAPACITY,
+                hotspot::PARAMS_CAPACITY_BASE * rule.duration_in_sec as usize,
+            )
+        };
+        ClusterParamFlow {
+            rule,
+            counters: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn acquire(&self, param: ParamKey, acquire_count: u32) -> TokenResponse {
+        let threshold = self
+            .rule
+            .specific_items
+            .get(&param)
+            .copied()
+            .unwrap_or(self.rule.threshold)
+            + self.rule.burst_count;
+        let window_ms = self.rule.duration_in_sec * 1000;
+        let now = utils::curr_time_millis();
+        let acquire_count = acquire_count as u64;
+
+        let mut counters = self.counters.lock().unwrap();
+        let (window_start, passed) = match counters.get(&param) {
+            Some(&(window_start, passed)) if now < window_start + window_ms => {
+                (window_start, passed)
+            }
+            _ => (now, 0),
+        };
+        if passed + acquire_count > threshold {
+            counters.put(param, (window_start, passed));
+            return TokenResponse::new(TokenStatus::Blocked, threshold.saturating_sub(passed));
+        }
+        counters.put(param, (window_start, passed + acquire_count));
+        TokenResponse::new(TokenStatus::Ok, threshold - passed - acquire_count)
+    }
+}
+
+/// `TokenService` checks the token requests against the global statistic of the cluster mode rules.
+#[derive(Debug, Default)]
+pub struct TokenService {
+    flows: RwLock<HashMap<u64, Arc<ClusterFlow>>>,
+    param_flows: RwLock<HashMap<u64, Arc<ClusterParamFlow>>>,
+}
+
+impl TokenService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `load_flow_rules` replaces the cluster mode flow rules served by the token server,
+    /// the rules not in cluster mode are ignored. The statistic is kept for the unchanged rules.
+    pub fn load_flow_rules(&self, rules: Vec<Arc<flow::Rule>>) {
+        let mut flows = self.flows.write().unwrap();
+        let mut new_flows = HashMap::with_capacity(rules.len());
+        for rule in rules {
+            if !rule.cluster_mode {
+                continue;
+            }
+            let flow_id = rule.cluster_config.flow_id;
+            if let Some(flow) = flows.get(&flow_id) {
+                if flow.rule == rule {
+                    new_flows.insert(flow_id, flow.clone());
+                    continue;
+                }
+            }
+            match ClusterFlow::new(rule.clone()) {
+                Ok(flow) => {
+                    new_flows.insert(flow_id, Arc::new(flow));
+                }
+                Err(err) => logging::warn!(
+                    "[TokenService] Ignoring invalid cluster flow rule {:?}, reason: {:?}",
+                    rule,
+                    err
+                ),
+            }
+        }
+        *flows = new_flows;
+    }
+
+    /// `load_hotspot_rules` replaces the cluster mode hotspot rules served by the token server,
+    /// the rules not in cluster mode are ignored. The statistic is kept for the unchanged rules.
+    pub fn load_hotspot_rules(&self, rules: Vec<Arc<hotspot::Rule>>) {
+        let mut param_flows = self.param_flows.write().unwrap();
+        let mut new_param_flows = HashMap::with_capacity(rules.len());
+        for rule in rules {
+            if !rule.cluster_mode {
+                continue;
+            }
+            let flow_id = rule.cluster_config.flow_id;
+            let param_flow = match param_flows.get(&flow_id) {
+                Some(param_flow) if param_flow.rule == rule => param_flow.clone(),
+                _ => Arc::new(ClusterParamFlow::new(rule)),
+            };
+            new_param_flows.insert(flow_id, param_flow);
+        }
+        *param_flows = new_param_flows;
+    }
+
+    pub fn request_token(&self, request: &TokenRequest) -> TokenResponse {
+        if request.acquire_count == 0 {
+            return TokenResponse::new(TokenStatus::BadRequest, 0);
+        }
+        if let Some(param) = &request.param {
+            let param_flow = self
+                .param_flows
+                .read()
+                .unwrap()
+                .get(&request.flow_id)
+                .cloned();
+            return match param_flow {
+                Some(param_flow) => param_flow.acquire(param.clone(), request.acquire_count),
+                None => TokenResponse::new(TokenStatus::NoRuleExists, 0),
+            };
+        }
+        let flow = self.flows.read().unwrap().get(&request.flow_id).cloned();
+        match flow {
+            Some(flow) => flow.acquire(request.acquire_count),
+            None => TokenResponse::new(TokenStatus::NoRuleExists, 0),
+        }
+    }
+}
+
+/// `TokenServer` serves the token requests from the token clients over TCP,
+/// each connection is handled in its own thread. The server stops when it is dropped.
+#[derive(Debug)]
+pub struct TokenServer {
+    local_addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    connections: Arc<Mutex<HashMap<usize, TcpStream>>>,
+}
+
+impl TokenServer {
+    /// `start` binds the address and serves the requests with the `service` in the background.
+    pub fn start<A: ToSocketAddrs>(addr: A, service: Arc<TokenService>) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let connections = Arc::new(Mutex::new(HashMap::new()));
+        {
+            let stopped = stopped.clone();
+            let connections = connections.clone();
+            thread::spawn(move || {
+                for (id, stream) in listener.incoming().enumerate() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            logging::warn!("[TokenServer] Failed to accept, reason: {:?}", err);
+                            continue;
+                        }
+                    };
+                    match stream.try_clone() {
+                        Ok(cloned) => {
+                            connections.lock().unwrap().insert(id, cloned);
+                        }
+                        Err(err) => {
+                            logging::warn!("[TokenServer] Failed to accept, reason: {:?}", err);
+                            continue;
+                        }
+                    }
+                    let service = service.clone();
+                    let connections = connections.clone();
+                    thread::spawn(move || {
+                        serve(stream, service);
+                        connections.lock().unwrap().remove(&id);
+                    });
+                }
+            });
+        }
+        logging::info!("[TokenServer] Token server is listening on {}", local_addr);
+        Ok(TokenServer {
+            local_addr,
+            stopped,
+            connections,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// `stop` stops accepting new connections and closes the established ones.
+    pub fn stop(&self) {
+        if self.stopped.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // wakes up the blocking listener
+        let _ = TcpStream::connect(self.local_addr);
+        for (_, stream) in self.connections.lock().unwrap().drain() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        logging::info!(
+            "[TokenServer] Token server on {} is stopped",
+            self.local_addr
+        );
+    }
+}
+
+impl Drop for TokenServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn serve(stream: TcpStream, service: Arc<TokenService>) {
+    let _ = stream.set_nodelay(true);
+    let mut writer = match stream.try_clone() {
+        Ok(stream) => BufWriter::new(stream),
+        Err(_) => return,
+    };
+    let mut reader = BufReader::new(stream);
+    loop {
+        let response = match read_message::<_, TokenRequest>(&mut reader) {
+            // the client matches the responses to the pipelined requests by the id
+            Ok(Some(request)) => TokenResponse {
+                id: request.id,
+                ..service.request_token(&request)
+            },
+            Ok(None) => return,
+            Err(err) => {
+                // the connection is broken, or the client does not speak the protocol
+                logging::debug!("[TokenServer] Closing the connection, reason: {:?}", err);
+                return;
+            }
+        };
+        if write_message(&mut writer, &response).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cluster_rule(flow_id: u64, threshold: f64) -> Arc<flow::Rule> {
+        Arc::new(flow::Rule {
+            resource: "abc".into(),
+            threshold,
+            cluster_mode: true,
+            cluster_config: super::super::ClusterConfig { flow_id },
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn request_token() {
+        let service = TokenService::new();
+        service.load_flow_rules(vec![
+            cluster_rule(1, 3.0),
+            Arc::new(flow::Rule {
+                resource: "abc".into(),
+                threshold: 3.0,
+                ..Default::default()
+            }),
+        ]);
+        let request = |flow_id, acquire_count| {
+            service.request_token(&TokenRequest {
+                flow_id,
+                acquire_count,
+                ..Default::default()
+            })
+        };
+        assert_eq!(request(1, 2), TokenResponse::new(TokenStatus::Ok, 1));
+        assert_eq!(request(1, 2), TokenResponse::new(TokenStatus::Blocked, 1));
+        assert_eq!(request(1, 1), TokenResponse::new(TokenStatus::Ok, 0));
+        assert_eq!(request(1, 0).status, TokenStatus::BadRequest);
+        assert_eq!(request(0, 1).status, TokenStatus::NoRuleExists);
+
+        // the statistic is kept for the unchanged rules
+        service.load_flow_rules(vec![cluster_rule(1, 3.0), cluster_rule(2, 3.0)]);
+        assert_eq!(request(1, 1).status, TokenStatus::Blocked);
+        assert_eq!(request(2, 1).status, TokenStatus::Ok);
+        service.load_flow_rules(vec![cluster_rule(1, 5.0)]);
+        assert_eq!(request(1, 1).status, TokenStatus::Ok);
+        assert_eq!(request(2, 1).status, TokenStatus::NoRuleExists);
+    }
+
+    #[test]
+    fn request_param_token() {
+        let service = TokenService::new();
+        let mut specific_items = HashMap::new();
+        specific_items.insert("vip".into(), 3);
+        service.load_hotspot_rules(vec![Arc::new(hotspot::Rule {
+            resource: "abc".into(),
+            metric_type: hotspot::MetricType::QPS,
+            threshold: 1,
+            duration_in_sec: 1,
+            specific_items,
+            cluster_mode: true,
+            cluster_config: super::super::ClusterConfig { flow_id: 1 },
+            ..Default::default()
+        })]);
+        let request = |param: &str| {
+            service
+                .request_token(&TokenRequest {
+                    flow_id: 1,
+                    acquire_count: 1,
+                    param: Some(param.into()),
+                    ..Default::default()
+                })
+                .status
+        };
+        // each parameter has its own quota
+        assert_eq!(request("a"), TokenStatus::Ok);
+        assert_eq!(request("a"), TokenStatus::Blocked);
+        assert_eq!(request("b"), TokenStatus::Ok);
+        for _ in 0..3 {
+            assert_eq!(request("vip"), TokenStatus::Ok);
+        }
+        assert_eq!(request("vip"), TokenStatus::Blocked);
+        // the flow ids of flow rules are in a separate namespace
+        assert_eq!(
+            service
+                .request_token(&TokenRequest {
+                    flow_id: 1,
+                    acquire_count: 1,
+                    ..Default::default()
+                })
+                .status,
+            TokenStatus::NoRuleExists
+        );
+        // a new window starts
+        utils::sleep_for_ms(1000);
+        assert_eq!(request("a"), TokenStatus::Ok);
+    }
+}
@@ -13,6 +13,16 @@
 //!  1. The function both `set_traffic_shaping_generator()` and `remove_traffic_shaping_generator()` are not thread safe.
 //!  2. Users can not override the Sentinel supported TrafficShapingController.
 //!
+//! Flow rules can also be origin-aware. The origin (caller) of an invocation is set by `EntryBuilder::with_origin()`,
+//! and the `limit_origin` of a rule decides which origins it limits, i.e., a specific origin, `LIMIT_ORIGIN_DEFAULT` (all origins as a whole)
+//! or `LIMIT_ORIGIN_OTHER` (each origin not specified by other rules of the resource).
+//!
+//! With `RelationStrategy::Chain`, a rule only limits the invocations under the invocation context (entrance) named `ref_resource`,
+//! which is entered by `enter_context()` or set by `EntryBuilder::with_context_name()`.
+//!
+//! Rules in `cluster_mode` request the tokens from the token server in the `cluster` mod, so that the threshold is shared
+//! by all the instances. They fall back to the local checking when the token server is not available.
+//!
 //!
 
 pub mod rule;
@@ -1,4 +1,8 @@
-use crate::{base::SentinelRule, logging, system_metric, Error};
+use crate::{
+    base::{MatchStrategy, PatternRule, RuleMode, Schedule, SentinelRule},
+    cluster::ClusterConfig,
+    logging, system_metric, Error,
+};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fmt;
@@ -10,6 +14,12 @@ cfg_k8s! {
 
 pub type Id = String;
 
+/// `LIMIT_ORIGIN_DEFAULT` means the rule limits the invocations from all origins as a whole
+pub const LIMIT_ORIGIN_DEFAULT: &str = "default";
+/// `LIMIT_ORIGIN_OTHER` means the rule limits the invocations from each origin,
+/// which is not limited by any rule with specific `limit_origin` of the same resource
+pub const LIMIT_ORIGIN_OTHER: &str = "other";
+
 /// RelationStrategy indicates the flow control strategy based on the relation of invocations.
 #[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -18,6 +28,12 @@ pub enum RelationStrategy {
     Current,
     /// Associated means flow control by the associated resource rather than current resource.
     Associated,
+    /// Chain means flow control only takes effect on the invocations under the context (entrance) `ref_resource`,
+    /// by the statistic of current resource under this context.
+    /// The statistic is kept per (entrance, resource) pair, regardless of the resources invoked in between,
+    /// see `stat::EntranceNode`. Only `ControlStrategy::Reject` with the `Direct`, `MemoryAdaptive` or `CpuAdaptive`
+    /// calculate strategy is supported.
+    Chain,
 }
 
 impl Default for RelationStrategy {
@@ -32,6 +48,11 @@ pub enum CalculateStrategy {
     Direct,
     WarmUp,
     MemoryAdaptive,
+    /// CpuAdaptive interpolates the threshold between the CPU usage water marks.
+    CpuAdaptive,
+    /// TokenBucket allows bursts up to `burst` invocations,
+    /// while the steady-state rate is limited by `refill_rate`.
+    TokenBucket,
     #[serde(skip)]
     Custom(u8),
 }
@@ -47,7 +68,8 @@ impl Default for CalculateStrategy {
 pub enum ControlStrategy {
     Reject,
     /// Throttling indicates that pending requests will be throttled,
-    /// wait in queue (until free capacity is available)
+    /// wait in queue (until free capacity is available).
+    /// With `CalculateStrategy::WarmUp`, the queueing interval follows the warm-up curve.
     Throttling,
     #[serde(skip)]
     Custom(u8),
@@ -77,10 +99,20 @@ pub struct Rule {
     pub id: Id,
     /// `resource` represents the resource name.
     pub resource: String,
+    /// `match_strategy` indicates how `resource` matches the resource names,
+    /// a glob or regex pattern makes the rule cover a family of resources.
+    pub match_strategy: MatchStrategy,
     pub ref_resource: String,
     pub calculate_strategy: CalculateStrategy,
     pub control_strategy: ControlStrategy,
     pub relation_strategy: RelationStrategy,
+    /// `limit_origin` indicates the origin (caller) limited by this rule,
+    /// it can be a specific origin, `LIMIT_ORIGIN_DEFAULT` or `LIMIT_ORIGIN_OTHER`.
+    /// Rules with specific origin or `LIMIT_ORIGIN_OTHER` are checked on the statistic of the origin
+    /// with the default metric statistic of resource, regardless of `stat_interval_ms`.
+    /// They only support `ControlStrategy::Reject` with the `Direct`, `MemoryAdaptive` or `CpuAdaptive` calculate strategy,
+    /// since the state of the other strategies (e.g., the last passed time of throttling) is not kept per origin.
+    pub limit_origin: String,
     /// `threshold` means the threshold during stat_interval_ms
     /// If `stat_interval_ms` is 1000(1 second), `threshold` means QPS
     pub threshold: f64,
@@ -90,6 +122,11 @@ pub struct Rule {
     /// When `max_queueing_time_ms` is 0, it means Throttling only controls interval of requests,
     /// and requests exceeding the threshold will be rejected directly.
     pub max_queueing_time_ms: u32,
+    /// `max_queue_size` only takes effect when `control_strategy` is Throttling.
+    /// It bounds the number of queueing requests, which pass in FIFO order,
+    /// and the requests arriving at a full queue will be rejected directly.
+    /// When `max_queue_size` is 0, the queue is only bounded by `max_queueing_time_ms`.
+    pub max_queue_size: u32,
     /// stat_interval_ms indicates the statistic interval and it's the optional setting for flow Rule.
     /// If user doesn't set stat_interval_ms, that means using default metric statistic of resource.
     /// If the stat_interval_ms user specifies can not reuse the global statistic of resource,
@@ -105,6 +142,32 @@ pub struct Rule {
     pub high_mem_usage_threshold: u64,
     pub mem_low_water_mark: u64,
     pub mem_high_water_mark: u64,
+
+    /// cpu adaptive flow control algorithm related parameters, similar to the memory adaptive ones,
+    /// the water marks are CPU usage percentages in [0.0, 100.0]
+    /// limitation: low_cpu_usage_threshold > high_cpu_usage_threshold && cpu_high_water_mark > cpu_low_water_mark
+    pub low_cpu_usage_threshold: u64,
+    pub high_cpu_usage_threshold: u64,
+    pub cpu_low_water_mark: f64,
+    pub cpu_high_water_mark: f64,
+
+    /// token bucket related parameters, only take effect when `calculate_strategy` is TokenBucket
+    /// - `burst` is the capacity of the bucket, i.e., the max invocations passing at once after being idle,
+    ///   the invocations with a `batch_count` larger than `burst` are always blocked
+    /// - `refill_rate` is the number of tokens refilled per second, i.e., the steady-state QPS
+    pub burst: u32,
+    pub refill_rate: f64,
+    /// `schedule` indicates when the rule is active, the rule is always active if it is `None`.
+    pub schedule: Option<Schedule>,
+    /// `cluster_mode` indicates whether the rule is checked against the global statistic in the token server,
+    /// it falls back to the local checking when the token server is not available.
+    /// Only `CalculateStrategy::Direct` with `ControlStrategy::Reject` is supported in cluster mode.
+    /// The tokens are requested synchronously, which blocks the calling thread, see `cluster` for details.
+    pub cluster_mode: bool,
+    pub cluster_config: ClusterConfig,
+    /// `mode` indicates whether the invocations exceeding the threshold are blocked,
+    /// or only recorded in shadow (dry-run) mode.
+    pub mode: RuleMode,
 }
 
 impl Hash for Rule {
@@ -123,19 +186,32 @@ impl Default for Rule {
             #[cfg(not(target_arch = "wasm32"))]
             id: uuid::Uuid::new_v4().to_string(),
             resource: String::default(),
+            match_strategy: MatchStrategy::default(),
             ref_resource: String::default(),
             calculate_strategy: CalculateStrategy::default(),
             control_strategy: ControlStrategy::default(),
             relation_strategy: RelationStrategy::default(),
+            limit_origin: LIMIT_ORIGIN_DEFAULT.into(),
             threshold: 0.0,
             warm_up_period_sec: 0,
             warm_up_cold_factor: 0,
             max_queueing_time_ms: 0,
+            max_queue_size: 0,
             stat_interval_ms: 0,
             low_mem_usage_threshold: 0,
             high_mem_usage_threshold: 0,
             mem_low_water_mark: 0,
             mem_high_water_mark: 0,
+            low_cpu_usage_threshold: 0,
+            high_cpu_usage_threshold: 0,
+            cpu_low_water_mark: 0.0,
+            cpu_high_water_mark: 0.0,
+            burst: 0,
+            refill_rate: 0.0,
+            schedule: None,
+            cluster_mode: false,
+            cluster_config: ClusterConfig::default(),
+            mode: RuleMode::default(),
         }
     }
 }
@@ -146,13 +222,43 @@ impl Rule {
             && self.relation_strategy == other.relation_strategy
             && self.ref_resource == other.ref_resource
             && self.stat_interval_ms == other.stat_interval_ms
+            && self.limit_origin == other.limit_origin
             && self.need_statistic()
             && other.need_statistic()
     }
 
+    /// `is_default_origin` indicates whether the rule limits all the origins as a whole
+    pub fn is_default_origin(&self) -> bool {
+        self.limit_origin.is_empty() || self.limit_origin == LIMIT_ORIGIN_DEFAULT
+    }
+
+    /// `is_checked_on_selected_node` indicates whether the rule is checked on the statistic node selected per invocation,
+    /// i.e., the node of the origin or the node under the invocation context,
+    /// rather than the standalone statistic of the traffic controller
+    pub fn is_checked_on_selected_node(&self) -> bool {
+        !self.is_default_origin() || self.relation_strategy == RelationStrategy::Chain
+    }
+
+    /// `is_other_origin` indicates whether the rule limits the origins not specified by other rules
+    pub fn is_other_origin(&self) -> bool {
+        self.limit_origin == LIMIT_ORIGIN_OTHER
+    }
+
     pub fn need_statistic(&self) -> bool {
-        self.calculate_strategy == CalculateStrategy::WarmUp
-            || self.control_strategy == ControlStrategy::Reject
+        // the token bucket checks its own tokens rather than the statistic
+        self.calculate_strategy != CalculateStrategy::TokenBucket
+            && (self.calculate_strategy == CalculateStrategy::WarmUp
+                || self.control_strategy == ControlStrategy::Reject)
+    }
+}
+
+impl PatternRule for Rule {
+    fn match_strategy(&self) -> MatchStrategy {
+        self.match_strategy
+    }
+
+    fn resource(&self) -> &str {
+        &self.resource
     }
 }
 
@@ -165,12 +271,38 @@ impl SentinelRule for Rule {
         if self.resource.is_empty() {
             return Err(Error::msg("empty resource name"));
         }
+        if self.match_strategy.is_pattern() {
+            self.match_strategy.compile(&self.resource)?;
+        }
         if self.threshold < 0.0 {
             return Err(Error::msg("negative threshold"));
         }
+        if let Some(schedule) = &self.schedule {
+            schedule.is_valid()?;
+        }
         if self.relation_strategy == RelationStrategy::Associated && self.ref_resource.is_empty() {
             return Err(Error::msg("ref_resource must be non empty when relation_strategy is RelationStrategy::Associated"));
         }
+        if self.relation_strategy == RelationStrategy::Chain && self.ref_resource.is_empty() {
+            return Err(Error::msg(
+                "ref_resource must be non empty when relation_strategy is RelationStrategy::Chain",
+            ));
+        }
+        // the controllers of origin-aware and chain rules are shared by the origins or contexts,
+        // so only the strategies checking the selected statistic node directly are supported
+        if self.is_checked_on_selected_node()
+            && (self.control_strategy != ControlStrategy::Reject
+                || !matches!(
+                    self.calculate_strategy,
+                    CalculateStrategy::Direct
+                        | CalculateStrategy::MemoryAdaptive
+                        | CalculateStrategy::CpuAdaptive
+                ))
+        {
+            return Err(Error::msg(
+                "only ControlStrategy::Reject with CalculateStrategy::Direct, MemoryAdaptive or CpuAdaptive is supported when limit_origin is specified or relation_strategy is RelationStrategy::Chain",
+            ));
+        }
         if self.calculate_strategy == CalculateStrategy::WarmUp {
             if self.warm_up_period_sec == 0 {
                 return Err(Error::msg("warm_up_period_sec must be great than 0"));
@@ -209,6 +341,52 @@ impl SentinelRule for Rule {
                 ));
             }
         }
+        if self.calculate_strategy == CalculateStrategy::CpuAdaptive {
+            if self.high_cpu_usage_threshold == 0 || self.low_cpu_usage_threshold == 0 {
+                return Err(Error::msg("cpu usage threshold setting to 0"));
+            }
+            if self.high_cpu_usage_threshold >= self.low_cpu_usage_threshold {
+                return Err(Error::msg(
+                    "self.high_cpu_usage_threshold >= self.low_cpu_usage_threshold",
+                ));
+            }
+            if self.cpu_low_water_mark < 0.0 || self.cpu_high_water_mark > 100.0 {
+                return Err(Error::msg(
+                    "invalid cpu water mark, valid range is [0.0, 100.0]",
+                ));
+            }
+            if self.cpu_low_water_mark >= self.cpu_high_water_mark {
+                // can not be equal to defeat from zero overflow
+                return Err(Error::msg(
+                    "self.cpu_low_water_mark >= self.cpu_high_water_mark",
+                ));
+            }
+        }
+        if self.calculate_strategy == CalculateStrategy::TokenBucket {
+            if self.burst == 0 {
+                return Err(Error::msg("burst must be great than 0"));
+            }
+            if self.refill_rate <= 0.0 {
+                return Err(Error::msg("refill_rate must be great than 0"));
+            }
+            if self.control_strategy != ControlStrategy::Reject {
+                return Err(Error::msg(
+                    "control_strategy must be ControlStrategy::Reject when calculate_strategy is CalculateStrategy::TokenBucket",
+                ));
+            }
+        }
+        if self.cluster_mode {
+            if self.calculate_strategy != CalculateStrategy::Direct
+                || self.control_strategy != ControlStrategy::Reject
+            {
+                return Err(Error::msg(
+                    "only CalculateStrategy::Direct with ControlStrategy::Reject is supported in cluster mode",
+                ));
+            }
+            if self.cluster_config.flow_id == 0 {
+                return Err(Error::msg("flow_id must be great than 0 in cluster mode"));
+            }
+        }
         Ok(())
     }
 }
@@ -217,19 +395,32 @@ impl PartialEq for Rule {
     fn eq(&self, other: &Self) -> bool {
         // todo: discuss under different strategies
         self.resource == other.resource
+            && self.match_strategy == other.match_strategy
             && self.ref_resource == other.ref_resource
             && self.calculate_strategy == other.calculate_strategy
             && self.control_strategy == other.control_strategy
             && self.relation_strategy == other.relation_strategy
+            && self.limit_origin == other.limit_origin
             && self.threshold == other.threshold
             && self.warm_up_period_sec == other.warm_up_period_sec
             && self.warm_up_cold_factor == other.warm_up_cold_factor
             && self.max_queueing_time_ms == other.max_queueing_time_ms
+            && self.max_queue_size == other.max_queue_size
             && self.stat_interval_ms == other.stat_interval_ms
             && self.low_mem_usage_threshold == other.low_mem_usage_threshold
             && self.high_mem_usage_threshold == other.high_mem_usage_threshold
             && self.mem_low_water_mark == other.mem_low_water_mark
             && self.mem_high_water_mark == other.mem_high_water_mark
+            && self.low_cpu_usage_threshold == other.low_cpu_usage_threshold
+            && self.high_cpu_usage_threshold == other.high_cpu_usage_threshold
+            && self.cpu_low_water_mark == other.cpu_low_water_mark
+            && self.cpu_high_water_mark == other.cpu_high_water_mark
+            && self.burst == other.burst
+            && self.refill_rate == other.refill_rate
+            && self.schedule == other.schedule
+            && self.cluster_mode == other.cluster_mode
+            && self.cluster_config == other.cluster_config
+            && self.mode == other.mode
     }
 }
 
@@ -296,6 +487,16 @@ mod test {
         assert!(!r2.need_statistic());
         assert!(r3.need_statistic());
         assert!(r4.need_statistic());
+        // no need
+        let r5 = Rule {
+            resource: "abc1".into(),
+            calculate_strategy: CalculateStrategy::TokenBucket,
+            control_strategy: ControlStrategy::Reject,
+            burst: 10,
+            refill_rate: 5.0,
+            ..Default::default()
+        };
+        assert!(!r5.need_statistic());
     }
 
     #[test]
@@ -529,4 +730,78 @@ mod test {
         rule.mem_high_water_mark = 300 * 1024;
         assert!(rule.is_valid().is_ok());
     }
+
+    #[test]
+    fn cpu_adaptive_is_valid() {
+        let mut rule = Rule {
+            resource: "abc1".into(),
+            calculate_strategy: CalculateStrategy::CpuAdaptive,
+            low_cpu_usage_threshold: 1000,
+            high_cpu_usage_threshold: 100,
+            cpu_low_water_mark: 20.0,
+            cpu_high_water_mark: 80.0,
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_ok());
+        rule.cpu_high_water_mark = 120.0;
+        assert!(rule.is_valid().is_err());
+        rule.cpu_high_water_mark = 10.0;
+        assert!(rule.is_valid().is_err());
+        rule.cpu_high_water_mark = 80.0;
+        rule.high_cpu_usage_threshold = 1000;
+        assert!(rule.is_valid().is_err());
+    }
+
+    #[test]
+    fn cluster_is_valid() {
+        let mut rule = Rule {
+            resource: "abc1".into(),
+            threshold: 10.0,
+            cluster_mode: true,
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_err());
+        rule.cluster_config.flow_id = 1;
+        assert!(rule.is_valid().is_ok());
+        rule.control_strategy = ControlStrategy::Throttling;
+        assert!(rule.is_valid().is_err());
+    }
+
+    #[test]
+    fn selected_node_is_valid() {
+        let mut rule = Rule {
+            resource: "abc1".into(),
+            threshold: 10.0,
+            limit_origin: LIMIT_ORIGIN_OTHER.into(),
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_ok());
+        rule.control_strategy = ControlStrategy::Throttling;
+        assert!(rule.is_valid().is_err());
+        rule.limit_origin = LIMIT_ORIGIN_DEFAULT.into();
+        assert!(rule.is_valid().is_ok());
+        rule.relation_strategy = RelationStrategy::Chain;
+        rule.ref_resource = "entrance".into();
+        assert!(rule.is_valid().is_err());
+        rule.control_strategy = ControlStrategy::Reject;
+        assert!(rule.is_valid().is_ok());
+
+        let rule = Rule {
+            resource: "abc1".into(),
+            limit_origin: "a".into(),
+            calculate_strategy: CalculateStrategy::TokenBucket,
+            burst: 10,
+            refill_rate: 10.0,
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_err());
+        let rule = Rule {
+            resource: "abc1".into(),
+            limit_origin: "a".into(),
+            calculate_strategy: CalculateStrategy::WarmUp,
+            warm_up_period_sec: 10,
+            ..Default::default()
+        };
+        assert!(rule.is_valid().is_err());
+    }
 }
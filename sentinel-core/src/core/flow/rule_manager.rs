@@ -2,9 +2,13 @@ use super::*;
 use crate::{
     core::{
         base,
-        base::{nop_read_stat, nop_write_stat, ResourceType, SentinelRule, StatNode},
+        base::{
+            nop_read_stat, nop_write_stat, MatchStrategy, PatternCache, ResourceType, SentinelRule,
+            StatNode,
+        },
+        cluster::TokenClient,
         config, stat,
-        stat::ResourceNode,
+        stat::{NodeStorage, ResourceNode},
     },
     logging, utils, Error, Result,
 };
@@ -82,16 +86,54 @@ lazy_static! {
             MemoryAdaptiveCalculator,
             ThrottlingChecker
         );
+        insert_flow_generator!(
+            gen_fun_map,
+            CalculateStrategy::CpuAdaptive,
+            ControlStrategy::Reject,
+            CpuAdaptiveCalculator,
+            RejectChecker
+        );
+        insert_flow_generator!(
+            gen_fun_map,
+            CalculateStrategy::CpuAdaptive,
+            ControlStrategy::Throttling,
+            CpuAdaptiveCalculator,
+            ThrottlingChecker
+        );
+        insert_flow_generator!(
+            gen_fun_map,
+            CalculateStrategy::TokenBucket,
+            ControlStrategy::Reject,
+            TokenBucketCalculator,
+            TokenBucketChecker
+        );
 
         RwLock::new(gen_fun_map)
     };
-    static ref CONTROLLER_MAP: Mutex<ControllerMap> = Mutex::new(HashMap::new());
     static ref NOP_STAT: Arc<StandaloneStat> = Arc::new(StandaloneStat::new(
         false,
         nop_read_stat(),
         Some(nop_write_stat())
     ));
-    static ref RULE_MAP: Mutex<RuleMap> = Mutex::new(HashMap::new());
+    static ref DEFAULT_RULE_MANAGER: Arc<RuleManager> =
+        Arc::new(RuleManager::new(stat::default_node_storage()));
+}
+
+/// `RuleManager` holds the flow rules and the traffic controllers of a Sentinel instance.
+/// The statistic of the controllers are generated from the resource nodes in its `NodeStorage`.
+pub struct RuleManager {
+    controller_map: Mutex<ControllerMap>,
+    rule_map: Mutex<RuleMap>,
+    /// the controllers resolved from the rules with resource patterns, for each resource name
+    pattern_controllers: PatternCache<Rule, Arc<Controller>>,
+    storage: Arc<NodeStorage>,
+    /// the token client used by the cluster mode rules, see `crate::cluster`
+    token_client: RwLock<Option<Arc<TokenClient>>>,
+}
+
+/// `default_rule_manager` returns the rule manager used by the default Sentinel instance
+pub fn default_rule_manager() -> Arc<RuleManager> {
+    DEFAULT_RULE_MANAGER.clone()
 }
 
 fn log_rule_update(map: &RuleMap) {
@@ -105,308 +147,519 @@ fn log_rule_update(map: &RuleMap) {
     }
 }
 
-/// different from
-pub fn append_rule(rule: Arc<Rule>) -> bool {
-    if RULE_MAP
-        .lock()
-        .unwrap()
-        .get(&rule.resource)
-        .unwrap_or(&HashSet::new())
-        .contains(&rule)
-    {
-        return false;
+impl RuleManager {
+    pub fn new(storage: Arc<NodeStorage>) -> Self {
+        RuleManager {
+            controller_map: Mutex::new(HashMap::new()),
+            rule_map: Mutex::new(HashMap::new()),
+            pattern_controllers: PatternCache::new(),
+            storage,
+            token_client: RwLock::new(None),
+        }
     }
-    match rule.is_valid() {
-        Ok(_) => {
-            RULE_MAP
+
+    /// `node_storage` returns the storage of the resource nodes bound to this rule manager
+    pub fn node_storage(&self) -> &Arc<NodeStorage> {
+        &self.storage
+    }
+
+    /// `set_token_client` sets the token client used by the cluster mode rules of this rule manager.
+    pub fn set_token_client(&self, client: Arc<TokenClient>) {
+        *self.token_client.write().unwrap() = Some(client);
+    }
+
+    /// `clear_token_client` removes the token client, then the cluster mode rules fall back to the local checking.
+    pub fn clear_token_client(&self) {
+        *self.token_client.write().unwrap() = None;
+    }
+
+    pub fn token_client(&self) -> Option<Arc<TokenClient>> {
+        self.token_client.read().unwrap().clone()
+    }
+
+    /// different from
+    pub fn append_rule(&self, rule: Arc<Rule>) -> bool {
+        if self
+            .rule_map
+            .lock()
+            .unwrap()
+            .get(&rule.resource)
+            .unwrap_or(&HashSet::new())
+            .contains(&rule)
+        {
+            return false;
+        }
+        match rule.is_valid() {
+            Ok(_) => {
+                self.rule_map
+                    .lock()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(Arc::clone(&rule));
+            }
+            Err(err) => logging::warn!(
+                "[Flow load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
+                rule,
+                err
+            ),
+        }
+        let mut placeholder = Vec::new();
+        let new_tcs_of_res = self.build_resource_traffic_shaping_controller(
+            &rule.resource,
+            self.rule_map.lock().unwrap().get(&rule.resource).unwrap(),
+            self.controller_map
+                .lock()
+                .unwrap()
+                .get_mut(&rule.resource)
+                .unwrap_or(&mut placeholder),
+        );
+        if !new_tcs_of_res.is_empty() {
+            self.controller_map
                 .lock()
                 .unwrap()
                 .entry(rule.resource.clone())
                 .or_default()
-                .insert(Arc::clone(&rule));
+                .push(Arc::clone(&new_tcs_of_res[0]));
         }
-        Err(err) => logging::warn!(
-            "[Flow load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
-            rule,
-            err
-        ),
+        self.load_pattern_rules(&self.controller_map.lock().unwrap());
+        true
     }
-    let mut placeholder = Vec::new();
-    let new_tcs_of_res = build_resource_traffic_shaping_controller(
-        &rule.resource,
-        RULE_MAP.lock().unwrap().get(&rule.resource).unwrap(),
-        CONTROLLER_MAP
-            .lock()
-            .unwrap()
-            .get_mut(&rule.resource)
-            .unwrap_or(&mut placeholder),
-    );
-    if !new_tcs_of_res.is_empty() {
-        CONTROLLER_MAP
-            .lock()
-            .unwrap()
-            .entry(rule.resource.clone())
-            .or_default()
-            .push(Arc::clone(&new_tcs_of_res[0]));
-    }
-    true
-}
 
-/// `load_rules` loads the given flow rules to the rule manager, while all previous rules will be replaced.
-/// The returned `bool` indicates whether do real load operation, if the rules is the same with previous rules, return false
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
-pub fn load_rules(rules: Vec<Arc<Rule>>) -> bool {
-    let mut rule_map: RuleMap = HashMap::new();
-    // todo: validate rules here,
-    // neglect invalid rules,
-    // instead of dealing with them in
-    // `on_rule_update`
-    for rule in rules {
-        let entry = rule_map.entry(rule.resource.clone()).or_default();
-        entry.insert(rule);
-    }
+    /// `load_rules` loads the given flow rules to the rule manager, while all previous rules will be replaced.
+    /// The returned `bool` indicates whether do real load operation, if the rules is the same with previous rules, return false
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn load_rules(&self, rules: Vec<Arc<Rule>>) -> bool {
+        let mut rule_map: RuleMap = HashMap::new();
+        // todo: validate rules here,
+        // neglect invalid rules,
+        // instead of dealing with them in
+        // `on_rule_update`
+        for rule in rules {
+            let entry = rule_map.entry(rule.resource.clone()).or_default();
+            entry.insert(rule);
+        }
 
-    let mut global_rule_map = RULE_MAP.lock().unwrap();
-    if *global_rule_map == rule_map {
-        logging::info!(
-            "[Flow] Load rules is the same with current rules, so ignore load operation."
+        let mut global_rule_map = self.rule_map.lock().unwrap();
+        if *global_rule_map == rule_map {
+            logging::info!(
+                "[Flow] Load rules is the same with current rules, so ignore load operation."
+            );
+            return false;
+        }
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
+        let mut valid_rules_map = HashMap::with_capacity(rule_map.len());
+        for (res, rules) in &rule_map {
+            let mut valid_rules = HashSet::new();
+            for rule in rules {
+                match rule.is_valid() {
+                    Ok(_) => {
+                        valid_rules.insert(Arc::clone(rule));
+                    }
+                    Err(err) => logging::warn!(
+                        "[Flow load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
+                        rule,
+                        err
+                    ),
+                }
+            }
+            if !valid_rules.is_empty() {
+                valid_rules_map.insert(res.clone(), valid_rules);
+            }
+        }
+
+        let start = utils::curr_time_nanos();
+        let mut controller_map = self.controller_map.lock().unwrap();
+        let mut valid_controller_map = HashMap::with_capacity(valid_rules_map.len());
+
+        // build controller_map according to valid rules
+        for (res, rules) in valid_rules_map.iter() {
+            let mut placeholder = Vec::new();
+            let new_tcs_of_res = self.build_resource_traffic_shaping_controller(
+                res,
+                rules,
+                controller_map.get_mut(res).unwrap_or(&mut placeholder),
+            );
+            if !new_tcs_of_res.is_empty() {
+                valid_controller_map.insert(res.clone(), new_tcs_of_res);
+            }
+        }
+        *controller_map = valid_controller_map;
+        *global_rule_map = rule_map;
+        drop(global_rule_map);
+        self.load_pattern_rules(&controller_map);
+        drop(controller_map);
+        logging::debug!(
+            "[Flow load_rules] Time statistic(ns) for updating flow rule, time cost {}",
+            utils::curr_time_nanos() - start
         );
-        return false;
+        log_rule_update(&valid_rules_map);
+        true
     }
-    // when rule_map is different with global one, update the global one
-    // ignore invalid rules
-    let mut valid_rules_map = HashMap::with_capacity(rule_map.len());
-    for (res, rules) in &rule_map {
-        let mut valid_rules = HashSet::new();
-        for rule in rules {
+
+    /// `load_rules_of_resource` loads the given resource's flow rules to the rule manager, while all previous resource's rules will be replaced.
+    /// The first returned value indicates whether do real load operation, if the rules is the same with previous resource's rules, return false
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn load_rules_of_resource(&self, res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+        if res.is_empty() {
+            return Err(Error::msg("empty resource"));
+        }
+        let rules: HashSet<_> = rules.into_iter().collect();
+        let mut global_rule_map = self.rule_map.lock().unwrap();
+        let mut global_controller_map = self.controller_map.lock().unwrap();
+        // clear resource rules
+        if rules.is_empty() {
+            global_rule_map.remove(res);
+            global_controller_map.remove(res);
+            self.load_pattern_rules(&global_controller_map);
+            logging::info!("[Flow] clear resource level rules, resource {}", res);
+            return Ok(true);
+        }
+        // load resource level rules
+        if global_rule_map.get(res).unwrap_or(&HashSet::new()) == &rules {
+            logging::info!("[Flow] Load resource level rules is the same with current resource level rules, so ignore load operation.");
+            return Ok(false);
+        }
+
+        let mut valid_res_rules = HashSet::with_capacity(res.len());
+        for rule in &rules {
             match rule.is_valid() {
                 Ok(_) => {
-                    valid_rules.insert(Arc::clone(rule));
+                    valid_res_rules.insert(Arc::clone(rule));
                 }
                 Err(err) => logging::warn!(
-                    "[Flow load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
+                    "[Flow load_rules_of_resource] Ignoring invalid flow rule {:?}, reason: {:?}",
                     rule,
                     err
                 ),
             }
         }
-        if !valid_rules.is_empty() {
-            valid_rules_map.insert(res.clone(), valid_rules);
-        }
-    }
+        // the `res` related rules changes, have to update
+        let start = utils::curr_time_nanos();
+        let mut placeholder = Vec::new();
+        let old_res_tcs = global_controller_map
+            .get_mut(res)
+            .unwrap_or(&mut placeholder);
 
-    let start = utils::curr_time_nanos();
-    let mut controller_map = CONTROLLER_MAP.lock().unwrap();
-    let mut valid_controller_map = HashMap::with_capacity(valid_rules_map.len());
+        let valid_res_rules_string = format!("{:?}", &valid_res_rules);
+        let new_res_tcs =
+            self.build_resource_traffic_shaping_controller(res, &valid_res_rules, old_res_tcs);
 
-    // build controller_map according to valid rules
-    for (res, rules) in valid_rules_map.iter() {
-        let mut placeholder = Vec::new();
-        let new_tcs_of_res = build_resource_traffic_shaping_controller(
+        if new_res_tcs.is_empty() {
+            global_controller_map.remove(res);
+        } else {
+            global_controller_map.insert(res.clone(), new_res_tcs);
+        }
+
+        global_rule_map.insert(res.clone(), rules);
+        self.load_pattern_rules(&global_controller_map);
+        logging::debug!(
+            "[Flow load_rules_of_resource] Time statistic(ns) for updating flow rule, timeCost: {}",
+            utils::curr_time_nanos() - start
+        );
+        logging::info!(
+            "[Flow] load resource level rules, resource: {}, valid_res_rules: {}",
             res,
-            rules,
-            controller_map.get_mut(res).unwrap_or(&mut placeholder),
+            valid_res_rules_string
         );
-        if !new_tcs_of_res.is_empty() {
-            valid_controller_map.insert(res.clone(), new_tcs_of_res);
+
+        Ok(true)
+    }
+
+    /// `get_rules` returns all the rules in `controller_map`
+    // This func acquires the locks on `controller_map`,
+    // please release your lock on it before calling this func
+    pub fn get_rules(&self) -> Vec<Arc<Rule>> {
+        let mut rules = Vec::new();
+        let controller_map = self.controller_map.lock().unwrap();
+        for (_, controllers) in controller_map.iter() {
+            for c in controllers {
+                rules.push(Arc::clone(c.rule()));
+            }
         }
+        rules
     }
-    *controller_map = valid_controller_map;
-    *global_rule_map = rule_map;
-    drop(global_rule_map);
-    drop(controller_map);
-    logging::debug!(
-        "[Flow load_rules] Time statistic(ns) for updating flow rule, time cost {}",
-        utils::curr_time_nanos() - start
-    );
-    log_rule_update(&valid_rules_map);
-    true
-}
 
-/// `load_rules_of_resource` loads the given resource's flow rules to the rule manager, while all previous resource's rules will be replaced.
-/// The first returned value indicates whether do real load operation, if the rules is the same with previous resource's rules, return false
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
-pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
-    if res.is_empty() {
-        return Err(Error::msg("empty resource"));
+    /// `get_rules_of_resource` returns specific resource's rules
+    // This func acquires the lock on `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn get_rules_of_resource(&self, res: &String) -> Vec<Arc<Rule>> {
+        let controller_map = self.controller_map.lock().unwrap();
+        let placeholder = Vec::new();
+        let controllers = controller_map.get(res).unwrap_or(&placeholder);
+        let mut rules = Vec::with_capacity(controllers.len());
+        for c in controllers {
+            rules.push(Arc::clone(c.rule()));
+        }
+        rules
     }
-    let rules: HashSet<_> = rules.into_iter().collect();
-    let mut global_rule_map = RULE_MAP.lock().unwrap();
-    let mut global_controller_map = CONTROLLER_MAP.lock().unwrap();
-    // clear resource rules
-    if rules.is_empty() {
-        global_rule_map.remove(res);
-        global_controller_map.remove(res);
-        logging::info!("[Flow] clear resource level rules, resource {}", res);
-        return Ok(true);
+
+    /// clear_rules clears all the rules in flow module.
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn clear_rules(&self) {
+        self.rule_map.lock().unwrap().clear();
+        self.controller_map.lock().unwrap().clear();
+        self.pattern_controllers.clear();
     }
-    // load resource level rules
-    if global_rule_map.get(res).unwrap_or(&HashSet::new()) == &rules {
-        logging::info!("[Flow] Load resource level rules is the same with current resource level rules, so ignore load operation.");
-        return Ok(false);
+
+    /// `clear_rules_of_resource` clears resource level rules in flow module.
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn clear_rules_of_resource(&self, res: &String) {
+        self.rule_map.lock().unwrap().remove(res);
+        let mut controller_map = self.controller_map.lock().unwrap();
+        controller_map.remove(res);
+        self.load_pattern_rules(&controller_map);
     }
 
-    let mut valid_res_rules = HashSet::with_capacity(res.len());
-    for rule in &rules {
-        match rule.is_valid() {
+    /// `load_pattern_rules` compiles the resource patterns of the rules in the `controller_map`,
+    /// whose template controllers are used to resolve the controllers of each resource name.
+    fn load_pattern_rules(&self, controller_map: &ControllerMap) {
+        self.pattern_controllers
+            .load_rules(controller_map.values().flatten().map(|tc| tc.rule()));
+    }
+
+    /// `get_active_traffic_controller_list_for` returns the controllers whose rules are active now,
+    /// according to the schedules of the rules, see `Controller::is_active()`
+    pub fn get_active_traffic_controller_list_for(&self, name: &String) -> Vec<Arc<Controller>> {
+        let mut controllers = self.get_traffic_controller_list_for(name);
+        controllers.retain(|tc| tc.is_active());
+        controllers
+    }
+
+    /// `get_traffic_controller_list_for` returns the controllers taking effect on the resource,
+    /// including the ones resolved from the rules with resource patterns.
+    // This func acquires the lock on `controller_map`,
+    // please release your lock on it before calling this func
+    pub fn get_traffic_controller_list_for(&self, name: &String) -> Vec<Arc<Controller>> {
+        let mut controllers: Vec<Arc<Controller>> =
+            match self.controller_map.lock().unwrap().get(name) {
+                // the controllers of pattern rules are kept as templates
+                Some(controllers) => controllers
+                    .iter()
+                    .filter(|tc| !tc.rule().match_strategy.is_pattern())
+                    .cloned()
+                    .collect(),
+                None => Vec::new(),
+            };
+        controllers.append(
+            &mut self
+                .pattern_controllers
+                .get_or_resolve(name, |rules, mut old_tcs| {
+                    self.resolve_pattern_controllers(name, rules, &mut old_tcs)
+                }),
+        );
+        controllers
+    }
+
+    /// `resolve_pattern_controllers` builds the controllers for the resource,
+    /// with the rules whose resource patterns match the resource name.
+    /// The controllers resolved from the previous rules are reused if the rules are unchanged,
+    /// or their statistics are reused if possible, see `build_resource_traffic_shaping_controller()`.
+    fn resolve_pattern_controllers(
+        &self,
+        name: &String,
+        pattern_rules: Vec<Arc<Rule>>,
+        old_tcs: &mut Vec<Arc<Controller>>,
+    ) -> Vec<Arc<Controller>> {
+        let rules: HashSet<Arc<Rule>> = pattern_rules
+            .into_iter()
+            .map(|rule| {
+                Arc::new(Rule {
+                    resource: name.clone(),
+                    match_strategy: MatchStrategy::Exact,
+                    ..(*rule).clone()
+                })
+            })
+            .collect();
+        self.build_resource_traffic_shaping_controller(name, &rules, old_tcs)
+    }
+
+    /// `generate_stat_for` generates a `StandaloneStat` according to the rule,
+    /// it may generate a cloned pointer to the global `NOP_STAT`,
+    /// a new stat node with default global metrics
+    /// or a new stat node with new metrics.
+    fn generate_stat_for(&self, rule: &Arc<Rule>) -> Result<Arc<StandaloneStat>> {
+        // the statistics of pattern rules are generated for each resolved resource
+        if !rule.need_statistic() || rule.match_strategy.is_pattern() {
+            return Ok(NOP_STAT.clone());
+        }
+
+        let interval_ms = rule.stat_interval_ms;
+
+        let res_node: Arc<ResourceNode> = {
+            if rule.relation_strategy == RelationStrategy::Associated {
+                // use associated statistic
+                self.storage
+                    .get_or_create_resource_node(&rule.ref_resource, &ResourceType::Common)
+            } else {
+                self.storage
+                    .get_or_create_resource_node(&rule.resource, &ResourceType::Common)
+            }
+        };
+
+        if interval_ms == 0 || interval_ms == config::metric_stat_interval_ms() {
+            // default case, use the resource's default statistic
+            let metric = res_node.default_metric();
+            let ret_stat = Arc::new(StandaloneStat::new(true, metric, None));
+            return Ok(ret_stat);
+        }
+
+        let mut sample_count: u32 = 1;
+        //calculate the sample count
+        if interval_ms > config::global_stat_bucket_length_ms()
+            && interval_ms < config::global_stat_interval_ms_total()
+            && interval_ms % config::global_stat_bucket_length_ms() == 0
+        {
+            sample_count = interval_ms / config::global_stat_bucket_length_ms();
+        }
+
+        let validity = base::check_validity_for_reuse_statistic(
+            sample_count,
+            interval_ms,
+            config::global_stat_sample_count_total(),
+            config::global_stat_interval_ms_total(),
+        );
+        let _err = Error::msg(base::GLOBAL_STATISTIC_NON_REUSABLE_ERROR);
+        match validity {
             Ok(_) => {
-                valid_res_rules.insert(Arc::clone(rule));
+                let metric = res_node.generate_read_stat(sample_count, interval_ms)?;
+                let ret_stat = Arc::new(StandaloneStat::new(true, metric, None));
+                Ok(ret_stat)
+            }
+            Err(_err) => {
+                logging::info!("[FlowRuleManager] Flow rule couldn't reuse global statistic and will generate independent statistic, rule: {:?}", rule);
+                let write_stat = Arc::new(stat::BucketLeapArray::new(sample_count, interval_ms)?);
+                let read_stat = Arc::new(stat::SlidingWindowMetric::new(
+                    sample_count,
+                    interval_ms,
+                    write_stat.clone(),
+                )?);
+                let res_stat = Arc::new(StandaloneStat::new(false, read_stat, Some(write_stat)));
+                Ok(res_stat)
             }
-            Err(err) => logging::warn!(
-                "[Flow load_rules_of_resource] Ignoring invalid flow rule {:?}, reason: {:?}",
-                rule,
-                err
-            ),
         }
     }
-    // the `res` related rules changes, have to update
-    let start = utils::curr_time_nanos();
-    let mut placeholder = Vec::new();
-    let old_res_tcs = global_controller_map
-        .get_mut(res)
-        .unwrap_or(&mut placeholder);
-
-    let valid_res_rules_string = format!("{:?}", &valid_res_rules);
-    let new_res_tcs = build_resource_traffic_shaping_controller(res, &valid_res_rules, old_res_tcs);
-
-    if new_res_tcs.is_empty() {
-        global_controller_map.remove(res);
-    } else {
-        global_controller_map.insert(res.clone(), new_res_tcs);
+
+    /// build_resource_traffic_shaping_controller builds Controller slice from rules. the resource of rules must be equals to res
+    pub fn build_resource_traffic_shaping_controller(
+        &self,
+        res: &String,
+        rules_of_res: &HashSet<Arc<Rule>>,
+        old_res_tcs: &mut Vec<Arc<Controller>>,
+    ) -> Vec<Arc<Controller>> {
+        let mut new_res_tcs = Vec::with_capacity(rules_of_res.len());
+        for rule in rules_of_res {
+            if res != &rule.resource {
+                logging::error!("unmatched resource name expect: {}, actual: {}. Unmatched resource name in flow::build_resource_traffic_shaping_controller(), rule: {:?}", res, rule.resource, rule);
+                continue;
+            }
+            let (eq_idx, reuse_stat_idx) = calculate_reuse_index_for(rule, old_res_tcs);
+
+            // First check equals scenario
+            if eq_idx != usize::MAX {
+                // reuse the old tc
+                let eq_old_tc = Arc::clone(&old_res_tcs[eq_idx]);
+                new_res_tcs.push(eq_old_tc);
+                // remove old tc from old_res_tcs
+                old_res_tcs.remove(eq_idx);
+                continue;
+            }
+
+            let gen_fun_map = GEN_FUN_MAP.read().unwrap();
+            let key = ControllerGenKey::new(rule.calculate_strategy, rule.control_strategy);
+            let generator = gen_fun_map.get(&key);
+
+            if generator.is_none() {
+                logging::error!("[FlowRuleManager build_resource_traffic_shaping_controller] Unsupported flow control strategy. Ignoring the rule due to unsupported control behavior in flow::build_resource_traffic_shaping_controller(), rule: {}",  rule);
+                continue;
+            }
+            let generator = generator.unwrap();
+
+            let tc = {
+                if reuse_stat_idx != usize::MAX {
+                    generator(
+                        Arc::clone(rule),
+                        Some(Arc::clone(old_res_tcs[reuse_stat_idx].stat())),
+                    )
+                } else {
+                    // generate the statistic from the resource nodes of this rule manager
+                    self.generate_stat_for(rule)
+                        .and_then(|stat| generator(Arc::clone(rule), Some(stat)))
+                }
+            };
+
+            if tc.is_err() {
+                logging::error!("[FlowRuleManager build_resource_traffic_shaping_controller] Bad generated traffic controller. Ignoring the rule due to bad generated traffic controller in flow::build_resource_traffic_shaping_controller(), rule: {:?}, error: {:?}", rule, tc);
+                continue;
+            }
+            let tc = tc.unwrap();
+            if reuse_stat_idx != usize::MAX {
+                // remove old tc from old_res_tcs
+                old_res_tcs.remove(reuse_stat_idx);
+            }
+            // the template controllers of the pattern rules never queue
+            #[cfg(feature = "exporter")]
+            if !rule.match_strategy.is_pattern() {
+                crate::exporter::watch_flow_queue_length(&tc);
+            }
+            new_res_tcs.push(tc);
+        }
+        new_res_tcs
     }
+}
 
-    global_rule_map.insert(res.clone(), rules);
-    logging::debug!(
-        "[Flow load_rules_of_resource] Time statistic(ns) for updating flow rule, timeCost: {}",
-        utils::curr_time_nanos() - start
-    );
-    logging::info!(
-        "[Flow] load resource level rules, resource: {}, valid_res_rules: {}",
-        res,
-        valid_res_rules_string
-    );
-
-    Ok(true)
+// The following functions operate on the default rule manager.
+fn generate_stat_for(rule: &Arc<Rule>) -> Result<Arc<StandaloneStat>> {
+    DEFAULT_RULE_MANAGER.generate_stat_for(rule)
+}
+
+pub fn append_rule(rule: Arc<Rule>) -> bool {
+    DEFAULT_RULE_MANAGER.append_rule(rule)
+}
+
+pub fn load_rules(rules: Vec<Arc<Rule>>) -> bool {
+    DEFAULT_RULE_MANAGER.load_rules(rules)
+}
+
+pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+    DEFAULT_RULE_MANAGER.load_rules_of_resource(res, rules)
 }
 
-/// `get_rules` returns all the rules in `CONTROLLER_MAP`
-// This func acquires the locks on global `CONTROLLER_MAP`,
-// please release your lock on it before calling this func
 pub fn get_rules() -> Vec<Arc<Rule>> {
-    let mut rules = Vec::new();
-    let controller_map = CONTROLLER_MAP.lock().unwrap();
-    for (_, controllers) in controller_map.iter() {
-        for c in controllers {
-            rules.push(Arc::clone(c.rule()));
-        }
-    }
-    rules
+    DEFAULT_RULE_MANAGER.get_rules()
 }
 
-/// `get_rules_of_resource` returns specific resource's rules
-// This func acquires the lock on global `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
 pub fn get_rules_of_resource(res: &String) -> Vec<Arc<Rule>> {
-    let controller_map = CONTROLLER_MAP.lock().unwrap();
-    let placeholder = Vec::new();
-    let controllers = controller_map.get(res).unwrap_or(&placeholder);
-    let mut rules = Vec::with_capacity(controllers.len());
-    for c in controllers {
-        rules.push(Arc::clone(c.rule()));
-    }
-    rules
+    DEFAULT_RULE_MANAGER.get_rules_of_resource(res)
 }
 
-/// clear_rules clears all the rules in flow module.
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
 pub fn clear_rules() {
-    RULE_MAP.lock().unwrap().clear();
-    CONTROLLER_MAP.lock().unwrap().clear();
+    DEFAULT_RULE_MANAGER.clear_rules()
 }
 
-/// `clear_rules_of_resource` clears resource level rules in flow module.
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
 pub fn clear_rules_of_resource(res: &String) {
-    RULE_MAP.lock().unwrap().remove(res);
-    CONTROLLER_MAP.lock().unwrap().remove(res);
+    DEFAULT_RULE_MANAGER.clear_rules_of_resource(res)
 }
 
-// This func acquires the lock on global `CONTROLLER_MAP`,
-// please release your lock on it before calling this func
 pub fn get_traffic_controller_list_for(name: &String) -> Vec<Arc<Controller>> {
-    let controller_map = CONTROLLER_MAP.lock().unwrap();
-    let controllers = controller_map.get(name);
-    match controllers {
-        Some(controllers) => controllers.clone(),
-        None => Vec::new(),
-    }
+    DEFAULT_RULE_MANAGER.get_traffic_controller_list_for(name)
 }
 
-/// `generate_stat_for` generates a `StandaloneStat` according to the rule,
-/// it may generate a cloned pointer to the global `NOP_STAT`,
-/// a new stat node with default global metrics
-/// or a new stat node with new metrics.
-fn generate_stat_for(rule: &Arc<Rule>) -> Result<Arc<StandaloneStat>> {
-    if !rule.need_statistic() {
-        return Ok(NOP_STAT.clone());
-    }
-
-    let interval_ms = rule.stat_interval_ms;
-
-    let res_node: Arc<ResourceNode> = {
-        if rule.relation_strategy == RelationStrategy::Associated {
-            // use associated statistic
-            stat::get_or_create_resource_node(&rule.ref_resource, &ResourceType::Common)
-        } else {
-            stat::get_or_create_resource_node(&rule.resource, &ResourceType::Common)
-        }
-    };
-
-    if interval_ms == 0 || interval_ms == config::metric_stat_interval_ms() {
-        // default case, use the resource's default statistic
-        let metric = res_node.default_metric();
-        let ret_stat = Arc::new(StandaloneStat::new(true, metric, None));
-        return Ok(ret_stat);
-    }
-
-    let mut sample_count: u32 = 1;
-    //calculate the sample count
-    if interval_ms > config::global_stat_bucket_length_ms()
-        && interval_ms < config::global_stat_interval_ms_total()
-        && interval_ms % config::global_stat_bucket_length_ms() == 0
-    {
-        sample_count = interval_ms / config::global_stat_bucket_length_ms();
-    }
+pub fn get_active_traffic_controller_list_for(name: &String) -> Vec<Arc<Controller>> {
+    DEFAULT_RULE_MANAGER.get_active_traffic_controller_list_for(name)
+}
 
-    let validity = base::check_validity_for_reuse_statistic(
-        sample_count,
-        interval_ms,
-        config::global_stat_sample_count_total(),
-        config::global_stat_interval_ms_total(),
-    );
-    let _err = Error::msg(base::GLOBAL_STATISTIC_NON_REUSABLE_ERROR);
-    match validity {
-        Ok(_) => {
-            let metric = res_node.generate_read_stat(sample_count, interval_ms)?;
-            let ret_stat = Arc::new(StandaloneStat::new(true, metric, None));
-            Ok(ret_stat)
-        }
-        Err(_err) => {
-            logging::info!("[FlowRuleManager] Flow rule couldn't reuse global statistic and will generate independent statistic, rule: {:?}", rule);
-            let write_stat = Arc::new(stat::BucketLeapArray::new(sample_count, interval_ms)?);
-            let read_stat = Arc::new(stat::SlidingWindowMetric::new(
-                sample_count,
-                interval_ms,
-                write_stat.clone(),
-            )?);
-            let res_stat = Arc::new(StandaloneStat::new(false, read_stat, Some(write_stat)));
-            Ok(res_stat)
-        }
-    }
+pub fn build_resource_traffic_shaping_controller(
+    res: &String,
+    rules_of_res: &HashSet<Arc<Rule>>,
+    old_res_tcs: &mut Vec<Arc<Controller>>,
+) -> Vec<Arc<Controller>> {
+    DEFAULT_RULE_MANAGER.build_resource_traffic_shaping_controller(res, rules_of_res, old_res_tcs)
 }
 
 /// `set_traffic_shaping_generator` sets the traffic controller generator for the given CalculateStrategy and ControlStrategy.
@@ -474,65 +727,6 @@ fn calculate_reuse_index_for(r: &Arc<Rule>, old_res_tcs: &[Arc<Controller>]) ->
     (eq_idx, reuse_stat_idx)
 }
 
-/// build_resource_traffic_shaping_controller builds Controller slice from rules. the resource of rules must be equals to res
-pub fn build_resource_traffic_shaping_controller(
-    res: &String,
-    rules_of_res: &HashSet<Arc<Rule>>,
-    old_res_tcs: &mut Vec<Arc<Controller>>,
-) -> Vec<Arc<Controller>> {
-    let mut new_res_tcs = Vec::with_capacity(rules_of_res.len());
-    for rule in rules_of_res {
-        if res != &rule.resource {
-            logging::error!("unmatched resource name expect: {}, actual: {}. Unmatched resource name in flow::build_resource_traffic_shaping_controller(), rule: {:?}", res, rule.resource, rule);
-            continue;
-        }
-        let (eq_idx, reuse_stat_idx) = calculate_reuse_index_for(rule, old_res_tcs);
-
-        // First check equals scenario
-        if eq_idx != usize::MAX {
-            // reuse the old tc
-            let eq_old_tc = Arc::clone(&old_res_tcs[eq_idx]);
-            new_res_tcs.push(eq_old_tc);
-            // remove old tc from old_res_tcs
-            old_res_tcs.remove(eq_idx);
-            continue;
-        }
-
-        let gen_fun_map = GEN_FUN_MAP.read().unwrap();
-        let key = ControllerGenKey::new(rule.calculate_strategy, rule.control_strategy);
-        let generator = gen_fun_map.get(&key);
-
-        if generator.is_none() {
-            logging::error!("[FlowRuleManager build_resource_traffic_shaping_controller] Unsupported flow control strategy. Ignoring the rule due to unsupported control behavior in flow::build_resource_traffic_shaping_controller(), rule: {}",  rule);
-            continue;
-        }
-        let generator = generator.unwrap();
-
-        let tc = {
-            if reuse_stat_idx != usize::MAX {
-                generator(
-                    Arc::clone(rule),
-                    Some(Arc::clone(old_res_tcs[reuse_stat_idx].stat())),
-                )
-            } else {
-                generator(Arc::clone(rule), None)
-            }
-        };
-
-        if tc.is_err() {
-            logging::error!("[FlowRuleManager build_resource_traffic_shaping_controller] Bad generated traffic controller. Ignoring the rule due to bad generated traffic controller in flow::build_resource_traffic_shaping_controller(), rule: {:?}, error: {:?}", rule, tc);
-            continue;
-        }
-        let tc = tc.unwrap();
-        if reuse_stat_idx != usize::MAX {
-            // remove old tc from old_res_tcs
-            old_res_tcs.remove(reuse_stat_idx);
-        }
-        new_res_tcs.push(tc);
-    }
-    new_res_tcs
-}
-
 #[cfg(test)]
 mod test {
     //! Some tests cannot run in parallel, since we cannot promise that
@@ -540,6 +734,7 @@ mod test {
     #![allow(clippy::vtable_address_comparisons)]
 
     use super::*;
+    use crate::base::{Schedule, TimeWindow};
 
     #[test]
     #[should_panic(expected = "Default control behaviors are not allowed to be modified.")]
@@ -597,7 +792,7 @@ mod test {
             control_strategy: ControlStrategy::Custom(STRATEGY),
         };
 
-        let controller_map = CONTROLLER_MAP.lock().unwrap();
+        let controller_map = DEFAULT_RULE_MANAGER.controller_map.lock().unwrap();
 
         assert!(GEN_FUN_MAP.read().unwrap().contains_key(&key));
         assert!(!controller_map[&resource].is_empty());
@@ -673,7 +868,7 @@ mod test {
             assert_eq!(rs[1], r1);
         }
 
-        let controller_map = CONTROLLER_MAP.lock().unwrap();
+        let controller_map = DEFAULT_RULE_MANAGER.controller_map.lock().unwrap();
 
         assert_eq!(1, controller_map["abc2"].len());
         assert!(!controller_map["abc2"][0].stat().reuse_global());
@@ -693,6 +888,119 @@ mod test {
         clear_rules();
     }
 
+    #[test]
+    fn scheduled_rules() {
+        let rule_manager = RuleManager::new(Arc::new(NodeStorage::new()));
+        let res_name = String::from("scheduled_rules");
+        rule_manager.load_rules(vec![
+            Arc::new(Rule {
+                resource: res_name.clone(),
+                threshold: 10.0,
+                // active all day long
+                schedule: Some(Schedule {
+                    windows: vec![TimeWindow {
+                        start: "00:00".into(),
+                        end: "00:00".into(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            Arc::new(Rule {
+                resource: res_name.clone(),
+                threshold: 20.0,
+                // never active, since there is no February 31st
+                schedule: Some(Schedule {
+                    cron: "0 0 31 2 *".into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        ]);
+        assert_eq!(
+            rule_manager
+                .get_traffic_controller_list_for(&res_name)
+                .len(),
+            2
+        );
+        let active = rule_manager.get_active_traffic_controller_list_for(&res_name);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].rule().threshold, 10.0);
+    }
+
+    #[test]
+    fn pattern_rules() {
+        let rule_manager = RuleManager::new(Arc::new(NodeStorage::new()));
+        rule_manager.load_rules(vec![
+            Arc::new(Rule {
+                resource: "/api/users/*".into(),
+                match_strategy: MatchStrategy::Glob,
+                threshold: 10.0,
+                ..Default::default()
+            }),
+            Arc::new(Rule {
+                resource: "/api/users/42".into(),
+                threshold: 1.0,
+                ..Default::default()
+            }),
+        ]);
+        assert_eq!(rule_manager.get_rules().len(), 2);
+        // the statistic is not generated for the pattern itself
+        assert!(rule_manager
+            .node_storage()
+            .get_resource_node(&"/api/users/*".into())
+            .is_none());
+
+        let tcs = rule_manager.get_traffic_controller_list_for(&"/api/users/1".into());
+        assert_eq!(tcs.len(), 1);
+        assert_eq!(tcs[0].rule().resource, "/api/users/1");
+        assert_eq!(tcs[0].rule().threshold, 10.0);
+        // resolved once and cached
+        let cached = rule_manager.get_traffic_controller_list_for(&"/api/users/1".into());
+        assert!(Arc::ptr_eq(&tcs[0], &cached[0]));
+
+        assert_eq!(
+            rule_manager
+                .get_traffic_controller_list_for(&"/api/users/42".into())
+                .len(),
+            2
+        );
+        // the template controller of the pattern is excluded
+        assert_eq!(
+            rule_manager
+                .get_traffic_controller_list_for(&"/api/users/*".into())
+                .len(),
+            1
+        );
+        assert!(rule_manager
+            .get_traffic_controller_list_for(&"/api/orders/1".into())
+            .is_empty());
+
+        // the resolved controller of an unchanged pattern rule is reused after reloading
+        rule_manager.load_rules(vec![
+            Arc::new(Rule {
+                resource: "/api/users/*".into(),
+                match_strategy: MatchStrategy::Glob,
+                threshold: 10.0,
+                ..Default::default()
+            }),
+            Arc::new(Rule {
+                resource: "/api/users/42".into(),
+                threshold: 2.0,
+                ..Default::default()
+            }),
+        ]);
+        let reloaded = rule_manager.get_traffic_controller_list_for(&"/api/users/1".into());
+        assert_eq!(reloaded.len(), 1);
+        assert!(Arc::ptr_eq(&tcs[0], &reloaded[0]));
+
+        rule_manager.clear_rules();
+        assert!(rule_manager
+            .get_traffic_controller_list_for(&"/api/users/1".into())
+            .is_empty());
+    }
+
     #[test]
     fn generate_stat_for_default_metric_stat() {
         let r1 = Arc::new(Rule {
@@ -771,7 +1079,7 @@ mod test {
             ..Default::default()
         });
 
-        let mut controller_map = CONTROLLER_MAP.lock().unwrap();
+        let mut controller_map = DEFAULT_RULE_MANAGER.controller_map.lock().unwrap();
         assert_eq!(
             0,
             controller_map
@@ -889,7 +1197,7 @@ mod test {
         assert!(!stat4.reuse_global());
         assert!(stat4.write_only_metric().is_some());
 
-        let mut controller_map = CONTROLLER_MAP.lock().unwrap();
+        let mut controller_map = DEFAULT_RULE_MANAGER.controller_map.lock().unwrap();
 
         controller_map.insert(
             "abc1".into(),
@@ -1009,8 +1317,8 @@ mod test {
         let result = load_rules_of_resource(&String::from("abc1"), vec![]);
         assert!(result.unwrap());
 
-        let rule_map = RULE_MAP.lock().unwrap();
-        let controller_map = CONTROLLER_MAP.lock().unwrap();
+        let rule_map = DEFAULT_RULE_MANAGER.rule_map.lock().unwrap();
+        let controller_map = DEFAULT_RULE_MANAGER.controller_map.lock().unwrap();
 
         assert_eq!(0, controller_map.get("abc1").unwrap_or(&Vec::new()).len());
         assert_eq!(0, rule_map.get("abc1").unwrap_or(&HashSet::new()).len());
@@ -1053,8 +1361,8 @@ mod test {
         load_rules(vec![r11, r12, r21, r22]);
         clear_rules_of_resource(&String::from("abc1"));
 
-        let rule_map = RULE_MAP.lock().unwrap();
-        let controller_map = CONTROLLER_MAP.lock().unwrap();
+        let rule_map = DEFAULT_RULE_MANAGER.rule_map.lock().unwrap();
+        let controller_map = DEFAULT_RULE_MANAGER.controller_map.lock().unwrap();
 
         assert_eq!(0, controller_map.get("abc1").unwrap_or(&Vec::new()).len());
         assert_eq!(0, rule_map.get("abc1").unwrap_or(&HashSet::new()).len());
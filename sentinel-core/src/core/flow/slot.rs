@@ -1,8 +1,9 @@
 use super::*;
 use crate::{
-    base::{BaseSlot, EntryContext, RuleCheckSlot, StatNode, TokenResult},
-    logging, stat, utils,
-    utils::AsAny,
+    base::{BaseSlot, BlockType, EntryContext, RuleCheckSlot, RuleMode, StatNode, TokenResult},
+    cluster::TokenStatus,
+    logging,
+    stat::NodeStorage,
 };
 use lazy_static::lazy_static;
 use std::sync::Arc;
@@ -10,10 +11,24 @@ use std::sync::Arc;
 const RULE_CHECK_SLOT_ORDER: u32 = 2000;
 
 /// A RuleSlot for flow related metrics
-pub struct Slot {}
+pub struct Slot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl Slot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        Slot { rule_manager }
+    }
+}
+
+impl Default for Slot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
 
 lazy_static! {
-    pub static ref DEFAULT_SLOT: Arc<Slot> = Arc::new(Slot {});
+    pub static ref DEFAULT_SLOT: Arc<Slot> = Arc::new(Slot::default());
 }
 
 pub fn default_slot() -> Arc<Slot> {
@@ -29,19 +44,38 @@ impl BaseSlot for Slot {
 impl RuleCheckSlot for Slot {
     fn check(&self, ctx: &mut EntryContext) -> TokenResult {
         let res = ctx.resource().name();
-        let stat_node = ctx.stat_node();
-        let input = ctx.input();
-        let tcs = get_traffic_controller_list_for(res);
-        for tc in tcs {
-            let r = can_pass_check(tc, stat_node.clone(), input.batch_count());
+        let batch_count = ctx.input().batch_count();
+        let prioritized = ctx.input().is_prioritized();
+        let tcs = self
+            .rule_manager
+            .get_active_traffic_controller_list_for(res);
+        for tc in &tcs {
+            let stat_node = match select_node(self.rule_manager.node_storage(), tc, &tcs, ctx) {
+                Some(node) => node,
+                // the rule does not take effect on current invocation
+                None => continue,
+            };
+            let mut r = can_pass_check(&self.rule_manager, tc, stat_node.clone(), batch_count);
+            let shadow = tc.rule().mode == RuleMode::Shadow;
+            if r.is_blocked() && prioritized && !shadow {
+                if let Some(nanos_to_wait) = try_occupy(tc, stat_node, batch_count) {
+                    ctx.mark_occupied(tc.rule().clone());
+                    r = TokenResult::new_should_wait(nanos_to_wait);
+                }
+            }
             match r {
                 TokenResult::Pass => {}
+                TokenResult::Blocked(block_err) if shadow => {
+                    ctx.add_shadow_block(block_err);
+                }
                 TokenResult::Blocked(_) => {
                     ctx.set_result(r);
                     return ctx.result().clone();
                 }
+                // rules in shadow mode never delay the invocation
+                TokenResult::Wait(_) if shadow => {}
                 TokenResult::Wait(nanos_to_wait) => {
-                    utils::sleep_for_ns(nanos_to_wait);
+                    ctx.wait_for_ns(nanos_to_wait);
                 }
             }
         }
@@ -49,22 +83,73 @@ impl RuleCheckSlot for Slot {
     }
 }
 
+/// `rule_matches_origin` checks whether the rule takes effect on the origin of current invocation,
+/// according to the `limit_origin` of the rule.
+fn rule_matches_origin(rule: &Rule, tcs: &[Arc<Controller>], origin: &String) -> bool {
+    if rule.is_default_origin() {
+        return true;
+    }
+    if origin.is_empty() {
+        return false;
+    }
+    if rule.is_other_origin() {
+        // origins specified by other rules are excluded
+        return !tcs.iter().any(|tc| &tc.rule().limit_origin == origin);
+    }
+    &rule.limit_origin == origin
+}
+
+/// `select_node` selects the statistic node to be checked by the controller,
+/// according to the origin, the invocation context and the relation strategy.
+/// It returns `None` if the rule does not take effect on current invocation.
+fn select_node(
+    storage: &NodeStorage,
+    tc: &Arc<Controller>,
+    tcs: &[Arc<Controller>],
+    ctx: &mut EntryContext,
+) -> Option<Option<Arc<dyn StatNode>>> {
+    let rule = tc.rule();
+    if !rule_matches_origin(rule, tcs, ctx.origin()) {
+        return None;
+    }
+    match rule.relation_strategy {
+        RelationStrategy::Chain => {
+            if ctx.context_name() != &rule.ref_resource {
+                return None;
+            }
+            Some(ctx.chain_node())
+        }
+        _ if !rule.is_default_origin() => Some(origin_node(storage, ctx)),
+        RelationStrategy::Associated => Some(
+            storage
+                .get_resource_node(&rule.ref_resource)
+                .map(|node| node as Arc<dyn StatNode>),
+        ),
+        RelationStrategy::Current => Some(ctx.stat_node()),
+    }
+}
+
+/// `origin_node` returns the statistic node of the origin on current resource.
+/// It is created on demand, so that the origins are only tracked for the resources limited by origin-aware rules.
+fn origin_node(storage: &NodeStorage, ctx: &mut EntryContext) -> Option<Arc<dyn StatNode>> {
+    if ctx.origin_node().is_none() {
+        let node = storage.get_resource_node(ctx.resource().name())?;
+        ctx.set_origin_node(node.get_or_create_origin_node(ctx.origin()));
+    }
+    ctx.origin_node()
+}
+
 fn can_pass_check(
-    tc: Arc<Controller>,
-    given_node: Option<Arc<dyn StatNode>>,
+    rule_manager: &RuleManager,
+    tc: &Arc<Controller>,
+    actual_node: Option<Arc<dyn StatNode>>,
     batch_count: u32,
 ) -> TokenResult {
-    let actual_node = {
-        match tc.rule().relation_strategy {
-            RelationStrategy::Associated => {
-                let node = stat::get_resource_node(&tc.rule().ref_resource).unwrap();
-                let node = node.as_any_arc();
-                let node = node.downcast_ref::<Arc<dyn StatNode>>().unwrap();
-                Some(node.clone())
-            }
-            _ => given_node,
+    if tc.rule().cluster_mode {
+        if let Some(r) = cluster_check(rule_manager, tc, batch_count) {
+            return r;
         }
-    };
+    }
     match actual_node {
         Some(node) => tc.perform_checking(node, batch_count, 0),
         None => {
@@ -79,18 +164,68 @@ fn can_pass_check(
     }
 }
 
+/// `cluster_check` requests the tokens from the token server for the cluster mode rule,
+/// it returns `None` to fall back to the local checking, if the token server is not available.
+/// It blocks the calling thread until the response arrives or the request times out, also in `SlotChain::entry_async()`.
+fn cluster_check(
+    rule_manager: &RuleManager,
+    tc: &Arc<Controller>,
+    batch_count: u32,
+) -> Option<TokenResult> {
+    let rule = tc.rule();
+    let client = rule_manager.token_client()?;
+    match client.request_token(rule.cluster_config.flow_id, batch_count) {
+        Ok(response) => match response.status {
+            TokenStatus::Ok => Some(TokenResult::new_pass()),
+            TokenStatus::Blocked => Some(TokenResult::new_blocked_with_cause(
+                BlockType::Flow,
+                "cluster flow check blocked".into(),
+                rule.clone(),
+                Arc::new(response.remaining),
+            )),
+            status => {
+                logging::debug!(
+                    "[FlowSlot] Unexpected token status {:?} of cluster flow rule {:?}, fall back to local checking",
+                    status,
+                    rule
+                );
+                None
+            }
+        },
+        Err(err) => {
+            logging::debug!(
+                "[FlowSlot] Failed to request tokens of cluster flow rule {:?}, fall back to local checking, reason: {:?}",
+                rule,
+                err
+            );
+            None
+        }
+    }
+}
+
+/// `try_occupy` tries to occupy the tokens of the next bucket for the prioritized invocation,
+/// it returns the nanoseconds to wait until the next bucket starts.
+fn try_occupy(
+    tc: &Arc<Controller>,
+    actual_node: Option<Arc<dyn StatNode>>,
+    batch_count: u32,
+) -> Option<u64> {
+    tc.try_occupy(actual_node?, batch_count, 0)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::base::{
-        EntryContext, MetricEvent, ResourceType, ResourceWrapper, SentinelInput, StatSlot,
-        TrafficType,
+        EntryContext, MetricEvent, ReadStat, ResourceType, ResourceWrapper, SentinelInput,
+        StatPrepareSlot, StatSlot, TrafficType,
     };
+    use crate::{cluster, stat, utils};
 
     #[test]
     fn rule_check_slot() {
-        let slot = Slot {};
-        let stat_slot = StandaloneStatSlot {};
+        let slot = Slot::default();
+        let stat_slot = StandaloneStatSlot::default();
         let res_name = String::from("abc");
         let res =
             ResourceWrapper::new(res_name.clone(), ResourceType::Common, TrafficType::Inbound);
@@ -126,4 +261,337 @@ mod test {
             50
         );
     }
+
+    #[test]
+    fn shadow_check() {
+        let storage = Arc::new(NodeStorage::new());
+        let rule_manager = Arc::new(RuleManager::new(storage.clone()));
+        let slot = Slot::new(rule_manager.clone());
+        let stat_prepare_slot = stat::ResourceNodePrepareSlot::new(storage.clone());
+        let stat_slot = stat::ResourceNodeStatSlot::new(storage.clone());
+        let res_name = String::from("shadow_check");
+        rule_manager.load_rules(vec![Arc::new(Rule {
+            resource: res_name.clone(),
+            calculate_strategy: CalculateStrategy::Direct,
+            control_strategy: ControlStrategy::Reject,
+            threshold: 0.0,
+            mode: RuleMode::Shadow,
+            ..Default::default()
+        })]);
+
+        let mut ctx = EntryContext::new();
+        ctx.set_input(SentinelInput::new(1, 0));
+        ctx.set_resource(ResourceWrapper::new(
+            res_name.clone(),
+            ResourceType::Common,
+            TrafficType::Outbound,
+        ));
+        stat_prepare_slot.prepare(&mut ctx);
+        assert!(slot.check(&mut ctx).is_pass());
+        assert_eq!(ctx.shadow_blocks().len(), 1);
+        stat_slot.on_entry_pass(&ctx);
+
+        let node = storage.get_resource_node(&res_name).unwrap();
+        assert_eq!(node.sum(MetricEvent::Pass), 1);
+        assert_eq!(node.sum(MetricEvent::ShadowBlock), 1);
+    }
+
+    #[test]
+    fn prioritized_check() {
+        let storage = Arc::new(NodeStorage::new());
+        let rule_manager = Arc::new(RuleManager::new(storage.clone()));
+        let slot = Slot::new(rule_manager.clone());
+        let stat_prepare_slot = stat::ResourceNodePrepareSlot::new(storage.clone());
+        let stat_slot = stat::ResourceNodeStatSlot::new(storage.clone());
+        let standalone_stat_slot = StandaloneStatSlot::new(rule_manager.clone());
+        let res_name = String::from("prioritized_check");
+        rule_manager.load_rules(vec![Arc::new(Rule {
+            resource: res_name.clone(),
+            calculate_strategy: CalculateStrategy::Direct,
+            control_strategy: ControlStrategy::Reject,
+            threshold: 1.0,
+            // a single bucket window, so that all the tokens are released when the next bucket starts
+            stat_interval_ms: 500,
+            ..Default::default()
+        })]);
+
+        let check = |prioritized: bool| {
+            let mut ctx = EntryContext::new();
+            let mut input = SentinelInput::new(1, 0);
+            input.set_prioritized(prioritized);
+            ctx.set_input(input);
+            ctx.set_resource(ResourceWrapper::new(
+                res_name.clone(),
+                ResourceType::Common,
+                TrafficType::Outbound,
+            ));
+            ctx.set_defer_wait(true);
+            stat_prepare_slot.prepare(&mut ctx);
+            slot.check(&mut ctx);
+            ctx
+        };
+        let pass = |mut ctx: EntryContext| {
+            utils::sleep_for_ns(ctx.take_wait_nanos());
+            stat_slot.on_entry_pass(&ctx);
+            standalone_stat_slot.on_entry_pass(&ctx);
+        };
+
+        let ctx = check(false);
+        assert!(ctx.result().is_pass());
+        assert!(!ctx.is_occupied());
+        pass(ctx);
+        assert!(check(false).result().is_blocked());
+        // occupies the token of the next bucket
+        let ctx = check(true);
+        assert!(ctx.result().is_pass());
+        assert!(ctx.is_occupied());
+        // the only token of the next bucket has been occupied
+        assert!(check(true).result().is_blocked());
+        // waits until the next bucket starts
+        pass(ctx);
+        assert!(check(false).result().is_blocked());
+
+        let node = storage.get_resource_node(&res_name).unwrap();
+        assert_eq!(node.sum(MetricEvent::Pass), 2);
+        assert_eq!(node.sum(MetricEvent::OccupiedPass), 1);
+    }
+
+    #[test]
+    fn occupied_within_threshold() {
+        let storage = Arc::new(NodeStorage::new());
+        let rule_manager = Arc::new(RuleManager::new(storage.clone()));
+        let slot = Slot::new(rule_manager.clone());
+        let stat_prepare_slot = stat::ResourceNodePrepareSlot::new(storage.clone());
+        let stat_slot = stat::ResourceNodeStatSlot::new(storage.clone());
+        let standalone_stat_slot = StandaloneStatSlot::new(rule_manager.clone());
+        let res_name = String::from("occupied_within_threshold");
+        let threshold = 3;
+        rule_manager.load_rules(vec![Arc::new(Rule {
+            resource: res_name.clone(),
+            calculate_strategy: CalculateStrategy::Direct,
+            control_strategy: ControlStrategy::Reject,
+            threshold: threshold as f64,
+            // a single bucket window
+            stat_interval_ms: 500,
+            ..Default::default()
+        })]);
+
+        let check = |prioritized: bool| {
+            let mut ctx = EntryContext::new();
+            let mut input = SentinelInput::new(1, 0);
+            input.set_prioritized(prioritized);
+            ctx.set_input(input);
+            ctx.set_resource(ResourceWrapper::new(
+                res_name.clone(),
+                ResourceType::Common,
+                TrafficType::Outbound,
+            ));
+            ctx.set_defer_wait(true);
+            stat_prepare_slot.prepare(&mut ctx);
+            slot.check(&mut ctx);
+            ctx
+        };
+        let pass = |ctx: &EntryContext| {
+            stat_slot.on_entry_pass(ctx);
+            standalone_stat_slot.on_entry_pass(ctx);
+        };
+        let tc = rule_manager
+            .get_traffic_controller_list_for(&res_name)
+            .pop()
+            .unwrap();
+        let window_pass = || tc.stat().read_only_metric().sum(MetricEvent::Pass);
+
+        for _ in 0..threshold {
+            let ctx = check(false);
+            assert!(ctx.result().is_pass());
+            pass(&ctx);
+        }
+        // all the tokens of the next window are occupied
+        let mut occupiers: Vec<EntryContext> = (0..threshold)
+            .map(|_| check(true))
+            .inspect(|ctx| assert!(ctx.is_occupied()))
+            .collect();
+        assert!(check(true).result().is_blocked());
+
+        // the next window starts, but the occupiers have not been recorded yet
+        utils::sleep_for_ns(occupiers[0].take_wait_nanos());
+        assert_eq!(window_pass(), 0);
+        assert!(check(false).result().is_blocked());
+        for ctx in &occupiers {
+            pass(ctx);
+        }
+        assert!(check(false).result().is_blocked());
+        assert_eq!(window_pass(), threshold);
+    }
+
+    #[test]
+    fn cluster_check() {
+        let storage = Arc::new(NodeStorage::new());
+        let rule_manager = Arc::new(RuleManager::new(storage.clone()));
+        let slot = Slot::new(rule_manager.clone());
+        let stat_prepare_slot = stat::ResourceNodePrepareSlot::new(storage.clone());
+        let stat_slot = stat::ResourceNodeStatSlot::new(storage);
+        let res_name = String::from("cluster_check");
+        let rule = Arc::new(Rule {
+            resource: res_name.clone(),
+            threshold: 5.0,
+            cluster_mode: true,
+            cluster_config: cluster::ClusterConfig { flow_id: 1 },
+            ..Default::default()
+        });
+        rule_manager.load_rules(vec![rule.clone()]);
+        let service = Arc::new(cluster::TokenService::new());
+        // the global threshold is lower than the local one
+        service.load_flow_rules(vec![Arc::new(Rule {
+            threshold: 2.0,
+            ..(*rule).clone()
+        })]);
+        let server = cluster::TokenServer::start("127.0.0.1:0", service).unwrap();
+        rule_manager.set_token_client(Arc::new(cluster::TokenClient::new(
+            server.local_addr().to_string(),
+            1000,
+        )));
+
+        let pass_count = || {
+            let mut passed = 0;
+            for _ in 0..10 {
+                let mut ctx = EntryContext::new();
+                ctx.set_input(SentinelInput::new(1, 0));
+                ctx.set_resource(ResourceWrapper::new(
+                    res_name.clone(),
+                    ResourceType::Common,
+                    TrafficType::Outbound,
+                ));
+                stat_prepare_slot.prepare(&mut ctx);
+                if slot.check(&mut ctx).is_pass() {
+                    stat_slot.on_entry_pass(&ctx);
+                    passed += 1;
+                }
+            }
+            passed
+        };
+        // limited by the token server
+        assert_eq!(pass_count(), 2);
+        // falls back to the local checking
+        drop(server);
+        assert_eq!(pass_count(), 3);
+    }
+
+    #[test]
+    #[ignore]
+    fn origin_check() {
+        let slot = Slot::default();
+        let stat_prepare_slot = stat::ResourceNodePrepareSlot::default();
+        let stat_slot = stat::ResourceNodeStatSlot::default();
+        let res_name = String::from("origin_check");
+        let r1 = Arc::new(Rule {
+            resource: res_name.clone(),
+            calculate_strategy: CalculateStrategy::Direct,
+            control_strategy: ControlStrategy::Reject,
+            limit_origin: "a".into(),
+            threshold: 10.0,
+            ..Default::default()
+        });
+        let r2 = Arc::new(Rule {
+            resource: res_name.clone(),
+            calculate_strategy: CalculateStrategy::Direct,
+            control_strategy: ControlStrategy::Reject,
+            limit_origin: LIMIT_ORIGIN_OTHER.into(),
+            threshold: 5.0,
+            ..Default::default()
+        });
+        load_rules_of_resource(&res_name, vec![r1, r2]).unwrap();
+
+        let new_ctx = |origin: &str| {
+            let mut ctx = EntryContext::new();
+            ctx.set_input(SentinelInput::new(1, 0));
+            ctx.set_resource(ResourceWrapper::new(
+                res_name.clone(),
+                ResourceType::Common,
+                TrafficType::Outbound,
+            ));
+            ctx.set_origin(origin.into());
+            stat_prepare_slot.prepare(&mut ctx);
+            ctx
+        };
+        let pass_count = |origin: &str| {
+            let mut passed = 0;
+            for _ in 0..20 {
+                let mut ctx = new_ctx(origin);
+                if slot.check(&mut ctx).is_pass() {
+                    stat_slot.on_entry_pass(&ctx);
+                    passed += 1;
+                }
+            }
+            passed
+        };
+        // limited by the rule with specific origin
+        assert_eq!(pass_count("a"), 10);
+        // limited by the rule for other origins, each origin has its own quota
+        assert_eq!(pass_count("b"), 5);
+        assert_eq!(pass_count("c"), 5);
+        // unknown origin is not limited by origin-aware rules
+        assert_eq!(pass_count(""), 20);
+        let node = stat::get_resource_node(&res_name).unwrap();
+        assert_eq!(node.origin_node_list().len(), 3);
+        clear_rules_of_resource(&res_name);
+
+        // the origins are not tracked without origin-aware rules
+        let mut ctx = new_ctx("d");
+        assert!(slot.check(&mut ctx).is_pass());
+        assert!(ctx.origin_node().is_none());
+        assert!(node.get_origin_node("d").is_none());
+    }
+
+    #[test]
+    #[ignore]
+    fn chain_check() {
+        let slot = Slot::default();
+        let stat_prepare_slot = stat::ResourceNodePrepareSlot::default();
+        let stat_slot = stat::ResourceNodeStatSlot::default();
+        let res_name = String::from("chain_check");
+        let r1 = Arc::new(Rule {
+            resource: res_name.clone(),
+            calculate_strategy: CalculateStrategy::Direct,
+            control_strategy: ControlStrategy::Reject,
+            relation_strategy: RelationStrategy::Chain,
+            ref_resource: "entrance_a".into(),
+            threshold: 10.0,
+            ..Default::default()
+        });
+        load_rules_of_resource(&res_name, vec![r1]).unwrap();
+
+        let pass_count = |context_name: &str| {
+            let mut passed = 0;
+            for _ in 0..20 {
+                let mut ctx = EntryContext::new();
+                ctx.set_input(SentinelInput::new(1, 0));
+                ctx.set_resource(ResourceWrapper::new(
+                    res_name.clone(),
+                    ResourceType::Common,
+                    TrafficType::Outbound,
+                ));
+                ctx.set_context_name(context_name.into());
+                stat_prepare_slot.prepare(&mut ctx);
+                if slot.check(&mut ctx).is_pass() {
+                    stat_slot.on_entry_pass(&ctx);
+                    passed += 1;
+                }
+            }
+            passed
+        };
+        // limited when reached through the entrance
+        assert_eq!(pass_count("entrance_a"), 10);
+        // not limited through other entrances
+        assert_eq!(pass_count("entrance_b"), 20);
+        let entrance = stat::get_entrance_node("entrance_a").unwrap();
+        assert_eq!(
+            entrance
+                .get_child_node(&res_name)
+                .unwrap()
+                .sum(MetricEvent::Pass),
+            10
+        );
+        clear_rules_of_resource(&res_name);
+    }
 }
@@ -5,11 +5,25 @@ use std::sync::Arc;
 
 const STAT_SLOT_ORDER: u32 = 3000;
 
-pub struct StandaloneStatSlot {}
+pub struct StandaloneStatSlot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl StandaloneStatSlot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        StandaloneStatSlot { rule_manager }
+    }
+}
+
+impl Default for StandaloneStatSlot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
 
 lazy_static! {
     pub static ref DEFAULT_STAND_ALONE_STAT_SLOT: Arc<StandaloneStatSlot> =
-        Arc::new(StandaloneStatSlot {});
+        Arc::new(StandaloneStatSlot::default());
 }
 
 pub fn default_stand_alone_stat_slot() -> Arc<StandaloneStatSlot> {
@@ -22,12 +36,25 @@ impl BaseSlot for StandaloneStatSlot {
     }
 }
 
+/// `release_occupied` releases the tokens occupied by the prioritized invocation,
+/// once it is recorded in the statistic or blocked.
+fn release_occupied(ctx: &EntryContext, tcs: &[Arc<Controller>]) {
+    for rule in ctx.occupied_rules() {
+        let occupied_tc = tcs
+            .iter()
+            .find(|tc| Arc::as_ptr(tc.rule()) as *const () == Arc::as_ptr(rule) as *const ());
+        if let Some(tc) = occupied_tc {
+            tc.release_occupied(ctx.input().batch_count());
+        }
+    }
+}
+
 impl StatSlot for StandaloneStatSlot {
     fn on_entry_pass(&self, ctx: &EntryContext) {
         let res = ctx.resource().name();
         let input = ctx.input();
-        let tcs = get_traffic_controller_list_for(res);
-        for tc in tcs {
+        let tcs = self.rule_manager.get_traffic_controller_list_for(res);
+        for tc in &tcs {
             if !tc.stat().reuse_global() {
                 tc.stat()
                     .write_only_metric()
@@ -35,9 +62,18 @@ impl StatSlot for StandaloneStatSlot {
                     .add_count(MetricEvent::Pass, input.batch_count() as u64);
             }
         }
+        // the statistic slots of resource nodes are executed before, so the occupied tokens are recorded now
+        release_occupied(ctx, &tcs);
     }
 
-    fn on_entry_blocked(&self, _ctx: &EntryContext, _block_error: BlockError) {}
+    fn on_entry_blocked(&self, ctx: &EntryContext, _block_error: BlockError) {
+        if ctx.is_occupied() {
+            let tcs = self
+                .rule_manager
+                .get_traffic_controller_list_for(ctx.resource().name());
+            release_occupied(ctx, &tcs);
+        }
+    }
 
     fn on_completed(&self, _ctx: &mut EntryContext) {}
 }
@@ -7,6 +7,10 @@
 //! - Otherwise, the threshold is `((water_mark - mem_low_water_mark)/(mem_high_water_mark - mem_low_water_mark)) *
 //! (high_mem_usage_threshold - low_mem_usage_threshold) + low_mem_usage_threshold`.
 //!
+//! CpuAdaptiveCalculator is the CPU adaptive counterpart,
+//! which maps the CPU usage (percentage) between Rule.cpu_low_water_mark and Rule.cpu_high_water_mark
+//! to the threshold between Rule.low_cpu_usage_threshold and Rule.high_cpu_usage_threshold in the same way.
+//!
 
 use super::Rule;
 use super::{Calculator, Controller};
@@ -59,6 +63,55 @@ impl Calculator for MemoryAdaptiveCalculator {
     }
 }
 
+#[derive(Debug)]
+pub struct CpuAdaptiveCalculator {
+    owner: Weak<Controller>,
+    cpu_low_water_mark: f64,
+    cpu_high_water_mark: f64,
+    low_cpu_usage_threshold: f64,
+    high_cpu_usage_threshold: f64,
+}
+
+impl CpuAdaptiveCalculator {
+    pub fn new(owner: Weak<Controller>, rule: Arc<Rule>) -> Self {
+        CpuAdaptiveCalculator {
+            owner,
+            cpu_low_water_mark: rule.cpu_low_water_mark,
+            cpu_high_water_mark: rule.cpu_high_water_mark,
+            low_cpu_usage_threshold: rule.low_cpu_usage_threshold as f64,
+            high_cpu_usage_threshold: rule.high_cpu_usage_threshold as f64,
+        }
+    }
+
+    fn threshold_of(&self, cpu: f64) -> f64 {
+        if cpu > self.cpu_high_water_mark {
+            self.high_cpu_usage_threshold
+        } else if cpu < self.cpu_low_water_mark {
+            self.low_cpu_usage_threshold
+        } else {
+            // linear mapping
+            (self.high_cpu_usage_threshold - self.low_cpu_usage_threshold)
+                / (self.cpu_high_water_mark - self.cpu_low_water_mark)
+                * (cpu - self.cpu_low_water_mark)
+                + self.low_cpu_usage_threshold
+        }
+    }
+}
+
+impl Calculator for CpuAdaptiveCalculator {
+    fn get_owner(&self) -> &Weak<Controller> {
+        &self.owner
+    }
+
+    fn set_owner(&mut self, owner: Weak<Controller>) {
+        self.owner = owner;
+    }
+
+    fn calculate_allowed_threshold(&self, _batch_count: u32, _flag: i32) -> f64 {
+        self.threshold_of(system_metric::current_cpu_usage() as f64)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -89,4 +142,20 @@ mod test {
         system_metric::set_memory_usage(3072);
         assert!((tc.calculate_allowed_threshold(0, 0) - 100.0).abs() < f64::EPSILON);
     }
+
+    #[test]
+    fn cpu_calculator() {
+        let tc = CpuAdaptiveCalculator {
+            owner: Weak::new(),
+            cpu_low_water_mark: 20.0,
+            cpu_high_water_mark: 80.0,
+            low_cpu_usage_threshold: 1000.0,
+            high_cpu_usage_threshold: 100.0,
+        };
+        assert!((tc.threshold_of(10.0) - 1000.0).abs() < f64::EPSILON);
+        assert!((tc.threshold_of(20.0) - 1000.0).abs() < f64::EPSILON);
+        assert!((tc.threshold_of(50.0) - 550.0).abs() < f64::EPSILON);
+        assert!((tc.threshold_of(80.0) - 100.0).abs() < f64::EPSILON);
+        assert!((tc.threshold_of(95.0) - 100.0).abs() < f64::EPSILON);
+    }
 }
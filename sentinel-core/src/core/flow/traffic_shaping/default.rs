@@ -1,6 +1,8 @@
 use super::{Calculator, Checker, Controller, Rule};
-use crate::base::{BlockType, MetricEvent, StatNode, TokenResult};
-use std::sync::{Arc, Weak};
+use crate::base::{BlockType, MetricEvent, ReadStat, StatNode, TokenResult};
+use crate::config;
+use crate::utils::curr_time_millis;
+use std::sync::{Arc, Mutex, Weak};
 
 /// Provide a determined threshold
 #[derive(Debug)]
@@ -37,11 +39,42 @@ impl Calculator for DirectCalculator {
 pub struct RejectChecker {
     owner: Weak<Controller>,
     rule: Arc<Rule>,
+    /// the tokens occupied by prioritized invocations, with the start timestamps of the buckets they wait for.
+    /// They are counted in the checking until the invocations are recorded in the statistic,
+    /// otherwise the window would be exceeded by the invocations passing when the bucket starts.
+    occupied: Mutex<Vec<(u64, u64)>>,
 }
 
 impl RejectChecker {
     pub fn new(owner: Weak<Controller>, rule: Arc<Rule>) -> Self {
-        RejectChecker { owner, rule }
+        RejectChecker {
+            owner,
+            rule,
+            occupied: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// `select_stat` selects the statistic to be checked,
+    /// e.g., origin-limited rules check the statistic of the origin
+    fn select_stat(&self, stat_node: Option<Arc<dyn StatNode>>) -> Arc<dyn ReadStat> {
+        match stat_node {
+            Some(stat_node) if self.rule.is_checked_on_selected_node() => stat_node,
+            _ => {
+                let owner = self.owner.upgrade().unwrap();
+                owner.stat().read_only_metric().clone()
+            }
+        }
+    }
+
+    /// `occupied_count` returns the occupied tokens which are not recorded yet.
+    /// The ones out of the statistic window are dropped, in case that the invocations are cancelled while waiting.
+    fn occupied_count(&self, occupied: &mut Vec<(u64, u64)>, now: u64) -> u64 {
+        let interval_ms = match self.rule.stat_interval_ms {
+            0 => config::metric_stat_interval_ms(),
+            interval_ms => interval_ms,
+        } as u64;
+        occupied.retain(|(start, _)| start + interval_ms > now);
+        occupied.iter().map(|(_, count)| count).sum()
     }
 }
 
@@ -56,13 +89,14 @@ impl Checker for RejectChecker {
 
     fn do_check(
         &self,
-        _stat_node: Option<Arc<dyn StatNode>>,
+        stat_node: Option<Arc<dyn StatNode>>,
         batch_count: u32,
         threshold: f64,
     ) -> TokenResult {
-        let owner = self.owner.upgrade().unwrap();
-        let read_only_metric = owner.stat().read_only_metric();
-        let cur_count = read_only_metric.sum(MetricEvent::Pass) as f64;
+        let occupied_count =
+            self.occupied_count(&mut self.occupied.lock().unwrap(), curr_time_millis());
+        let cur_count =
+            (self.select_stat(stat_node).sum(MetricEvent::Pass) + occupied_count) as f64;
         if cur_count + batch_count as f64 > threshold {
             TokenResult::new_blocked_with_cause(
                 BlockType::Flow,
@@ -74,4 +108,41 @@ impl Checker for RejectChecker {
             TokenResult::new_pass()
         }
     }
+
+    fn try_occupy(
+        &self,
+        stat_node: Option<Arc<dyn StatNode>>,
+        batch_count: u32,
+        threshold: f64,
+    ) -> Option<u64> {
+        let (next_start, remaining) = self
+            .select_stat(stat_node)
+            .next_bucket_sum(MetricEvent::Pass)?;
+        let mut occupied = self.occupied.lock().unwrap();
+        let now = curr_time_millis();
+        let occupied_count = self.occupied_count(&mut occupied, now);
+        if (remaining + occupied_count + batch_count as u64) as f64 > threshold {
+            return None;
+        }
+        match occupied.iter_mut().find(|(start, _)| *start == next_start) {
+            Some((_, count)) => *count += batch_count as u64,
+            None => occupied.push((next_start, batch_count as u64)),
+        }
+        Some(next_start.saturating_sub(now) * 1_000_000)
+    }
+
+    fn release_occupied(&self, batch_count: u32) {
+        let mut occupied = self.occupied.lock().unwrap();
+        // the invocations are released in the order of the buckets they wait for
+        let mut to_release = batch_count as u64;
+        for (_, count) in occupied.iter_mut() {
+            let released = to_release.min(*count);
+            *count -= released;
+            to_release -= released;
+            if to_release == 0 {
+                break;
+            }
+        }
+        occupied.retain(|(_, count)| *count > 0);
+    }
 }
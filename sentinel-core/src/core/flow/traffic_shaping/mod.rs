@@ -6,16 +6,19 @@ pub mod adaptive;
 pub mod default;
 /// Throttling checker
 pub mod throttling;
+/// Token bucket calculator and checker
+pub mod token_bucket;
 /// Warm Up calculator
 pub mod warmup;
 
 pub use adaptive::*;
 pub use default::*;
 pub use throttling::*;
+pub use token_bucket::*;
 pub use warmup::*;
 
 use super::Rule;
-use crate::base::{ReadStat, StatNode, TokenResult, WriteStat};
+use crate::base::{CompiledSchedule, ReadStat, StatNode, TokenResult, WriteStat};
 #[cfg(feature = "exporter")]
 use crate::core::base::rule::SentinelRule;
 use std::sync::{Arc, Mutex, Weak};
@@ -39,6 +42,25 @@ pub trait Checker: Send + Sync + std::fmt::Debug {
         batch_count: u32,
         threshold: f64,
     ) -> TokenResult;
+    /// `try_occupy` is called for prioritized invocations blocked by `do_check`.
+    /// It returns the nanoseconds to wait if the tokens of the next bucket are occupied,
+    /// `None` indicates the checker does not support occupying or there are no tokens left.
+    fn try_occupy(
+        &self,
+        _stat_node: Option<Arc<dyn StatNode>>,
+        _batch_count: u32,
+        _threshold: f64,
+    ) -> Option<u64> {
+        None
+    }
+    /// `release_occupied` is called when the prioritized invocation occupying the tokens is recorded in the statistic,
+    /// or blocked by other rules, so that its tokens are no longer counted by the checker.
+    fn release_occupied(&self, _batch_count: u32) {}
+    /// `queue_length` returns the number of the requests queueing in the checker,
+    /// it is always 0 for the checkers without queueing.
+    fn queue_length(&self) -> usize {
+        0
+    }
 }
 
 /// StandaloneStat indicates the independent statistic for each Traffic Shaping Controller
@@ -93,16 +115,21 @@ pub struct Controller {
     rule: Arc<Rule>,
     // stat is the statistic of current Traffic Shaping Controller
     stat: Arc<StandaloneStat>,
+    /// the schedule of the rule, compiled when the controller is generated
+    schedule: Option<CompiledSchedule>,
 }
 
 impl Controller {
     /// Please refer to the generators in the `rule_manager`
     pub fn new(rule: Arc<Rule>, stat: Arc<StandaloneStat>) -> Self {
+        // the schedule has been validated along with the rule
+        let schedule = rule.schedule.as_ref().and_then(|s| s.compile().ok());
         Controller {
             calculator: None,
             checker: None,
             rule,
             stat,
+            schedule,
         }
     }
 
@@ -110,6 +137,14 @@ impl Controller {
         &self.rule
     }
 
+    /// `is_active` indicates whether the rule is active now according to its schedule
+    pub fn is_active(&self) -> bool {
+        match &self.schedule {
+            Some(schedule) => schedule.is_active(),
+            None => true,
+        }
+    }
+
     pub fn get_checker(&self) -> &Arc<Mutex<dyn Checker>> {
         self.checker.as_ref().unwrap()
     }
@@ -146,4 +181,35 @@ impl Controller {
         let checker = checker.lock().unwrap();
         checker.do_check(Some(res_stat), batch_count, allowed_threshold)
     }
+
+    /// `queue_length` returns the number of the requests queueing in the controller, see `Checker::queue_length()`
+    pub fn queue_length(&self) -> usize {
+        let checker = self.checker.as_ref().unwrap();
+        let checker = checker.lock().unwrap();
+        checker.queue_length()
+    }
+
+    /// `try_occupy` tries to occupy the tokens of the next bucket for the prioritized invocation,
+    /// see `Checker::try_occupy()`
+    pub fn try_occupy(
+        &self,
+        res_stat: Arc<dyn StatNode>,
+        batch_count: u32,
+        flag: i32,
+    ) -> Option<u64> {
+        let calculator = self.calculator.as_ref().unwrap();
+        let calculator = calculator.lock().unwrap();
+        let allowed_threshold = calculator.calculate_allowed_threshold(batch_count, flag);
+
+        let checker = self.checker.as_ref().unwrap();
+        let checker = checker.lock().unwrap();
+        checker.try_occupy(Some(res_stat), batch_count, allowed_threshold)
+    }
+
+    /// `release_occupied` releases the tokens occupied by a prioritized invocation, see `Checker::release_occupied()`
+    pub fn release_occupied(&self, batch_count: u32) {
+        let checker = self.checker.as_ref().unwrap();
+        let checker = checker.lock().unwrap();
+        checker.release_occupied(batch_count)
+    }
 }
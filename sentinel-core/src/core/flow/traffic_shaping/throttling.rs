@@ -1,23 +1,51 @@
 //! Throttling indicates that pending requests will be throttled,
 //! wait in queue (until free capacity is available)
+//!
+//! Combined with the `WarmUp` calculate strategy, the interval between two requests
+//! follows the warm-up curve, i.e., the requests are paced slowly at the cold start,
+//! and the interval shrinks to `1 / threshold` as the resource warms up.
+//! The batch exceeding the allowed threshold at the moment is still rejected.
+//!
+//! The queueing requests pass in FIFO order, and the queue is bounded by
+//! `max_queueing_time_ms` and `max_queue_size` of the rule.
 
 use super::{Checker, Controller, Rule};
 use crate::base::{BlockType, StatNode, TokenResult};
 use crate::utils;
+use std::collections::VecDeque;
 use std::convert::TryInto;
-use std::sync::{
-    atomic::{AtomicI64, Ordering},
-    Arc, Weak,
-};
+use std::sync::{Arc, Mutex, Weak};
 
 static BLOCK_MSG_QUEUEING: &str = "flow throttling check blocked, threshold is <= 0.0";
+static BLOCK_MSG_QUEUE_FULL: &str = "flow throttling check blocked, the queue is full";
+
+/// `ThrottlingQueue` holds the expected pass time (in nanoseconds) of the queueing requests.
+/// The requests are admitted under the lock, and each one is expected to pass an interval after the previous one,
+/// so that they pass in FIFO order.
+#[derive(Debug, Default)]
+struct ThrottlingQueue {
+    /// the expected pass time of the last admitted request
+    last_passed_time: i64,
+    pending: VecDeque<i64>,
+}
+
+impl ThrottlingQueue {
+    /// `expire` removes the requests which have passed
+    fn expire(&mut self, curr_nano: i64) {
+        while self.pending.front().is_some_and(|&t| t <= curr_nano) {
+            self.pending.pop_front();
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct ThrottlingChecker {
     owner: Weak<Controller>,
     max_queueing_time_ns: i64,
+    /// `max_queue_size` bounds the number of queueing requests, 0 means unbounded
+    max_queue_size: usize,
     stat_interval_ns: i64,
-    last_passed_time: AtomicI64,
+    queue: Mutex<ThrottlingQueue>,
 }
 
 impl ThrottlingChecker {
@@ -37,8 +65,25 @@ impl ThrottlingChecker {
         ThrottlingChecker {
             owner,
             max_queueing_time_ns: utils::milli2nano(timeout_ms).try_into().unwrap(),
+            max_queue_size: rule.max_queue_size as usize,
             stat_interval_ns,
-            last_passed_time: AtomicI64::new(0),
+            queue: Mutex::new(ThrottlingQueue::default()),
+        }
+    }
+
+    fn blocked<T: std::fmt::Debug + Send + Sync + 'static>(
+        &self,
+        msg: &str,
+        snapshot: T,
+    ) -> TokenResult {
+        match self.owner.upgrade() {
+            Some(owner) => TokenResult::new_blocked_with_cause(
+                BlockType::Flow,
+                msg.into(),
+                owner.rule().clone(),
+                Arc::new(snapshot),
+            ),
+            None => TokenResult::new_blocked_with_msg(BlockType::Flow, msg.into()),
         }
     }
 }
@@ -62,25 +107,8 @@ impl Checker for ThrottlingChecker {
         if batch_count == 0 {
             return TokenResult::new_pass();
         }
-        let owner = self.owner.upgrade();
-
         if threshold <= 0.0 {
-            match owner {
-                Some(owner) => {
-                    return TokenResult::new_blocked_with_cause(
-                        BlockType::Flow,
-                        BLOCK_MSG_QUEUEING.into(),
-                        owner.rule().clone(),
-                        Arc::new(threshold),
-                    );
-                }
-                None => {
-                    return TokenResult::new_blocked_with_msg(
-                        BlockType::Flow,
-                        BLOCK_MSG_QUEUEING.into(),
-                    );
-                }
-            }
+            return self.blocked(BLOCK_MSG_QUEUEING, threshold);
         }
         let batch_count = batch_count as f64;
         if batch_count > threshold {
@@ -93,82 +121,44 @@ impl Checker for ThrottlingChecker {
         // The interval between two requests (in nanoseconds).
         let interval_ns = (batch_count.ceil() / threshold * (self.stat_interval_ns as f64)) as i64;
 
-        let loaded_last_passed_time = self.last_passed_time.load(Ordering::SeqCst);
+        let mut queue = self.queue.lock().unwrap();
+        queue.expire(curr_nano);
         // Expected pass time of this request.
-        let expected_time = loaded_last_passed_time + interval_ns;
+        let expected_time = queue.last_passed_time + interval_ns;
         // It has been more than `interval_ns` not running this task
-        if expected_time <= curr_nano
-            && self
-                .last_passed_time
-                .compare_exchange(
-                    loaded_last_passed_time,
-                    curr_nano,
-                    Ordering::SeqCst,
-                    Ordering::Relaxed,
-                )
-                .is_ok()
-        {
+        if expected_time <= curr_nano {
+            queue.last_passed_time = curr_nano;
             return TokenResult::new_pass();
         }
         // It has been run recently, need queueing, check queueing time
-        let estimated_queue_duration =
-            self.last_passed_time.load(Ordering::SeqCst) + interval_ns - curr_nano;
-        if estimated_queue_duration > self.max_queueing_time_ns {
-            match owner {
-                Some(owner) => {
-                    return TokenResult::new_blocked_with_cause(
-                        BlockType::Flow,
-                        BLOCK_MSG_QUEUEING.into(),
-                        owner.rule().clone(),
-                        Arc::new(estimated_queue_duration),
-                    );
-                }
-                None => {
-                    return TokenResult::new_blocked_with_msg(
-                        BlockType::Flow,
-                        BLOCK_MSG_QUEUEING.into(),
-                    );
-                }
-            }
-        }
-        // It is expected to run at `expected_time`
-        let expected_time = self
-            .last_passed_time
-            .fetch_add(interval_ns, Ordering::SeqCst)
-            + interval_ns;
         let estimated_queue_duration = expected_time - curr_nano;
         if estimated_queue_duration > self.max_queueing_time_ns {
-            // Subtract the interval.
-            self.last_passed_time
-                .fetch_sub(interval_ns, Ordering::SeqCst);
-            match owner {
-                Some(owner) => {
-                    return TokenResult::new_blocked_with_cause(
-                        BlockType::Flow,
-                        BLOCK_MSG_QUEUEING.into(),
-                        owner.rule().clone(),
-                        Arc::new(estimated_queue_duration),
-                    );
-                }
-                None => {
-                    return TokenResult::new_blocked_with_msg(
-                        BlockType::Flow,
-                        BLOCK_MSG_QUEUEING.into(),
-                    );
-                }
-            }
+            return self.blocked(BLOCK_MSG_QUEUEING, estimated_queue_duration);
         }
-        if estimated_queue_duration > 0 {
-            TokenResult::new_should_wait(estimated_queue_duration.try_into().unwrap())
-        } else {
-            TokenResult::new_should_wait(0)
+        if self.max_queue_size > 0 && queue.pending.len() >= self.max_queue_size {
+            return self.blocked(BLOCK_MSG_QUEUE_FULL, queue.pending.len());
         }
+        // It is expected to run at `expected_time`
+        queue.last_passed_time = expected_time;
+        queue.pending.push_back(expected_time);
+        TokenResult::new_should_wait(estimated_queue_duration.try_into().unwrap())
+    }
+
+    fn queue_length(&self) -> usize {
+        let curr_nano: i64 = utils::curr_time_nanos().try_into().unwrap();
+        let mut queue = self.queue.lock().unwrap();
+        queue.expire(curr_nano);
+        queue.pending.len()
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::super::{Calculator, StandaloneStat, WarmUpCalculator};
     use super::*;
+    use crate::base::{nop_read_stat, ResourceType};
+    use crate::core::flow::{CalculateStrategy, ControlStrategy};
+    use crate::core::stat::ResourceNode;
     use crate::utils::unix_time_unit_offset;
     use std::sync::atomic::{AtomicU32, Ordering};
 
@@ -246,6 +236,93 @@ mod test {
         }
     }
 
+    #[test]
+    fn bounded_queue() {
+        let threshold = 10.0;
+        let rule = Arc::new(Rule {
+            max_queueing_time_ms: 10000,
+            max_queue_size: 3,
+            ..Default::default()
+        });
+        let tc = ThrottlingChecker::new(Weak::new(), rule);
+
+        assert!(tc.do_check(None, 1, threshold).is_pass());
+        let mut waits = Vec::new();
+        for _ in 0..3 {
+            let res = tc.do_check(None, 1, threshold);
+            assert!(res.is_wait());
+            waits.push(res.nanos_to_wait());
+        }
+        // FIFO, each request waits an interval longer than the previous one
+        assert!(waits.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(tc.queue_length(), 3);
+        // the queue is full, though the queueing time is allowed
+        let res = tc.do_check(None, 1, threshold);
+        assert!(res.is_blocked());
+        assert_eq!(res.block_err().unwrap().block_msg(), BLOCK_MSG_QUEUE_FULL);
+        // the first queueing request passes after an interval
+        utils::sleep_for_ms(110);
+        assert_eq!(tc.queue_length(), 2);
+        assert!(tc.do_check(None, 1, threshold).is_wait());
+    }
+
+    #[test]
+    fn paced_warm_up() {
+        let rule = Arc::new(Rule {
+            resource: "paced_warm_up".into(),
+            threshold: 10.0,
+            calculate_strategy: CalculateStrategy::WarmUp,
+            control_strategy: ControlStrategy::Throttling,
+            warm_up_period_sec: 10,
+            warm_up_cold_factor: 3,
+            max_queueing_time_ms: 1000,
+            ..Default::default()
+        });
+        let node = Arc::new(ResourceNode::new(
+            "paced_warm_up".into(),
+            ResourceType::Common,
+        ));
+        // the interval between the first two requests, when `stored_tokens` are left in the bucket
+        let interval_ms = |stored_tokens: u64| {
+            let stat = Arc::new(StandaloneStat::new(false, nop_read_stat(), None));
+            let calculator = Arc::new(Mutex::new(WarmUpCalculator::new(Weak::new(), rule.clone())));
+            let checker = Arc::new(Mutex::new(ThrottlingChecker::new(
+                Weak::new(),
+                rule.clone(),
+            )));
+            let mut tc = Controller::new(rule.clone(), stat);
+            tc.set_calculator(calculator.clone());
+            tc.set_checker(checker.clone());
+            let tc = Arc::new(tc);
+            calculator.lock().unwrap().set_owner(Arc::downgrade(&tc));
+            checker.lock().unwrap().set_owner(Arc::downgrade(&tc));
+            calculator
+                .lock()
+                .unwrap()
+                .freeze_stored_tokens(stored_tokens);
+
+            // the batch exceeding the allowed threshold is rejected rather than paced
+            if stored_tokens == 100 {
+                assert!(tc.perform_checking(node.clone(), 5, 0).is_blocked());
+            }
+            assert!(tc.perform_checking(node.clone(), 1, 0).is_pass());
+            let res = tc.perform_checking(node.clone(), 1, 0);
+            assert!(res.is_wait());
+            res.nanos_to_wait() / 1_000_000
+        };
+
+        // `max_token` is 100 and `warning_token` is 50 for the rule,
+        // the tokens are consumed as the resource warms up
+        let intervals: Vec<u64> = [100, 75, 50, 0].into_iter().map(interval_ms).collect();
+        assert!(intervals.windows(2).all(|w| w[0] >= w[1]));
+        // 1 / (threshold / cold_factor) at the cold start
+        assert!(intervals[0] > 250 && intervals[0] <= 300);
+        assert!(intervals[1] > 150 && intervals[1] <= 200);
+        // 1 / threshold when warmed up
+        assert!(intervals[2] > 50 && intervals[2] <= 100);
+        assert!(intervals[3] > 50 && intervals[3] <= 100);
+    }
+
     #[test]
     fn parallel_queueing() {
         let interval_ms = 10000;
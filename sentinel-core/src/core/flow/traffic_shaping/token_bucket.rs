@@ -0,0 +1,159 @@
+//! `TokenBucketCalculator` and `TokenBucketChecker` implement the **Token Bucket** algorithm.
+//!
+//! The bucket holds at most `Rule.burst` tokens and is refilled with `Rule.refill_rate` tokens per second.
+//! Each invocation takes `batch_count` tokens, so that the steady-state rate is limited by `refill_rate`,
+//! while short spikes up to `burst` are absorbed by the tokens accumulated when the traffic is low.
+
+use super::{Calculator, Checker, Controller, Rule};
+use crate::base::{BlockType, StatNode, TokenResult};
+use crate::utils;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Provide the refill rate (tokens per second) of the token bucket as the threshold
+#[derive(Debug)]
+pub struct TokenBucketCalculator {
+    owner: Weak<Controller>,
+    refill_rate: f64,
+}
+
+impl TokenBucketCalculator {
+    pub fn new(owner: Weak<Controller>, rule: Arc<Rule>) -> Self {
+        TokenBucketCalculator {
+            owner,
+            refill_rate: rule.refill_rate,
+        }
+    }
+}
+
+impl Calculator for TokenBucketCalculator {
+    fn get_owner(&self) -> &Weak<Controller> {
+        &self.owner
+    }
+
+    fn set_owner(&mut self, owner: Weak<Controller>) {
+        self.owner = owner;
+    }
+
+    fn calculate_allowed_threshold(&self, _batch_count: u32, _flag: i32) -> f64 {
+        self.refill_rate
+    }
+}
+
+#[derive(Debug)]
+pub struct TokenBucketChecker {
+    owner: Weak<Controller>,
+    rule: Arc<Rule>,
+    capacity: f64,
+    /// the stored tokens and the last time (in ms) the bucket was refilled
+    bucket: Mutex<(f64, u64)>,
+}
+
+impl TokenBucketChecker {
+    pub fn new(owner: Weak<Controller>, rule: Arc<Rule>) -> Self {
+        let capacity = rule.burst as f64;
+        TokenBucketChecker {
+            owner,
+            rule,
+            capacity,
+            // the bucket is full at the beginning
+            bucket: Mutex::new((capacity, utils::curr_time_millis())),
+        }
+    }
+
+    fn check_at(&self, now: u64, batch_count: u32, refill_rate: f64) -> TokenResult {
+        // the bucket never holds enough tokens for such a batch
+        if batch_count as f64 > self.capacity {
+            return TokenResult::new_blocked_with_cause(
+                BlockType::Flow,
+                "flow token bucket check blocked: batch count exceeds burst".into(),
+                self.rule.clone(),
+                Arc::new(batch_count),
+            );
+        }
+        let mut bucket = self.bucket.lock().unwrap();
+        if now > bucket.1 {
+            let refilled = (now - bucket.1) as f64 * refill_rate / 1000.0;
+            bucket.0 = self.capacity.min(bucket.0 + refilled);
+            bucket.1 = now;
+        }
+        if bucket.0 < batch_count as f64 {
+            return TokenResult::new_blocked_with_cause(
+                BlockType::Flow,
+                "flow token bucket check blocked".into(),
+                self.rule.clone(),
+                Arc::new(bucket.0),
+            );
+        }
+        bucket.0 -= batch_count as f64;
+        TokenResult::new_pass()
+    }
+}
+
+impl Checker for TokenBucketChecker {
+    fn get_owner(&self) -> &Weak<Controller> {
+        &self.owner
+    }
+
+    fn set_owner(&mut self, owner: Weak<Controller>) {
+        self.owner = owner;
+    }
+
+    fn do_check(
+        &self,
+        _stat_node: Option<Arc<dyn StatNode>>,
+        batch_count: u32,
+        threshold: f64,
+    ) -> TokenResult {
+        self.check_at(utils::curr_time_millis(), batch_count, threshold)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn burst_and_refill() {
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            burst: 5,
+            refill_rate: 10.0,
+            ..Default::default()
+        });
+        let checker = TokenBucketChecker::new(Weak::new(), rule);
+        let start = checker.bucket.lock().unwrap().1;
+        // the spike is absorbed by the full bucket
+        for _ in 0..5 {
+            assert!(checker.check_at(start, 1, 10.0).is_pass());
+        }
+        assert!(checker.check_at(start, 1, 10.0).is_blocked());
+        // 1 token is refilled every 100 ms
+        assert!(checker.check_at(start + 50, 1, 10.0).is_blocked());
+        assert!(checker.check_at(start + 100, 1, 10.0).is_pass());
+        assert!(checker.check_at(start + 100, 1, 10.0).is_blocked());
+        // the tokens never exceed the burst capacity
+        assert!(checker.check_at(start + 10_000, 5, 10.0).is_pass());
+        assert!(checker.check_at(start + 10_000, 1, 10.0).is_blocked());
+    }
+
+    #[test]
+    fn batch_exceeds_burst() {
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            burst: 5,
+            refill_rate: 10.0,
+            ..Default::default()
+        });
+        let checker = TokenBucketChecker::new(Weak::new(), rule);
+        let start = checker.bucket.lock().unwrap().1;
+        let res = checker.check_at(start + 10_000, 6, 10.0);
+        assert!(res.is_blocked());
+        assert!(res
+            .block_err()
+            .unwrap()
+            .block_msg()
+            .contains("exceeds burst"));
+        // the tokens are not consumed by the blocked batch
+        assert!(checker.check_at(start + 10_000, 5, 10.0).is_pass());
+    }
+}
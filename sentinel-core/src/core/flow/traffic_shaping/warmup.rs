@@ -91,6 +91,13 @@ impl WarmUpCalculator {
 
         std::cmp::min(new_value, self.max_token)
     }
+
+    /// `freeze_stored_tokens` pins the stored tokens, so that the allowed threshold is not synced with the passed QPS
+    #[cfg(test)]
+    pub(crate) fn freeze_stored_tokens(&self, stored_tokens: u64) {
+        self.stored_tokens.store(stored_tokens, Ordering::SeqCst);
+        self.last_filled_time.store(u64::MAX, Ordering::SeqCst);
+    }
 }
 
 impl Calculator for WarmUpCalculator {
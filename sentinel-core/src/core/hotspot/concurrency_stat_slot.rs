@@ -9,11 +9,25 @@ use std::sync::{atomic::Ordering, Arc};
 const STAT_SLOT_ORDER: u32 = 4000;
 
 /// ConcurrencyStatSlot is to record the Concurrency statistic for all arguments
-pub struct ConcurrencyStatSlot {}
+pub struct ConcurrencyStatSlot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl ConcurrencyStatSlot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        ConcurrencyStatSlot { rule_manager }
+    }
+}
+
+impl Default for ConcurrencyStatSlot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
 
 lazy_static! {
     pub static ref DEFAULT_STAND_ALONE_STAT_SLOT: Arc<ConcurrencyStatSlot> =
-        Arc::new(ConcurrencyStatSlot {});
+        Arc::new(ConcurrencyStatSlot::default());
 }
 
 pub fn default_stand_alone_stat_slot() -> Arc<ConcurrencyStatSlot> {
@@ -29,7 +43,7 @@ impl BaseSlot for ConcurrencyStatSlot {
 impl StatSlot for ConcurrencyStatSlot {
     fn on_entry_pass(&self, ctx: &EntryContext) {
         let res = ctx.resource().name();
-        let tcs = get_traffic_controller_list_for(res);
+        let tcs = self.rule_manager.get_traffic_controller_list_for(res);
         for tc in tcs {
             if tc.rule().metric_type != MetricType::Concurrency {
                 continue;
@@ -52,7 +66,7 @@ impl StatSlot for ConcurrencyStatSlot {
 
     fn on_completed(&self, ctx: &mut EntryContext) {
         let res = ctx.resource().name();
-        let tcs = get_traffic_controller_list_for(res);
+        let tcs = self.rule_manager.get_traffic_controller_list_for(res);
         for tc in tcs {
             if tc.rule().metric_type != MetricType::Concurrency {
                 continue;
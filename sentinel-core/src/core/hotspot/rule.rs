@@ -1,5 +1,6 @@
 use crate::{
-    base::{ParamKey, SentinelRule},
+    base::{ParamKey, RuleMode, Schedule, SentinelRule},
+    cluster::ClusterConfig,
     Error,
 };
 use serde::{Deserialize, Serialize};
@@ -90,8 +91,20 @@ pub struct Rule {
     pub duration_in_sec: u64,
     /// `params_max_capacity` is the max capacity of cache statistic
     pub params_max_capacity: usize,
-    /// `specific_items` indicates the special threshold for specific value
+    /// `specific_items` indicates the special threshold for specific value,
+    /// the keys are matched with the args by their values, e.g., the key `"100"` matches the arg `100u32`, see `ParamKey`
     pub specific_items: HashMap<ParamKey, u64>,
+    /// `mode` indicates whether the invocations exceeding the threshold are blocked,
+    /// or only recorded in shadow (dry-run) mode.
+    pub mode: RuleMode,
+    /// `schedule` indicates when the rule is active, the rule is always active if it is `None`.
+    pub schedule: Option<Schedule>,
+    /// `cluster_mode` indicates whether the tokens of each parameter are requested from the token server,
+    /// so that the threshold is shared by all the instances. It falls back to the local checking
+    /// when the token server is not available. Only `MetricType::QPS` with `ControlStrategy::Reject` is supported.
+    /// The tokens are requested synchronously, which blocks the calling thread, see `cluster` for details.
+    pub cluster_mode: bool,
+    pub cluster_config: ClusterConfig,
 }
 
 impl Default for Rule {
@@ -112,6 +125,10 @@ impl Default for Rule {
             duration_in_sec: 0,
             params_max_capacity: 0,
             specific_items: HashMap::default(),
+            mode: RuleMode::default(),
+            schedule: None,
+            cluster_mode: false,
+            cluster_config: ClusterConfig::default(),
         }
     }
 }
@@ -152,6 +169,21 @@ impl SentinelRule for Rule {
                 "param index and param key are mutually exclusive",
             ));
         }
+        if let Some(schedule) = &self.schedule {
+            schedule.is_valid()?;
+        }
+        if self.cluster_mode {
+            if self.metric_type != MetricType::QPS
+                || self.control_strategy != ControlStrategy::Reject
+            {
+                return Err(Error::msg(
+                    "only MetricType::QPS with ControlStrategy::Reject is supported in cluster mode",
+                ));
+            }
+            if self.cluster_config.flow_id == 0 {
+                return Err(Error::msg("flow_id must be great than 0 in cluster mode"));
+            }
+        }
         Ok(())
     }
 }
@@ -167,6 +199,10 @@ impl PartialEq for Rule {
             && self.threshold == other.threshold
             && self.duration_in_sec == other.duration_in_sec
             && self.specific_items == other.specific_items
+            && self.mode == other.mode
+            && self.schedule == other.schedule
+            && self.cluster_mode == other.cluster_mode
+            && self.cluster_config == other.cluster_config
             && ((self.control_strategy == ControlStrategy::Reject
                 && self.burst_count == other.burst_count)
                 || (self.control_strategy == ControlStrategy::Throttling
@@ -218,6 +254,20 @@ mod test {
         rule.is_valid().unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "flow_id must be great than 0 in cluster mode")]
+    fn invalid_cluster() {
+        let rule = Rule {
+            resource: "abc".into(),
+            metric_type: MetricType::QPS,
+            control_strategy: ControlStrategy::Reject,
+            duration_in_sec: 1,
+            cluster_mode: true,
+            ..Default::default()
+        };
+        rule.is_valid().unwrap();
+    }
+
     #[test]
     fn test_eq() {
         let mut specific_items: HashMap<ParamKey, u64> = HashMap::new();
@@ -236,6 +286,10 @@ mod test {
             duration_in_sec: 1,
             params_max_capacity: 10000,
             specific_items: specific_items.clone(),
+            mode: RuleMode::Enforce,
+            schedule: None,
+            cluster_mode: false,
+            cluster_config: ClusterConfig::default(),
         };
         let rule2 = Rule {
             id: "abc".into(),
@@ -250,7 +304,25 @@ mod test {
             duration_in_sec: 1,
             params_max_capacity: 10000,
             specific_items,
+            mode: RuleMode::Enforce,
+            schedule: None,
+            cluster_mode: false,
+            cluster_config: ClusterConfig::default(),
         };
         assert_eq!(rule1, rule2);
     }
+
+    #[test]
+    fn display_bytes_items() {
+        let rule = Rule {
+            resource: "abc".into(),
+            specific_items: HashMap::from([
+                (ParamKey::from(vec![0xff, 0xfe]), 1),
+                ("sss".into(), 2),
+            ]),
+            ..Default::default()
+        };
+        let decoded: Rule = serde_json::from_str(&rule.to_string()).unwrap();
+        assert_eq!(decoded.specific_items, rule.specific_items);
+    }
 }
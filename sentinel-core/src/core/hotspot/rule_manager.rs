@@ -1,5 +1,5 @@
 use super::*;
-use crate::{base::SentinelRule, logging, utils, Error, Result};
+use crate::{base::SentinelRule, cluster::TokenClient, logging, utils, Error, Result};
 use lazy_static::lazy_static;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, RwLock};
@@ -32,8 +32,26 @@ lazy_static! {
 
         RwLock::new(gen_fun_map)
     };
-    static ref CONTROLLER_MAP: RwLock<ControllerMap> = RwLock::new(HashMap::new());
-    static ref RULE_MAP: Mutex<RuleMap> = Mutex::new(HashMap::new());
+    static ref DEFAULT_RULE_MANAGER: Arc<RuleManager> = Arc::new(RuleManager::new());
+}
+
+/// `RuleManager` holds the hotspot param flow rules and the traffic controllers of a Sentinel instance.
+pub struct RuleManager {
+    controller_map: RwLock<ControllerMap>,
+    rule_map: Mutex<RuleMap>,
+    /// the token client used by the cluster mode rules, see `crate::cluster`
+    token_client: RwLock<Option<Arc<TokenClient>>>,
+}
+
+impl Default for RuleManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `default_rule_manager` returns the rule manager used by the default Sentinel instance
+pub fn default_rule_manager() -> Arc<RuleManager> {
+    DEFAULT_RULE_MANAGER.clone()
 }
 
 pub(super) use gen_fns::*;
@@ -75,248 +93,319 @@ mod gen_fns {
     }
 }
 
-pub fn get_traffic_controller_list_for(res: &String) -> Vec<Arc<Controller>> {
-    CONTROLLER_MAP
-        .read()
-        .unwrap()
-        .get(res)
-        .unwrap_or(&Vec::new())
-        .clone()
-}
+impl RuleManager {
+    pub fn new() -> Self {
+        RuleManager {
+            controller_map: RwLock::new(HashMap::new()),
+            rule_map: Mutex::new(HashMap::new()),
+            token_client: RwLock::new(None),
+        }
+    }
 
-fn log_rule_update(map: &RuleMap) {
-    if map.is_empty() {
-        logging::info!("[HotspotRuleManager] Hotspot param flow rules were cleared")
-    } else {
-        logging::info!(
-            "[HotspotRuleManager] Hotspot param flow rules were loaded: {:?}",
-            map.values()
-        )
+    /// `set_token_client` sets the token client used by the cluster mode rules of this rule manager.
+    pub fn set_token_client(&self, client: Arc<TokenClient>) {
+        *self.token_client.write().unwrap() = Some(client);
     }
-}
 
-pub fn append_rule(rule: Arc<Rule>) -> bool {
-    if RULE_MAP
-        .lock()
-        .unwrap()
-        .get(&rule.resource)
-        .unwrap_or(&HashSet::new())
-        .contains(&rule)
-    {
-        return false;
+    /// `clear_token_client` removes the token client, then the cluster mode rules fall back to the local checking.
+    pub fn clear_token_client(&self) {
+        *self.token_client.write().unwrap() = None;
     }
-    match rule.is_valid() {
-        Ok(_) => {
-            RULE_MAP
-                .lock()
-                .unwrap()
-                .entry(rule.resource.clone())
-                .or_default()
-                .insert(Arc::clone(&rule));
-        }
-        Err(err) => logging::warn!(
-            "[Hot Spot append_rule] Ignoring invalid flow rule {:?}, reason: {:?}",
-            rule,
-            err
-        ),
+
+    pub fn token_client(&self) -> Option<Arc<TokenClient>> {
+        self.token_client.read().unwrap().clone()
     }
-    let mut placeholder = Vec::new();
-    let new_tcs_of_res = build_resource_traffic_shaping_controller(
-        &rule.resource,
-        RULE_MAP.lock().unwrap().get(&rule.resource).unwrap(),
-        CONTROLLER_MAP
-            .write()
-            .unwrap()
-            .get_mut(&rule.resource)
-            .unwrap_or(&mut placeholder),
-    );
-    if !new_tcs_of_res.is_empty() {
-        CONTROLLER_MAP
-            .write()
+
+    pub fn get_traffic_controller_list_for(&self, res: &String) -> Vec<Arc<Controller>> {
+        self.controller_map
+            .read()
             .unwrap()
-            .entry(rule.resource.clone())
-            .or_default()
-            .push(Arc::clone(&new_tcs_of_res[0]));
+            .get(res)
+            .unwrap_or(&Vec::new())
+            .clone()
     }
-    true
-}
 
-/// `load_rules` loads the given hotspot param flow rules to the rule manager, while all previous rules will be replaced.
-/// The returned `bool` indicates whether do real load operation, if the rules is the same with previous rules, return false
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
-pub fn load_rules(rules: Vec<Arc<Rule>>) -> bool {
-    let mut rule_map: RuleMap = HashMap::new();
-    for rule in rules {
-        let entry = rule_map.entry(rule.resource.clone()).or_default();
-        entry.insert(rule);
+    /// `get_active_traffic_controller_list_for` returns the controllers whose rules are active now,
+    /// according to the schedules of the rules, see `Controller::is_active()`
+    pub fn get_active_traffic_controller_list_for(&self, res: &String) -> Vec<Arc<Controller>> {
+        let mut controllers = self.get_traffic_controller_list_for(res);
+        controllers.retain(|tc| tc.is_active());
+        controllers
     }
 
-    let mut global_rule_map = RULE_MAP.lock().unwrap();
-    if *global_rule_map == rule_map {
-        logging::info!(
-            "[HotSpot] Load rules is the same with current rules, so ignore load operation."
+    pub fn append_rule(&self, rule: Arc<Rule>) -> bool {
+        if self
+            .rule_map
+            .lock()
+            .unwrap()
+            .get(&rule.resource)
+            .unwrap_or(&HashSet::new())
+            .contains(&rule)
+        {
+            return false;
+        }
+        match rule.is_valid() {
+            Ok(_) => {
+                self.rule_map
+                    .lock()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(Arc::clone(&rule));
+            }
+            Err(err) => logging::warn!(
+                "[Hot Spot append_rule] Ignoring invalid flow rule {:?}, reason: {:?}",
+                rule,
+                err
+            ),
+        }
+        let mut placeholder = Vec::new();
+        let new_tcs_of_res = build_resource_traffic_shaping_controller(
+            &rule.resource,
+            self.rule_map.lock().unwrap().get(&rule.resource).unwrap(),
+            self.controller_map
+                .write()
+                .unwrap()
+                .get_mut(&rule.resource)
+                .unwrap_or(&mut placeholder),
         );
-        return false;
+        if !new_tcs_of_res.is_empty() {
+            self.controller_map
+                .write()
+                .unwrap()
+                .entry(rule.resource.clone())
+                .or_default()
+                .push(Arc::clone(&new_tcs_of_res[0]));
+        }
+        true
     }
-    // when rule_map is different with global one, update the global one
-    // ignore invalid rules
-    let mut valid_rules_map = HashMap::with_capacity(rule_map.len());
-    for (res, rules) in &rule_map {
-        let mut valid_rules = HashSet::new();
+
+    /// `load_rules` loads the given hotspot param flow rules to the rule manager, while all previous rules will be replaced.
+    /// The returned `bool` indicates whether do real load operation, if the rules is the same with previous rules, return false
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn load_rules(&self, rules: Vec<Arc<Rule>>) -> bool {
+        let mut rule_map: RuleMap = HashMap::new();
         for rule in rules {
+            let entry = rule_map.entry(rule.resource.clone()).or_default();
+            entry.insert(rule);
+        }
+
+        let mut global_rule_map = self.rule_map.lock().unwrap();
+        if *global_rule_map == rule_map {
+            logging::info!(
+                "[HotSpot] Load rules is the same with current rules, so ignore load operation."
+            );
+            return false;
+        }
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
+        let mut valid_rules_map = HashMap::with_capacity(rule_map.len());
+        for (res, rules) in &rule_map {
+            let mut valid_rules = HashSet::new();
+            for rule in rules {
+                match rule.is_valid() {
+                    Ok(_) => {valid_rules.insert(Arc::clone(rule));},
+                    Err(err) => logging::warn!(
+                        "[HotSpot onRuleUpdate] Ignoring invalid hotspot param flow rule {:?}, reason: {:?}",
+                        rule,
+                        err
+                    ),
+                }
+            }
+            if !valid_rules.is_empty() {
+                valid_rules_map.insert(res.clone(), valid_rules);
+            }
+        }
+
+        let start = utils::curr_time_nanos();
+        let mut controller_map = self.controller_map.write().unwrap();
+        let mut valid_controller_map = HashMap::with_capacity(valid_rules_map.len());
+
+        // build controller_map according to valid rules
+        for (res, rules) in valid_rules_map.iter() {
+            let mut placeholder = Vec::new();
+            let new_tcs_of_res = build_resource_traffic_shaping_controller(
+                res,
+                rules,
+                controller_map.get_mut(res).unwrap_or(&mut placeholder),
+            );
+            if !new_tcs_of_res.is_empty() {
+                valid_controller_map.insert(res.clone(), new_tcs_of_res);
+            }
+        }
+        *controller_map = valid_controller_map;
+        *global_rule_map = rule_map;
+        drop(global_rule_map);
+        drop(controller_map);
+        logging::debug!(
+            "[HotSpot load_rules] Time statistic(ns) for updating hotspot param flow rule, time cost {}",
+            utils::curr_time_nanos() - start
+        );
+
+        log_rule_update(&valid_rules_map);
+        true
+    }
+
+    /// `load_rules_of_resource` loads the given resource's flow rules to the rule manager, while all previous resource's rules will be replaced.
+    /// The first returned value indicates whether do real load operation, if the rules is the same with previous resource's rules, return false
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn load_rules_of_resource(&self, res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+        if res.is_empty() {
+            return Err(Error::msg("empty resource"));
+        }
+        let rules: HashSet<_> = rules.into_iter().collect();
+        let mut global_rule_map = self.rule_map.lock().unwrap();
+        let mut global_controller_map = self.controller_map.write().unwrap();
+        // clear resource rules
+        if rules.is_empty() {
+            global_rule_map.remove(res);
+            global_controller_map.remove(res);
+            logging::info!("[HotSpot] clear resource level rules, resource {}", res);
+            return Ok(true);
+        }
+        // load resource level rules
+        if global_rule_map.get(res).unwrap_or(&HashSet::new()) == &rules {
+            logging::info!("[HotSpot] Load resource level rules is the same with current resource level rules, so ignore load operation.");
+            return Ok(false);
+        }
+
+        let mut valid_res_rules = HashSet::with_capacity(res.len());
+        for rule in &rules {
             match rule.is_valid() {
-                Ok(_) => {valid_rules.insert(Arc::clone(rule));},
+                Ok(_) => {
+                    valid_res_rules.insert(Arc::clone(rule));
+                }
                 Err(err) => logging::warn!(
-                    "[HotSpot onRuleUpdate] Ignoring invalid hotspot param flow rule {:?}, reason: {:?}",
+                    "[HotSpot load_rules_of_resource] Ignoring invalid flow rule {:?}, reason: {:?}",
                     rule,
                     err
                 ),
             }
         }
-        if !valid_rules.is_empty() {
-            valid_rules_map.insert(res.clone(), valid_rules);
+        // the `res` related rules changes, have to update
+        let start = utils::curr_time_nanos();
+        let mut placeholder = Vec::new();
+        let old_res_tcs = global_controller_map
+            .get_mut(res)
+            .unwrap_or(&mut placeholder);
+
+        let valid_res_rules_string = format!("{:?}", &valid_res_rules);
+        let new_res_tcs =
+            build_resource_traffic_shaping_controller(res, &valid_res_rules, old_res_tcs);
+
+        if new_res_tcs.is_empty() {
+            global_controller_map.remove(res);
+        } else {
+            global_controller_map.insert(res.clone(), new_res_tcs);
         }
-    }
 
-    let start = utils::curr_time_nanos();
-    let mut controller_map = CONTROLLER_MAP.write().unwrap();
-    let mut valid_controller_map = HashMap::with_capacity(valid_rules_map.len());
-
-    // build controller_map according to valid rules
-    for (res, rules) in valid_rules_map.iter() {
-        let mut placeholder = Vec::new();
-        let new_tcs_of_res = build_resource_traffic_shaping_controller(
+        global_rule_map.insert(res.clone(), rules);
+        logging::debug!(
+            "[HotSpot load_rules_of_resource] Time statistic(ns) for updating hotspot param flow rule, timeCost: {}",
+            utils::curr_time_nanos() - start
+        );
+        logging::info!(
+            "[HotSpot] load resource level hotspot param rules, resource: {}, valid_res_rules: {}",
             res,
-            rules,
-            controller_map.get_mut(res).unwrap_or(&mut placeholder),
+            valid_res_rules_string
         );
-        if !new_tcs_of_res.is_empty() {
-            valid_controller_map.insert(res.clone(), new_tcs_of_res);
-        }
-    }
-    *controller_map = valid_controller_map;
-    *global_rule_map = rule_map;
-    drop(global_rule_map);
-    drop(controller_map);
-    logging::debug!(
-        "[HotSpot load_rules] Time statistic(ns) for updating hotspot param flow rule, time cost {}",
-        utils::curr_time_nanos() - start
-    );
-
-    log_rule_update(&valid_rules_map);
-    true
-}
 
-/// `load_rules_of_resource` loads the given resource's flow rules to the rule manager, while all previous resource's rules will be replaced.
-/// The first returned value indicates whether do real load operation, if the rules is the same with previous resource's rules, return false
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
-pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
-    if res.is_empty() {
-        return Err(Error::msg("empty resource"));
-    }
-    let rules: HashSet<_> = rules.into_iter().collect();
-    let mut global_rule_map = RULE_MAP.lock().unwrap();
-    let mut global_controller_map = CONTROLLER_MAP.write().unwrap();
-    // clear resource rules
-    if rules.is_empty() {
-        global_rule_map.remove(res);
-        global_controller_map.remove(res);
-        logging::info!("[HotSpot] clear resource level rules, resource {}", res);
-        return Ok(true);
-    }
-    // load resource level rules
-    if global_rule_map.get(res).unwrap_or(&HashSet::new()) == &rules {
-        logging::info!("[HotSpot] Load resource level rules is the same with current resource level rules, so ignore load operation.");
-        return Ok(false);
+        Ok(true)
     }
 
-    let mut valid_res_rules = HashSet::with_capacity(res.len());
-    for rule in &rules {
-        match rule.is_valid() {
-            Ok(_) => {
-                valid_res_rules.insert(Arc::clone(rule));
+    /// `get_rules` returns all the rules in `controller_map`
+    // This func acquires the locks on `controller_map`,
+    // please release your lock on it before calling this func
+    pub fn get_rules(&self) -> Vec<Arc<Rule>> {
+        let mut rules = Vec::new();
+        let controller_map = self.controller_map.read().unwrap();
+        for (_, controllers) in controller_map.iter() {
+            for c in controllers {
+                rules.push(Arc::clone(c.rule()));
             }
-            Err(err) => logging::warn!(
-                "[HotSpot load_rules_of_resource] Ignoring invalid flow rule {:?}, reason: {:?}",
-                rule,
-                err
-            ),
         }
-    }
-    // the `res` related rules changes, have to update
-    let start = utils::curr_time_nanos();
-    let mut placeholder = Vec::new();
-    let old_res_tcs = global_controller_map
-        .get_mut(res)
-        .unwrap_or(&mut placeholder);
-
-    let valid_res_rules_string = format!("{:?}", &valid_res_rules);
-    let new_res_tcs = build_resource_traffic_shaping_controller(res, &valid_res_rules, old_res_tcs);
-
-    if new_res_tcs.is_empty() {
-        global_controller_map.remove(res);
-    } else {
-        global_controller_map.insert(res.clone(), new_res_tcs);
+        rules
     }
 
-    global_rule_map.insert(res.clone(), rules);
-    logging::debug!(
-        "[HotSpot load_rules_of_resource] Time statistic(ns) for updating hotspot param flow rule, timeCost: {}",
-        utils::curr_time_nanos() - start
-    );
-    logging::info!(
-        "[HotSpot] load resource level hotspot param rules, resource: {}, valid_res_rules: {}",
-        res,
-        valid_res_rules_string
-    );
-
-    Ok(true)
-}
-
-/// `get_rules` returns all the rules in `CONTROLLER_MAP`
-// This func acquires the locks on global `CONTROLLER_MAP`,
-// please release your lock on it before calling this func
-pub fn get_rules() -> Vec<Arc<Rule>> {
-    let mut rules = Vec::new();
-    let controller_map = CONTROLLER_MAP.read().unwrap();
-    for (_, controllers) in controller_map.iter() {
+    /// `get_rules_of_resource` returns specific resource's rules
+    // This func acquires the lock on `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn get_rules_of_resource(&self, res: &String) -> Vec<Arc<Rule>> {
+        let controller_map = self.controller_map.read().unwrap();
+        let placeholder = Vec::new();
+        let controllers = controller_map.get(res).unwrap_or(&placeholder);
+        let mut rules = Vec::with_capacity(controllers.len());
         for c in controllers {
             rules.push(Arc::clone(c.rule()));
         }
+        rules
+    }
+
+    /// clear_rules clears all the rules in hotspot param flow module.
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn clear_rules(&self) {
+        self.rule_map.lock().unwrap().clear();
+        self.controller_map.write().unwrap().clear();
+    }
+
+    /// `clear_rules_of_resource` clears resource level rules in hotspot param flow module.
+    // This func acquires locks on `rule_map` and `controller_map`,
+    // please release your locks on them before calling this func
+    pub fn clear_rules_of_resource(&self, res: &String) {
+        self.rule_map.lock().unwrap().remove(res);
+        self.controller_map.write().unwrap().remove(res);
     }
-    rules
 }
 
-/// `get_rules_of_resource` returns specific resource's rules
-// This func acquires the lock on global `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
+// The following functions operate on the default rule manager.
+pub fn get_traffic_controller_list_for(res: &String) -> Vec<Arc<Controller>> {
+    DEFAULT_RULE_MANAGER.get_traffic_controller_list_for(res)
+}
+
+pub fn get_active_traffic_controller_list_for(res: &String) -> Vec<Arc<Controller>> {
+    DEFAULT_RULE_MANAGER.get_active_traffic_controller_list_for(res)
+}
+
+pub fn append_rule(rule: Arc<Rule>) -> bool {
+    DEFAULT_RULE_MANAGER.append_rule(rule)
+}
+
+pub fn load_rules(rules: Vec<Arc<Rule>>) -> bool {
+    DEFAULT_RULE_MANAGER.load_rules(rules)
+}
+
+pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+    DEFAULT_RULE_MANAGER.load_rules_of_resource(res, rules)
+}
+
+pub fn get_rules() -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules()
+}
+
 pub fn get_rules_of_resource(res: &String) -> Vec<Arc<Rule>> {
-    let controller_map = CONTROLLER_MAP.read().unwrap();
-    let placeholder = Vec::new();
-    let controllers = controller_map.get(res).unwrap_or(&placeholder);
-    let mut rules = Vec::with_capacity(controllers.len());
-    for c in controllers {
-        rules.push(Arc::clone(c.rule()));
-    }
-    rules
+    DEFAULT_RULE_MANAGER.get_rules_of_resource(res)
 }
 
-/// clear_rules clears all the rules in hotspot param flow module.
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
 pub fn clear_rules() {
-    RULE_MAP.lock().unwrap().clear();
-    CONTROLLER_MAP.write().unwrap().clear();
+    DEFAULT_RULE_MANAGER.clear_rules()
 }
 
-/// `clear_rules_of_resource` clears resource level rules in hotspot param flow module.
-// This func acquires locks on global `RULE_MAP` and `CONTROLLER_MAP`,
-// please release your locks on them before calling this func
 pub fn clear_rules_of_resource(res: &String) {
-    RULE_MAP.lock().unwrap().remove(res);
-    CONTROLLER_MAP.write().unwrap().remove(res);
+    DEFAULT_RULE_MANAGER.clear_rules_of_resource(res)
+}
+
+fn log_rule_update(map: &RuleMap) {
+    if map.is_empty() {
+        logging::info!("[HotspotRuleManager] Hotspot param flow rules were cleared")
+    } else {
+        logging::info!(
+            "[HotspotRuleManager] Hotspot param flow rules were loaded: {:?}",
+            map.values()
+        )
+    }
 }
 
 /// `set_traffic_shaping_generator` sets the traffic controller generator for the given CalculateStrategy and ControlStrategy.
@@ -433,11 +522,12 @@ pub fn build_resource_traffic_shaping_controller(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::base::{ParamKey, Schedule, TimeWindow};
 
     #[test]
     fn gen_without_metric() {
         let mut specific_items = HashMap::new();
-        specific_items.insert(100.to_string(), 100);
+        specific_items.insert(ParamKey::from(100), 100);
         let rule = Arc::new(Rule {
             resource: "abc".into(),
             metric_type: MetricType::Concurrency,
@@ -459,7 +549,7 @@ mod test {
     #[test]
     fn gen_with_metric() {
         let mut specific_items = HashMap::new();
-        specific_items.insert(100.to_string(), 100);
+        specific_items.insert(ParamKey::from(100), 100);
         let rule = Arc::new(Rule {
             resource: "abc".into(),
             metric_type: MetricType::QPS,
@@ -490,13 +580,56 @@ mod test {
         assert_eq!(0, tc.param_index());
     }
 
+    #[test]
+    fn scheduled_rules() {
+        let rule_manager = RuleManager::new();
+        let res_name = String::from("scheduled_rules");
+        rule_manager.load_rules(vec![
+            Arc::new(Rule {
+                resource: res_name.clone(),
+                threshold: 10,
+                duration_in_sec: 1,
+                // active all day long
+                schedule: Some(Schedule {
+                    windows: vec![TimeWindow {
+                        start: "00:00".into(),
+                        end: "00:00".into(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            Arc::new(Rule {
+                resource: res_name.clone(),
+                threshold: 20,
+                duration_in_sec: 1,
+                // never active, since there is no February 31st
+                schedule: Some(Schedule {
+                    cron: "0 0 31 2 *".into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        ]);
+        assert_eq!(
+            rule_manager
+                .get_traffic_controller_list_for(&res_name)
+                .len(),
+            2
+        );
+        let active = rule_manager.get_active_traffic_controller_list_for(&res_name);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].rule().threshold, 10);
+    }
+
     #[test]
     #[ignore]
     fn test_load_rules() {
         clear_rules();
         let mut specific_items = HashMap::new();
-        specific_items.insert(String::from("sss"), 1);
-        specific_items.insert(String::from("123"), 3);
+        specific_items.insert(ParamKey::from("sss"), 1);
+        specific_items.insert(ParamKey::from("123"), 3);
         let rule = Arc::new(Rule {
             id: "1".into(),
             resource: "abc".into(),
@@ -515,8 +648,8 @@ mod test {
         let success = load_rules(vec![rule]);
         assert!(!success);
 
-        let controller_map = CONTROLLER_MAP.read().unwrap();
-        let rule_map = RULE_MAP.lock().unwrap();
+        let controller_map = DEFAULT_RULE_MANAGER.controller_map.read().unwrap();
+        let rule_map = DEFAULT_RULE_MANAGER.rule_map.lock().unwrap();
 
         assert_eq!(1, rule_map["abc"].len());
         assert_eq!(1, controller_map["abc"].len());
@@ -530,8 +663,8 @@ mod test {
     fn test_load_rules_of_resource() {
         clear_rules();
         let mut specific_items = HashMap::new();
-        specific_items.insert(String::from("sss"), 1);
-        specific_items.insert(String::from("123"), 3);
+        specific_items.insert(ParamKey::from("sss"), 1);
+        specific_items.insert(ParamKey::from("123"), 3);
         let r11 = Arc::new(Rule {
             id: "1".into(),
             resource: "abc1".into(),
@@ -595,8 +728,8 @@ mod test {
         let success = load_rules_of_resource(&"abc1".into(), vec![]);
         assert!(success.unwrap());
 
-        let controller_map = CONTROLLER_MAP.read().unwrap();
-        let rule_map = RULE_MAP.lock().unwrap();
+        let controller_map = DEFAULT_RULE_MANAGER.controller_map.read().unwrap();
+        let rule_map = DEFAULT_RULE_MANAGER.rule_map.lock().unwrap();
 
         assert_eq!(0, rule_map.get("abc1").unwrap_or(&HashSet::new()).len());
         assert_eq!(0, controller_map.get("abc1").unwrap_or(&Vec::new()).len());
@@ -612,8 +745,8 @@ mod test {
     fn test_clear_rules() {
         clear_rules();
         let mut specific_items = HashMap::new();
-        specific_items.insert(String::from("sss"), 1);
-        specific_items.insert(String::from("123"), 3);
+        specific_items.insert(ParamKey::from("sss"), 1);
+        specific_items.insert(ParamKey::from("123"), 3);
         let r11 = Arc::new(Rule {
             id: "1".into(),
             resource: "abc1".into(),
@@ -666,7 +799,8 @@ mod test {
 
         assert_eq!(
             0,
-            RULE_MAP
+            DEFAULT_RULE_MANAGER
+                .rule_map
                 .lock()
                 .unwrap()
                 .get("abc1")
@@ -675,7 +809,8 @@ mod test {
         );
         assert_eq!(
             0,
-            CONTROLLER_MAP
+            DEFAULT_RULE_MANAGER
+                .controller_map
                 .read()
                 .unwrap()
                 .get("abc1")
@@ -684,7 +819,8 @@ mod test {
         );
         assert_eq!(
             2,
-            RULE_MAP
+            DEFAULT_RULE_MANAGER
+                .rule_map
                 .lock()
                 .unwrap()
                 .get("abc2")
@@ -693,7 +829,8 @@ mod test {
         );
         assert_eq!(
             2,
-            CONTROLLER_MAP
+            DEFAULT_RULE_MANAGER
+                .controller_map
                 .read()
                 .unwrap()
                 .get("abc2")
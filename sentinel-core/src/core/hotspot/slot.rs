@@ -1,18 +1,32 @@
 use super::*;
-use crate::{
-    base::{BaseSlot, EntryContext, RuleCheckSlot, TokenResult},
-    utils,
+use crate::base::{
+    BaseSlot, BlockType, EntryContext, ParamKey, RuleCheckSlot, RuleMode, TokenResult,
 };
+use crate::{cluster::TokenStatus, logging};
 use lazy_static::lazy_static;
 use std::sync::Arc;
 
 const RULE_CHECK_SLOT_ORDER: u32 = 4000;
 
 /// A RuleSlot for flow related metrics
-pub struct Slot {}
+pub struct Slot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl Slot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        Slot { rule_manager }
+    }
+}
+
+impl Default for Slot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
 
 lazy_static! {
-    pub static ref DEFAULT_SLOT: Arc<Slot> = Arc::new(Slot {});
+    pub static ref DEFAULT_SLOT: Arc<Slot> = Arc::new(Slot::default());
 }
 
 pub fn default_slot() -> Arc<Slot> {
@@ -29,19 +43,30 @@ impl RuleCheckSlot for Slot {
     fn check(&self, ctx: &mut EntryContext) -> TokenResult {
         let res = ctx.resource().name();
         let batch = ctx.input().batch_count();
-        let tcs = get_traffic_controller_list_for(res);
+        let tcs = self
+            .rule_manager
+            .get_active_traffic_controller_list_for(res);
         for tc in tcs {
             let extracted = tc.extract_args(ctx);
             if let Some(arg) = extracted {
-                let r = tc.perform_checking(arg, batch);
+                let r = match cluster_check(&self.rule_manager, &tc, &arg, batch) {
+                    Some(r) => r,
+                    None => tc.perform_checking(arg, batch),
+                };
+                let shadow = tc.rule().mode == RuleMode::Shadow;
                 match r {
                     TokenResult::Pass => {}
+                    TokenResult::Blocked(block_err) if shadow => {
+                        ctx.add_shadow_block(block_err);
+                    }
                     TokenResult::Blocked(_) => {
                         ctx.set_result(r);
                         return ctx.result().clone();
                     }
+                    // rules in shadow mode never delay the invocation
+                    TokenResult::Wait(_) if shadow => {}
                     TokenResult::Wait(nanos_to_wait) => {
-                        utils::sleep_for_ns(nanos_to_wait);
+                        ctx.wait_for_ns(nanos_to_wait);
                     }
                 }
             }
@@ -49,3 +74,47 @@ impl RuleCheckSlot for Slot {
         return ctx.result().clone();
     }
 }
+
+/// `cluster_check` requests the tokens of the parameter from the token server for the cluster mode rule,
+/// it returns `None` to fall back to the local checking, if the rule is not in cluster mode
+/// or the token server is not available.
+/// It blocks the calling thread until the response arrives or the request times out, also in `SlotChain::entry_async()`.
+fn cluster_check(
+    rule_manager: &RuleManager,
+    tc: &Arc<Controller>,
+    arg: &ParamKey,
+    batch_count: u32,
+) -> Option<TokenResult> {
+    let rule = tc.rule();
+    if !rule.cluster_mode {
+        return None;
+    }
+    let client = rule_manager.token_client()?;
+    match client.request_param_token(rule.cluster_config.flow_id, batch_count, arg.clone()) {
+        Ok(response) => match response.status {
+            TokenStatus::Ok => Some(TokenResult::new_pass()),
+            TokenStatus::Blocked => Some(TokenResult::new_blocked_with_cause(
+                BlockType::HotSpotParamFlow,
+                format!("hotspot cluster QPS check blocked, arg: {:?}", arg),
+                rule.clone(),
+                Arc::new(response.remaining),
+            )),
+            status => {
+                logging::debug!(
+                    "[HotspotSlot] Unexpected token status {:?} of cluster hotspot rule {:?}, fall back to local checking",
+                    status,
+                    rule
+                );
+                None
+            }
+        },
+        Err(err) => {
+            logging::debug!(
+                "[HotspotSlot] Failed to request tokens of cluster hotspot rule {:?}, fall back to local checking, reason: {:?}",
+                rule,
+                err
+            );
+            None
+        }
+    }
+}
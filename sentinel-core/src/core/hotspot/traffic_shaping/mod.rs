@@ -6,7 +6,7 @@ pub use throttling::*;
 
 use super::*;
 use crate::{
-    base::{BlockType, EntryContext, ParamKey, TokenResult},
+    base::{BlockType, CompiledSchedule, EntryContext, ParamKey, TokenResult},
     logging,
 };
 use std::cmp::min;
@@ -29,6 +29,8 @@ where
     rule: Arc<Rule>,
     metric: Arc<ParamsMetric<C>>,
     checker: Option<Arc<Mutex<dyn Checker<C>>>>,
+    /// the schedule of the rule, compiled when the controller is generated
+    schedule: Option<CompiledSchedule>,
 }
 
 impl<C> Controller<C>
@@ -77,10 +79,13 @@ where
 
     /// Please refer to the generators in the `rule_manager`
     pub fn new_with_metric(rule: Arc<Rule>, metric: Arc<ParamsMetric<C>>) -> Controller<C> {
+        // the schedule has been validated along with the rule
+        let schedule = rule.schedule.as_ref().and_then(|s| s.compile().ok());
         Controller {
             rule,
             metric,
             checker: None,
+            schedule,
         }
     }
 
@@ -100,6 +105,14 @@ where
         &self.rule
     }
 
+    /// `is_active` indicates whether the rule is active now according to its schedule
+    pub fn is_active(&self) -> bool {
+        match &self.schedule {
+            Some(schedule) => schedule.is_active(),
+            None => true,
+        }
+    }
+
     pub fn param_index(&self) -> isize {
         self.rule.param_index
     }
@@ -146,7 +159,9 @@ where
         }
     }
 
-    /// ExtractArgs matches the arg from ctx based on Controller
+    /// ExtractArgs matches the arg from ctx based on Controller,
+    /// the attachment of `param_key` takes precedence over the arg of `param_index`.
+    /// The arg is matched with `specific_items` by its value, see `ParamKey`.
     pub fn extract_args(&self, ctx: &EntryContext) -> Option<ParamKey> {
         if let Some(args) = self.extract_kv_args(ctx) {
             Some(args)
@@ -191,11 +206,12 @@ where
                         self.rule.param_key
                     );
                     None
-                } else if !attachments.contains_key(key) {
-                    logging::debug!("[extract_args] The extracted data does not exist, key: {:?}, attachments: {:?}", self.rule.param_key, attachments);
-                    None
                 } else {
-                    Some(attachments[key].clone())
+                    let arg = attachments.get(key).cloned();
+                    if arg.is_none() {
+                        logging::debug!("[extract_args] The extracted data does not exist, key: {:?}, attachments: {:?}", self.rule.param_key, attachments);
+                    }
+                    arg
                 }
             }
             None => {
@@ -230,7 +246,7 @@ pub(crate) mod test {
             controller
                 .metric()
                 .concurrency_counter
-                .add_if_absent(i.to_string(), 0);
+                .add_if_absent(ParamKey::from(i), 0);
         }
         assert_eq!(
             CONCURRENCY_MAX_COUNT,
@@ -250,7 +266,7 @@ pub(crate) mod test {
             controller
                 .metric()
                 .concurrency_counter
-                .add_if_absent(i.to_string(), 0);
+                .add_if_absent(ParamKey::from(i), 0);
         }
         assert_eq!(100, controller.metric().concurrency_counter.len());
     }
@@ -269,11 +285,11 @@ pub(crate) mod test {
             controller
                 .metric()
                 .rule_token_counter
-                .add_if_absent(i.to_string(), 0);
+                .add_if_absent(ParamKey::from(i), 0);
             controller
                 .metric()
                 .rule_time_counter
-                .add_if_absent(i.to_string(), 0);
+                .add_if_absent(ParamKey::from(i), 0);
         }
         assert_eq!(
             PARAMS_MAX_CAPACITY,
@@ -310,6 +326,24 @@ pub(crate) mod test {
         assert!(extracted.is_none());
     }
 
+    #[test]
+    fn extract_typed_args() {
+        let rule: Rule = serde_json::from_str(
+            r#"{"resource": "abc", "param_index": 1, "specific_items": {"100": 5, "true": 3}}"#,
+        )
+        .unwrap();
+        let controller = gen_reject::<Counter>(Arc::new(rule), None);
+
+        let mut ctx = EntryContext::new();
+        let mut input = SentinelInput::new(1, 0);
+        input.set_args(vec!["abc".into(), 100u32.into()]);
+        ctx.set_input(input);
+        let extracted = controller.extract_args(&ctx).unwrap();
+        assert!(matches!(extracted, ParamKey::UInt(100)));
+        assert_eq!(controller.rule().specific_items.get(&extracted), Some(&5));
+        assert_eq!(controller.rule().specific_items.get(&true.into()), Some(&3));
+    }
+
     #[test]
     fn extract_args_exist() {
         let rule = Arc::new(Rule {
@@ -334,7 +368,7 @@ pub(crate) mod test {
         ctx.set_input(input);
 
         let extracted = controller.extract_args(&ctx);
-        assert_eq!("v1", &extracted.unwrap());
+        assert_eq!(ParamKey::from("v1"), extracted.unwrap());
     }
 
     #[test]
@@ -361,7 +395,7 @@ pub(crate) mod test {
         ctx.set_input(input);
 
         let extracted = controller.extract_args(&ctx);
-        assert_eq!("v1", &extracted.unwrap());
+        assert_eq!(ParamKey::from("v1"), extracted.unwrap());
     }
 
     #[test]
@@ -388,7 +422,7 @@ pub(crate) mod test {
         ctx.set_input(input);
 
         let extracted = controller.extract_args(&ctx);
-        assert_eq!("2", &extracted.unwrap());
+        assert_eq!(ParamKey::from("2"), extracted.unwrap());
     }
 
     #[test]
@@ -446,18 +480,20 @@ pub(crate) mod test {
                 });
 
                 let controller = gen_reject(rule, Some(metric));
-                let token = controller.perform_checking_for_concurrency_metric(666688.to_string());
+                let token =
+                    controller.perform_checking_for_concurrency_metric(ParamKey::from(666688));
                 assert!(token.is_pass());
 
                 concurrency.store(101, Ordering::SeqCst);
-                let token = controller.perform_checking_for_concurrency_metric(666688.to_string());
+                let token =
+                    controller.perform_checking_for_concurrency_metric(ParamKey::from(666688));
                 assert!(token.is_blocked());
             }
 
             #[test]
             fn args() {
                 let mut specific_items = HashMap::new();
-                specific_items.insert(666688.to_string(), 20);
+                specific_items.insert(ParamKey::from(666688), 20);
                 let rule = Arc::new(Rule {
                     resource: "abc".into(),
                     metric_type: MetricType::Concurrency,
@@ -480,11 +516,13 @@ pub(crate) mod test {
                 });
 
                 let controller = gen_reject(rule, Some(metric));
-                let token = controller.perform_checking_for_concurrency_metric(666688.to_string());
+                let token =
+                    controller.perform_checking_for_concurrency_metric(ParamKey::from(666688));
                 assert!(token.is_blocked());
 
                 concurrency.store(10, Ordering::SeqCst);
-                let token = controller.perform_checking_for_concurrency_metric(666688.to_string());
+                let token =
+                    controller.perform_checking_for_concurrency_metric(ParamKey::from(666688));
                 assert!(token.is_pass());
             }
         }
@@ -529,10 +567,10 @@ pub(crate) mod test {
                 });
 
                 let controller = gen_reject(rule, Some(metric));
-                let token = controller.perform_checking(10110.to_string(), 130);
+                let token = controller.perform_checking(ParamKey::from(10110), 130);
                 assert!(token.is_blocked());
 
-                let token = controller.perform_checking(10110.to_string(), 20);
+                let token = controller.perform_checking(ParamKey::from(10110), 20);
                 assert!(token.is_pass());
             }
 
@@ -575,7 +613,7 @@ pub(crate) mod test {
                 });
 
                 let controller = gen_reject(rule, Some(metric));
-                let token = controller.perform_checking(10110.to_string(), 20);
+                let token = controller.perform_checking(ParamKey::from(10110), 20);
                 assert!(token.is_pass());
                 assert_eq!(30, old_qps.load(Ordering::SeqCst));
             }
@@ -619,7 +657,7 @@ pub(crate) mod test {
 
                 let controller = gen_reject(rule, Some(metric));
                 utils::sleep_for_ms(10);
-                let token = controller.perform_checking(10110.to_string(), 20);
+                let token = controller.perform_checking(ParamKey::from(10110), 20);
                 assert!(token.is_pass());
                 assert!(last_add_token_time.load(Ordering::SeqCst) > curr_time);
             }
@@ -664,7 +702,7 @@ pub(crate) mod test {
 
                 let controller = gen_reject(rule, Some(metric));
                 utils::sleep_for_ms(10);
-                let token = controller.perform_checking(10110.to_string(), 20);
+                let token = controller.perform_checking(ParamKey::from(10110), 20);
                 assert!(token.is_pass());
                 assert!(last_add_token_time.load(Ordering::SeqCst) > curr_time);
                 assert!(old_qps.load(Ordering::SeqCst) > 30);
@@ -703,7 +741,7 @@ pub(crate) mod test {
                 ..Default::default()
             });
             let controller = gen_throttling(rule, Some(metric));
-            let token = controller.perform_checking(10110.to_string(), 20);
+            let token = controller.perform_checking(ParamKey::from(10110), 20);
             assert!(token.is_pass());
         }
     }
@@ -0,0 +1,263 @@
+//! Adaptive concurrency limiters
+//!
+//! The limiters continuously adjust the concurrency limit of a resource
+//! from the round trip time and the in-flight requests observed on completion,
+//! which is similar to the TCP congestion control.
+
+use super::{LimitStrategy, Rule};
+use std::fmt;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex,
+};
+
+/// `Sample` is observed when an invocation completes
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    /// the round trip time in milliseconds
+    pub rt: u64,
+    /// the number of in-flight invocations
+    pub in_flight: u32,
+    /// whether the invocation timed out or was blocked downstream, which is regarded as a signal of overload
+    pub dropped: bool,
+}
+
+/// `LimitAlgorithm` calculates the new concurrency limit from a sample.
+pub trait LimitAlgorithm: Send + Sync + fmt::Debug {
+    fn update(&mut self, limit: f64, sample: &Sample) -> f64;
+}
+
+/// `Aimd` increases the limit additively when the limit is reached, and decreases it multiplicatively on failures.
+#[derive(Debug)]
+pub struct Aimd {
+    backoff_ratio: f64,
+}
+
+impl Default for Aimd {
+    fn default() -> Self {
+        Aimd { backoff_ratio: 0.9 }
+    }
+}
+
+impl LimitAlgorithm for Aimd {
+    fn update(&mut self, limit: f64, sample: &Sample) -> f64 {
+        if sample.dropped {
+            limit * self.backoff_ratio
+        } else if sample.in_flight as f64 * 2.0 >= limit {
+            limit + 1.0
+        } else {
+            limit
+        }
+    }
+}
+
+/// `Vegas` estimates the queue size from the difference between the observed RT and the no-load RT,
+/// and keeps the queue size between `alpha` and `beta`, which grow logarithmically with the limit.
+#[derive(Debug, Default)]
+pub struct Vegas {
+    rt_no_load: u64,
+}
+
+fn log10_root(limit: f64) -> f64 {
+    limit.log10().floor().max(1.0)
+}
+
+impl LimitAlgorithm for Vegas {
+    fn update(&mut self, limit: f64, sample: &Sample) -> f64 {
+        let rt = sample.rt.max(1);
+        if self.rt_no_load == 0 || rt < self.rt_no_load {
+            self.rt_no_load = rt;
+        }
+        if sample.dropped {
+            return limit - log10_root(limit);
+        }
+        // the limit is not reached, nothing to learn from the sample
+        if (sample.in_flight as f64) * 2.0 < limit {
+            return limit;
+        }
+        let queue_size = (limit * (1.0 - self.rt_no_load as f64 / rt as f64)).ceil();
+        let threshold = log10_root(limit);
+        let alpha = 3.0 * threshold;
+        let beta = 6.0 * threshold;
+        if queue_size <= threshold {
+            limit + beta
+        } else if queue_size < alpha {
+            limit + threshold
+        } else if queue_size > beta {
+            limit - threshold
+        } else {
+            limit
+        }
+    }
+}
+
+/// `Gradient2` compares the short-term RT with the long-term (exponential average) RT,
+/// and shrinks the limit by the gradient when the RT grows, with a headroom of `sqrt(limit)` for queueing.
+#[derive(Debug)]
+pub struct Gradient2 {
+    long_rt: f64,
+    count: u32,
+    tolerance: f64,
+    smoothing: f64,
+}
+
+impl Default for Gradient2 {
+    fn default() -> Self {
+        Gradient2 {
+            long_rt: 0.0,
+            count: 0,
+            tolerance: 1.5,
+            smoothing: 0.2,
+        }
+    }
+}
+
+const GRADIENT_WARM_UP_SAMPLES: u32 = 10;
+const GRADIENT_LONG_WINDOW: f64 = 600.0;
+
+impl LimitAlgorithm for Gradient2 {
+    fn update(&mut self, limit: f64, sample: &Sample) -> f64 {
+        let short_rt = sample.rt.max(1) as f64;
+        // simple average during warm-up, then exponential average
+        self.count = self.count.saturating_add(1);
+        if self.count <= GRADIENT_WARM_UP_SAMPLES {
+            self.long_rt += (short_rt - self.long_rt) / self.count as f64;
+        } else {
+            self.long_rt += (short_rt - self.long_rt) * 2.0 / (GRADIENT_LONG_WINDOW + 1.0);
+        }
+        // recover faster when the long-term RT has drifted too far from the short-term RT
+        if self.long_rt / short_rt > 2.0 {
+            self.long_rt *= 0.95;
+        }
+        // the limit is not reached, nothing to learn from the sample
+        if (sample.in_flight as f64) < limit / 2.0 {
+            return limit;
+        }
+        let gradient = (self.tolerance * self.long_rt / short_rt).clamp(0.5, 1.0);
+        let new_limit = limit * gradient + limit.sqrt();
+        limit * (1.0 - self.smoothing) + new_limit * self.smoothing
+    }
+}
+
+/// `AdaptiveLimiter` holds the adjusted concurrency limit of an adaptive isolation rule.
+#[derive(Debug)]
+pub struct AdaptiveLimiter {
+    rule: Arc<Rule>,
+    limit: AtomicU32,
+    state: Mutex<(f64, Box<dyn LimitAlgorithm>)>,
+}
+
+impl AdaptiveLimiter {
+    /// `new` returns `None` if the rule has a fixed threshold
+    pub fn new(rule: Arc<Rule>) -> Option<Self> {
+        let algorithm: Box<dyn LimitAlgorithm> = match rule.strategy {
+            LimitStrategy::Fixed => return None,
+            LimitStrategy::AIMD => Box::<Aimd>::default(),
+            LimitStrategy::Vegas => Box::<Vegas>::default(),
+            LimitStrategy::Gradient2 => Box::<Gradient2>::default(),
+        };
+        let threshold = rule.threshold;
+        Some(AdaptiveLimiter {
+            rule,
+            limit: AtomicU32::new(threshold),
+            state: Mutex::new((threshold as f64, algorithm)),
+        })
+    }
+
+    pub fn rule(&self) -> &Arc<Rule> {
+        &self.rule
+    }
+
+    /// `limit` returns the current concurrency limit
+    pub fn limit(&self) -> u32 {
+        self.limit.load(Ordering::SeqCst)
+    }
+
+    /// `on_sample` adjusts the limit by the sample, and returns the new limit
+    pub fn on_sample(&self, sample: &Sample) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        let (min, max) = (
+            self.rule.min_threshold as f64,
+            self.rule.max_threshold as f64,
+        );
+        let (limit, algorithm) = &mut *state;
+        let new_limit = algorithm.update(*limit, sample).clamp(min, max);
+        *limit = new_limit;
+        let limit = new_limit as u32;
+        self.limit.store(limit, Ordering::SeqCst);
+        limit
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(rt: u64, in_flight: u32, dropped: bool) -> Sample {
+        Sample {
+            rt,
+            in_flight,
+            dropped,
+        }
+    }
+
+    #[test]
+    fn aimd() {
+        let mut aimd = Aimd::default();
+        assert!((aimd.update(10.0, &sample(10, 5, false)) - 11.0).abs() < f64::EPSILON);
+        assert!((aimd.update(10.0, &sample(10, 2, false)) - 10.0).abs() < f64::EPSILON);
+        assert!((aimd.update(10.0, &sample(10, 5, true)) - 9.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn vegas() {
+        let mut vegas = Vegas::default();
+        // no queueing
+        assert!(vegas.update(20.0, &sample(10, 20, false)) > 20.0);
+        // the RT doubles, so that half of the in-flight requests are queueing
+        assert!(vegas.update(20.0, &sample(20, 20, false)) < 20.0);
+        assert!(vegas.update(20.0, &sample(10, 20, true)) < 20.0);
+    }
+
+    #[test]
+    fn gradient2() {
+        let mut gradient = Gradient2::default();
+        let mut limit = 20.0;
+        for _ in 0..GRADIENT_WARM_UP_SAMPLES {
+            limit = gradient.update(limit, &sample(10, 20, false));
+        }
+        assert!(limit > 20.0);
+        let warm_limit = limit;
+        for _ in 0..10 {
+            limit = gradient.update(limit, &sample(100, limit as u32, false));
+        }
+        assert!(limit < warm_limit);
+    }
+
+    #[test]
+    fn limiter() {
+        let fixed = Arc::new(Rule {
+            resource: "abc".into(),
+            threshold: 10,
+            ..Default::default()
+        });
+        assert!(AdaptiveLimiter::new(fixed).is_none());
+
+        let rule = Arc::new(Rule {
+            resource: "abc".into(),
+            threshold: 10,
+            strategy: LimitStrategy::AIMD,
+            min_threshold: 9,
+            max_threshold: 11,
+            ..Default::default()
+        });
+        let limiter = AdaptiveLimiter::new(rule).unwrap();
+        assert_eq!(limiter.limit(), 10);
+        assert_eq!(limiter.on_sample(&sample(10, 10, false)), 11);
+        assert_eq!(limiter.on_sample(&sample(10, 10, false)), 11);
+        for _ in 0..10 {
+            limiter.on_sample(&sample(10, 10, true));
+        }
+        assert_eq!(limiter.limit(), 9);
+    }
+}
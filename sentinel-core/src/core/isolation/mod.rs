@@ -1,9 +1,14 @@
-//! mod isolation provides implementation of concurrency limiting (semaphore isolation).
+//! mod isolation provides implementation of concurrency limiting (semaphore isolation),
+//! with a fixed or an adaptive concurrency limit.
 
+pub mod limiter;
 pub mod rule;
 pub mod rule_manager;
 pub mod slot;
+pub mod stat_slot;
 
+pub use limiter::*;
 pub use rule::*;
 pub use rule_manager::*;
 pub use slot::*;
+pub use stat_slot::*;
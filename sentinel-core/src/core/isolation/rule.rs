@@ -1,4 +1,7 @@
-use crate::{base::SentinelRule, Error};
+use crate::{
+    base::{MatchStrategy, PatternRule, Schedule, SentinelRule},
+    Error,
+};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fmt;
@@ -21,6 +24,21 @@ impl Default for MetricType {
     }
 }
 
+/// `LimitStrategy` indicates how the concurrency limit is determined.
+#[cfg_attr(feature = "ds_k8s", derive(JsonSchema))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize, Hash, Eq)]
+pub enum LimitStrategy {
+    /// `Fixed` uses `threshold` as the limit
+    #[default]
+    Fixed,
+    /// `AIMD` increases the limit additively and decreases it multiplicatively on errors
+    AIMD,
+    /// `Vegas` adjusts the limit by the queue size estimated from the RT and the no-load RT
+    Vegas,
+    /// `Gradient2` adjusts the limit by the gradient of the short-term RT to the long-term RT
+    Gradient2,
+}
+
 /// `Rule` describes the policy for system resiliency.
 #[cfg_attr(
     feature = "ds_k8s",
@@ -39,9 +57,21 @@ pub struct Rule {
     pub id: String,
     /// `resource` represents the target resource definition
     pub resource: String,
+    /// `match_strategy` indicates how `resource` matches the resource names,
+    /// a glob or regex pattern makes the rule cover a family of resources.
+    pub match_strategy: MatchStrategy,
     /// `metric_type` indicates the type of the trigger metric.
     pub metric_type: MetricType,
+    /// `threshold` is the concurrency limit of a fixed rule, or the initial limit of an adaptive rule.
     pub threshold: u32,
+    /// `strategy` indicates whether the limit is fixed or adjusted adaptively
+    /// from the RT and the in-flight invocations observed on completion.
+    pub strategy: LimitStrategy,
+    /// `min_threshold` and `max_threshold` bound the limit adjusted by an adaptive strategy.
+    pub min_threshold: u32,
+    pub max_threshold: u32,
+    /// `schedule` indicates when the rule is active, the rule is always active if it is `None`.
+    pub schedule: Option<Schedule>,
 }
 
 impl Default for Rule {
@@ -52,8 +82,13 @@ impl Default for Rule {
             #[cfg(not(target_arch = "wasm32"))]
             id: uuid::Uuid::new_v4().to_string(),
             resource: String::default(),
+            match_strategy: MatchStrategy::default(),
             metric_type: MetricType::default(),
             threshold: 0,
+            strategy: LimitStrategy::default(),
+            min_threshold: 0,
+            max_threshold: 0,
+            schedule: None,
         }
     }
 }
@@ -61,8 +96,13 @@ impl Default for Rule {
 impl PartialEq for Rule {
     fn eq(&self, other: &Self) -> bool {
         self.resource == other.resource
+            && self.match_strategy == other.match_strategy
             && self.metric_type == other.metric_type
             && self.threshold == other.threshold
+            && self.strategy == other.strategy
+            && self.min_threshold == other.min_threshold
+            && self.max_threshold == other.max_threshold
+            && self.schedule == other.schedule
     }
 }
 
@@ -75,6 +115,16 @@ impl Hash for Rule {
     }
 }
 
+impl PatternRule for Rule {
+    fn match_strategy(&self) -> MatchStrategy {
+        self.match_strategy
+    }
+
+    fn resource(&self) -> &str {
+        &self.resource
+    }
+}
+
 impl SentinelRule for Rule {
     fn resource_name(&self) -> String {
         format!("{:?}", self.metric_type)
@@ -84,10 +134,26 @@ impl SentinelRule for Rule {
         if self.resource.is_empty() {
             return Err(Error::msg("empty resource of isolation rule"));
         }
+        if self.match_strategy.is_pattern() {
+            self.match_strategy.compile(&self.resource)?;
+        }
 
         if self.threshold == 0 {
             return Err(Error::msg("zero threshold"));
         }
+        if let Some(schedule) = &self.schedule {
+            schedule.is_valid()?;
+        }
+        if self.strategy != LimitStrategy::Fixed {
+            if self.min_threshold == 0 {
+                return Err(Error::msg("zero min_threshold of adaptive strategy"));
+            }
+            if self.min_threshold > self.threshold || self.threshold > self.max_threshold {
+                return Err(Error::msg(
+                    "threshold of adaptive strategy should be in [min_threshold, max_threshold]",
+                ));
+            }
+        }
         Ok(())
     }
 }
@@ -114,6 +180,20 @@ mod test {
         rule.is_valid().unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "threshold of adaptive strategy should be in")]
+    fn invalid_adaptive_bounds() {
+        let rule = Rule {
+            resource: "invalid_adaptive_bounds".into(),
+            threshold: 10,
+            strategy: LimitStrategy::Vegas,
+            min_threshold: 1,
+            max_threshold: 5,
+            ..Default::default()
+        };
+        rule.is_valid().unwrap();
+    }
+
     #[test]
     #[should_panic(expected = "empty resource of isolation rule")]
     fn invalid_cpu_usage() {
@@ -1,5 +1,8 @@
 use super::*;
-use crate::{base::SentinelRule, logging, utils};
+use crate::{
+    base::{CompiledSchedule, PatternCache, SentinelRule},
+    logging, utils,
+};
 use crate::{Error, Result};
 use lazy_static::lazy_static;
 use std::collections::{HashMap, HashSet};
@@ -7,204 +10,387 @@ use std::sync::{Arc, Mutex, RwLock};
 
 pub type RuleMap = HashMap<String, HashSet<Arc<Rule>>>;
 
-lazy_static! {
-    static ref RULE_MAP: RwLock<RuleMap> = RwLock::new(RuleMap::new());
-    static ref CURRENT_RULES: Mutex<RuleMap> = Mutex::new(RuleMap::new());
+/// `RuleManager` holds the isolation rules of a Sentinel instance.
+pub struct RuleManager {
+    rule_map: RwLock<RuleMap>,
+    current_rules: Mutex<RuleMap>,
+    /// the rules with resource patterns matching each resource name
+    pattern_rules: PatternCache<Rule, Arc<Rule>>,
+    /// the limiters of the adaptive rules, created on the first check
+    limiters: RwLock<HashMap<Arc<Rule>, Arc<AdaptiveLimiter>>>,
+    /// the schedules of the scheduled rules, compiled when the rules are loaded
+    schedules: RwLock<HashMap<Arc<Rule>, CompiledSchedule>>,
 }
 
-/// `get_rules` returns all the rules in the global `RULE_MAP`
-// This func acquires a read lock on global `RULE_MAP`,
-// please release the lock before calling this func
-pub fn get_rules() -> Vec<Arc<Rule>> {
-    let rule_map = RULE_MAP.read().unwrap();
-    let mut rules = Vec::with_capacity(rule_map.len());
-    for r in rule_map.values() {
-        rules.append(&mut r.clone().into_iter().collect());
+impl Default for RuleManager {
+    fn default() -> Self {
+        Self::new()
     }
-    rules
 }
 
-/// `get_rules_of_resource` returns specific resource's rules
-// This func acquires a read lock on global `RULE_MAP`,
-// please release the lock before calling this func
-pub fn get_rules_of_resource(res: &String) -> Vec<Arc<Rule>> {
-    let placeholder = HashSet::new();
-    let rule_map = RULE_MAP.read().unwrap();
-    let res_rules = rule_map.get(res).unwrap_or(&placeholder);
+lazy_static! {
+    static ref DEFAULT_RULE_MANAGER: Arc<RuleManager> = Arc::new(RuleManager::new());
+}
 
-    res_rules.clone().into_iter().collect()
+/// `default_rule_manager` returns the rule manager used by the default Sentinel instance
+pub fn default_rule_manager() -> Arc<RuleManager> {
+    DEFAULT_RULE_MANAGER.clone()
 }
 
-pub fn append_rule(rule: Arc<Rule>) -> bool {
-    if RULE_MAP
-        .read()
-        .unwrap()
-        .get(&rule.resource)
-        .unwrap_or(&HashSet::new())
-        .contains(&rule)
-    {
-        return false;
+impl RuleManager {
+    pub fn new() -> Self {
+        RuleManager {
+            rule_map: RwLock::new(RuleMap::new()),
+            current_rules: Mutex::new(RuleMap::new()),
+            pattern_rules: PatternCache::new(),
+            limiters: RwLock::new(HashMap::new()),
+            schedules: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// `get_rules` returns all the rules in the `rule_map`
+    // This func acquires a read lock on `rule_map`,
+    // please release the lock before calling this func
+    pub fn get_rules(&self) -> Vec<Arc<Rule>> {
+        let rule_map = self.rule_map.read().unwrap();
+        let mut rules = Vec::with_capacity(rule_map.len());
+        for r in rule_map.values() {
+            rules.append(&mut r.clone().into_iter().collect());
+        }
+        rules
+    }
+
+    /// `get_rules_of_resource` returns specific resource's rules
+    // This func acquires a read lock on `rule_map`,
+    // please release the lock before calling this func
+    pub fn get_rules_of_resource(&self, res: &String) -> Vec<Arc<Rule>> {
+        let placeholder = HashSet::new();
+        let rule_map = self.rule_map.read().unwrap();
+        let res_rules = rule_map.get(res).unwrap_or(&placeholder);
+
+        res_rules.clone().into_iter().collect()
+    }
+
+    /// `get_active_rules_for` returns the rules taking effect on the resource which are active now,
+    /// according to the schedules of the rules
+    pub fn get_active_rules_for(&self, res: &String) -> Vec<Arc<Rule>> {
+        let mut rules = self.get_rules_for(res);
+        if rules.iter().any(|rule| rule.schedule.is_some()) {
+            let now = utils::curr_time_millis();
+            let schedules = self.schedules.read().unwrap();
+            rules.retain(|rule| match schedules.get(rule) {
+                Some(schedule) => schedule.is_active_at(now),
+                None => true,
+            });
+        }
+        rules
     }
 
-    match rule.is_valid() {
-        Ok(_) => {
-            RULE_MAP
+    /// `get_rules_for` returns the rules taking effect on the resource,
+    /// including the rules whose resource patterns match the resource name.
+    // This func acquires a read lock on `rule_map`,
+    // please release the lock before calling this func
+    pub fn get_rules_for(&self, res: &String) -> Vec<Arc<Rule>> {
+        let mut rules: Vec<Arc<Rule>> = self
+            .get_rules_of_resource(res)
+            .into_iter()
+            .filter(|rule| !rule.match_strategy.is_pattern())
+            .collect();
+        rules.append(&mut self.pattern_rules.get_or_resolve(res, |rules, _| rules));
+        rules
+    }
+
+    /// `get_limiter_of` returns the adaptive limiter of the rule,
+    /// or `None` if the rule has a fixed threshold.
+    /// Note that the limiter of a pattern rule is shared by all the matched resources.
+    pub fn get_limiter_of(&self, rule: &Arc<Rule>) -> Option<Arc<AdaptiveLimiter>> {
+        if rule.strategy == LimitStrategy::Fixed {
+            return None;
+        }
+        if let Some(limiter) = self.limiters.read().unwrap().get(rule) {
+            return Some(Arc::clone(limiter));
+        }
+        let limiter = AdaptiveLimiter::new(Arc::clone(rule))?;
+        Some(Arc::clone(
+            self.limiters
                 .write()
                 .unwrap()
-                .entry(rule.resource.clone())
-                .or_default()
-                .insert(Arc::clone(&rule));
-            CURRENT_RULES
-                .lock()
-                .unwrap()
-                .entry(rule.resource.clone())
-                .or_default()
-                .insert(rule);
+                .entry(Arc::clone(rule))
+                .or_insert_with(|| Arc::new(limiter)),
+        ))
+    }
+
+    /// `current_limit_of` returns the current concurrency limit of the rule
+    pub fn current_limit_of(&self, rule: &Arc<Rule>) -> u32 {
+        match self.get_limiter_of(rule) {
+            Some(limiter) => limiter.limit(),
+            None => rule.threshold,
         }
-        Err(err) => logging::warn!(
-            "[System append_rule] Ignoring invalid rule {:?}, reason: {:?}",
-            rule,
-            err
-        ),
-    };
-    true
-}
+    }
 
-/// `load_rules` loads given isolation rules to the rule manager, while all previous rules will be replaced.
-// This func acquires the locks on global `CURRENT_RULES` and `RULE_MAP`,
-// please release the locks before calling this func
-pub fn load_rules(rules: Vec<Arc<Rule>>) {
-    let mut res_rules_map = RuleMap::new();
-    for rule in rules {
-        let val = res_rules_map.entry(rule.resource.clone()).or_default();
-        val.insert(rule);
+    /// `get_limits_for` returns the current concurrency limits of the rules taking effect on the resource
+    pub fn get_limits_for(&self, res: &String) -> Vec<(Arc<Rule>, u32)> {
+        self.get_rules_for(res)
+            .into_iter()
+            .map(|rule| {
+                let limit = self.current_limit_of(&rule);
+                (rule, limit)
+            })
+            .collect()
+    }
+
+    /// `compile_rules` compiles the schedules and the resource patterns of the rules,
+    /// the ones of the removed rules are dropped
+    fn compile_rules(&self, rule_map: &RuleMap) {
+        self.pattern_rules.load_rules(rule_map.values().flatten());
+        let mut schedules = self.schedules.write().unwrap();
+        schedules.clear();
+        for rule in rule_map.values().flatten() {
+            // the schedule has been validated along with the rule
+            if let Some(schedule) = rule.schedule.as_ref().and_then(|s| s.compile().ok()) {
+                schedules.insert(Arc::clone(rule), schedule);
+            }
+        }
     }
-    let mut current_rules = CURRENT_RULES.lock().unwrap();
-    if *current_rules == res_rules_map {
+
+    /// `prune_limiters` drops the limiters of the rules which have been removed
+    fn prune_limiters(&self, rule_map: &RuleMap) {
+        self.limiters.write().unwrap().retain(|rule, _| {
+            rule_map
+                .get(&rule.resource)
+                .is_some_and(|rules| rules.contains(rule))
+        });
+    }
+
+    pub fn append_rule(&self, rule: Arc<Rule>) -> bool {
+        if self
+            .rule_map
+            .read()
+            .unwrap()
+            .get(&rule.resource)
+            .unwrap_or(&HashSet::new())
+            .contains(&rule)
+        {
+            return false;
+        }
+
+        match rule.is_valid() {
+            Ok(_) => {
+                self.rule_map
+                    .write()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(Arc::clone(&rule));
+                self.current_rules
+                    .lock()
+                    .unwrap()
+                    .entry(rule.resource.clone())
+                    .or_default()
+                    .insert(rule);
+                self.compile_rules(&self.rule_map.read().unwrap());
+            }
+            Err(err) => logging::warn!(
+                "[System append_rule] Ignoring invalid rule {:?}, reason: {:?}",
+                rule,
+                err
+            ),
+        };
+        true
+    }
+
+    /// `load_rules` loads given isolation rules to the rule manager, while all previous rules will be replaced.
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn load_rules(&self, rules: Vec<Arc<Rule>>) {
+        let mut res_rules_map = RuleMap::new();
+        for rule in rules {
+            let val = res_rules_map.entry(rule.resource.clone()).or_default();
+            val.insert(rule);
+        }
+        let mut current_rules = self.current_rules.lock().unwrap();
+        if *current_rules == res_rules_map {
+            logging::info!(
+                "[Isolation] Load rules is the same with current rules, so ignore load operation."
+            );
+            return;
+        }
+
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
+        let mut valid_res_rule_map = RuleMap::with_capacity(res_rules_map.len());
+        for (res, rules) in &res_rules_map {
+            let mut valid_res_rules = HashSet::with_capacity(rules.len());
+            for rule in rules {
+                match rule.is_valid() {
+                    Ok(_) => {
+                        valid_res_rules.insert(Arc::clone(rule));
+                    }
+                    Err(err) => logging::warn!(
+                        "[Isolation load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
+                        rule,
+                        err
+                    ),
+                }
+            }
+            if !valid_res_rules.is_empty() {
+                valid_res_rule_map.insert(res.clone(), valid_res_rules);
+            }
+        }
+
+        let start = utils::curr_time_nanos();
+        let mut rule_map = self.rule_map.write().unwrap();
+        *rule_map = valid_res_rule_map;
+        *current_rules = res_rules_map;
+        self.prune_limiters(&rule_map);
+        self.compile_rules(&rule_map);
+
+        logging::debug!(
+            "[Isolation load_rules] Time statistic(ns) for updating isolation rule, timeCost {:?}",
+            utils::curr_time_nanos() - start
+        );
         logging::info!(
-            "[Isolation] Load rules is the same with current rules, so ignore load operation."
+            "[SystemRuleManager] Isolation rules loaded, rules {:?}",
+            rule_map
         );
-        return;
     }
 
-    // when rule_map is different with global one, update the global one
-    // ignore invalid rules
-    let mut valid_res_rule_map = RuleMap::with_capacity(res_rules_map.len());
-    for (res, rules) in &res_rules_map {
+    /// `load_rules` loads the given resource's isolation rules to the rule manager, while all previous resource's rules will be replaced.
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn load_rules_of_resource(&self, res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+        if res.is_empty() {
+            return Err(Error::msg("empty resource"));
+        }
+        let rules: HashSet<_> = rules.into_iter().collect();
+
+        if rules.is_empty() {
+            self.clear_rules_of_resource(res);
+            logging::info!("[Isolation] clear resource level rules, resource {}", res);
+            return Ok(true);
+        }
+
+        if self
+            .current_rules
+            .lock()
+            .unwrap()
+            .get(res)
+            .unwrap_or(&HashSet::new())
+            == &rules
+        {
+            logging::info!(
+                "[Isolation] Load resource level rules is the same with current resource level rules, so ignore load operation."
+            );
+            return Ok(false);
+        }
+
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
         let mut valid_res_rules = HashSet::with_capacity(rules.len());
-        for rule in rules {
+        for rule in &rules {
             match rule.is_valid() {
                 Ok(_) => {
                     valid_res_rules.insert(Arc::clone(rule));
                 }
                 Err(err) => logging::warn!(
-                    "[Isolation load_rules] Ignoring invalid flow rule {:?}, reason: {:?}",
+                    "[Isolation load_rules_of_resource] Ignoring invalid flow rule {:?}, reason: {:?}",
                     rule,
                     err
                 ),
             }
         }
-        if !valid_res_rules.is_empty() {
-            valid_res_rule_map.insert(res.clone(), valid_res_rules);
-        }
-    }
-
-    let start = utils::curr_time_nanos();
-    let mut rule_map = RULE_MAP.write().unwrap();
-    *rule_map = valid_res_rule_map;
-    *current_rules = res_rules_map;
-
-    logging::debug!(
-        "[Isolation load_rules] Time statistic(ns) for updating isolation rule, timeCost {:?}",
-        utils::curr_time_nanos() - start
-    );
-    logging::info!(
-        "[SystemRuleManager] Isolation rules loaded, rules {:?}",
-        rule_map
-    );
-}
-
-/// `load_rules` loads the given resource's isolation rules to the rule manager, while all previous resource's rules will be replaced.
-// This func acquires the locks on global `CURRENT_RULES` and `RULE_MAP`,
-// please release the locks before calling this func
-pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
-    if res.is_empty() {
-        return Err(Error::msg("empty resource"));
-    }
-    let rules: HashSet<_> = rules.into_iter().collect();
 
-    if rules.is_empty() {
-        clear_rules_of_resource(res);
-        logging::info!("[Isolation] clear resource level rules, resource {}", res);
-        return Ok(true);
-    }
+        let valid_res_rules_string = format!("{:?}", &valid_res_rules);
+        let start = utils::curr_time_nanos();
+        if valid_res_rules.is_empty() {
+            self.rule_map.write().unwrap().remove(res);
+        } else {
+            self.rule_map
+                .write()
+                .unwrap()
+                .insert(res.clone(), valid_res_rules);
+        }
+        self.current_rules
+            .lock()
+            .unwrap()
+            .insert(res.clone(), rules);
+        let rule_map = self.rule_map.read().unwrap();
+        self.prune_limiters(&rule_map);
+        self.compile_rules(&rule_map);
 
-    if CURRENT_RULES
-        .lock()
-        .unwrap()
-        .get(res)
-        .unwrap_or(&HashSet::new())
-        == &rules
-    {
+        logging::debug!(
+            "[Isolation load_rules] Time statistic(ns) for updating isolation rule, timeCost {:?}",
+            utils::curr_time_nanos() - start
+        );
         logging::info!(
-            "[Isolation] Load resource level rules is the same with current resource level rules, so ignore load operation."
+            "[IsolationRuleManager] Isolation rules loaded, rules {}",
+            valid_res_rules_string
         );
-        return Ok(false);
+        Ok(true)
     }
 
-    // when rule_map is different with global one, update the global one
-    // ignore invalid rules
-    let mut valid_res_rules = HashSet::with_capacity(rules.len());
-    for rule in &rules {
-        match rule.is_valid() {
-            Ok(_) => {
-                valid_res_rules.insert(Arc::clone(rule));
-            }
-            Err(err) => logging::warn!(
-                "[Isolation load_rules_of_resource] Ignoring invalid flow rule {:?}, reason: {:?}",
-                rule,
-                err
-            ),
-        }
+    /// `clear_rules` clear all the rules in isolation module
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn clear_rules(&self) {
+        self.current_rules.lock().unwrap().clear();
+        self.rule_map.write().unwrap().clear();
+        self.pattern_rules.clear();
+        self.limiters.write().unwrap().clear();
+        self.schedules.write().unwrap().clear();
     }
 
-    let valid_res_rules_string = format!("{:?}", &valid_res_rules);
-    let start = utils::curr_time_nanos();
-    if valid_res_rules.is_empty() {
-        RULE_MAP.write().unwrap().remove(res);
-    } else {
-        RULE_MAP
-            .write()
-            .unwrap()
-            .insert(res.clone(), valid_res_rules);
+    /// ClearRulesOfResource clears resource level rules in isolation module.
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn clear_rules_of_resource(&self, res: &String) {
+        self.current_rules.lock().unwrap().remove(res);
+        self.rule_map.write().unwrap().remove(res);
+        let rule_map = self.rule_map.read().unwrap();
+        self.prune_limiters(&rule_map);
+        self.compile_rules(&rule_map);
     }
-    CURRENT_RULES.lock().unwrap().insert(res.clone(), rules);
-
-    logging::debug!(
-        "[Isolation load_rules] Time statistic(ns) for updating isolation rule, timeCost {:?}",
-        utils::curr_time_nanos() - start
-    );
-    logging::info!(
-        "[IsolationRuleManager] Isolation rules loaded, rules {}",
-        valid_res_rules_string
-    );
-    Ok(true)
 }
 
-/// `clear_rules` clear all the rules in isolation module
-// This func acquires the locks on global `CURRENT_RULES` and `RULE_MAP`,
-// please release the locks before calling this func
+// The following functions operate on the default rule manager.
+pub fn get_rules() -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules()
+}
+
+pub fn get_rules_of_resource(res: &String) -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules_of_resource(res)
+}
+
+pub fn get_rules_for(res: &String) -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules_for(res)
+}
+
+pub fn get_active_rules_for(res: &String) -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_active_rules_for(res)
+}
+
+pub fn get_limits_for(res: &String) -> Vec<(Arc<Rule>, u32)> {
+    DEFAULT_RULE_MANAGER.get_limits_for(res)
+}
+
+pub fn append_rule(rule: Arc<Rule>) -> bool {
+    DEFAULT_RULE_MANAGER.append_rule(rule)
+}
+
+pub fn load_rules(rules: Vec<Arc<Rule>>) {
+    DEFAULT_RULE_MANAGER.load_rules(rules)
+}
+
+pub fn load_rules_of_resource(res: &String, rules: Vec<Arc<Rule>>) -> Result<bool> {
+    DEFAULT_RULE_MANAGER.load_rules_of_resource(res, rules)
+}
+
 pub fn clear_rules() {
-    CURRENT_RULES.lock().unwrap().clear();
-    RULE_MAP.write().unwrap().clear();
+    DEFAULT_RULE_MANAGER.clear_rules()
 }
 
-/// ClearRulesOfResource clears resource level rules in isolation module.
-// This func acquires the locks on global `CURRENT_RULES` and `RULE_MAP`,
-// please release the locks before calling this func
 pub fn clear_rules_of_resource(res: &String) {
-    CURRENT_RULES.lock().unwrap().remove(res);
-    RULE_MAP.write().unwrap().remove(res);
+    DEFAULT_RULE_MANAGER.clear_rules_of_resource(res)
 }
 
 #[cfg(test)]
@@ -212,6 +398,93 @@ mod test {
     //! Some tests cannot run in parallel, since we cannot promise that
     //! the global data structs are not modified before assertion.
     use super::*;
+    use crate::base::{Schedule, TimeWindow};
+
+    #[test]
+    fn pattern_rules() {
+        let rule_manager = RuleManager::new();
+        rule_manager.load_rules(vec![Arc::new(Rule {
+            resource: r"/api/users/\d+".into(),
+            match_strategy: crate::base::MatchStrategy::Regex,
+            threshold: 10,
+            ..Default::default()
+        })]);
+        assert_eq!(rule_manager.get_rules_for(&"/api/users/1".into()).len(), 1);
+        assert!(rule_manager
+            .get_rules_for(&"/api/users/a".into())
+            .is_empty());
+        rule_manager.clear_rules();
+        assert!(rule_manager
+            .get_rules_for(&"/api/users/1".into())
+            .is_empty());
+    }
+
+    #[test]
+    fn adaptive_limits() {
+        let rule_manager = RuleManager::new();
+        let rule = Arc::new(Rule {
+            resource: "adaptive_limits".into(),
+            threshold: 10,
+            strategy: LimitStrategy::AIMD,
+            min_threshold: 1,
+            max_threshold: 100,
+            ..Default::default()
+        });
+        rule_manager.load_rules(vec![Arc::clone(&rule)]);
+        let limiter = rule_manager.get_limiter_of(&rule).unwrap();
+        limiter.on_sample(&Sample {
+            rt: 10,
+            in_flight: 10,
+            dropped: false,
+        });
+        assert_eq!(
+            rule_manager.get_limits_for(&"adaptive_limits".into()),
+            vec![(Arc::clone(&rule), 11)]
+        );
+        // the state of the limiter is kept, since the rule is not changed
+        rule_manager
+            .load_rules_of_resource(&"adaptive_limits".into(), vec![Arc::clone(&rule)])
+            .unwrap();
+        assert_eq!(rule_manager.current_limit_of(&rule), 11);
+        rule_manager.clear_rules();
+        assert!(rule_manager.limiters.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn scheduled_rules() {
+        let rule_manager = RuleManager::new();
+        let res_name = String::from("scheduled_rules");
+        rule_manager.load_rules(vec![
+            Arc::new(Rule {
+                resource: res_name.clone(),
+                threshold: 10,
+                // active all day long
+                schedule: Some(Schedule {
+                    windows: vec![TimeWindow {
+                        start: "00:00".into(),
+                        end: "00:00".into(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            Arc::new(Rule {
+                resource: res_name.clone(),
+                threshold: 20,
+                // never active, since there is no February 31st
+                schedule: Some(Schedule {
+                    cron: "0 0 31 2 *".into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        ]);
+        assert_eq!(rule_manager.get_rules_for(&res_name).len(), 2);
+        let active = rule_manager.get_active_rules_for(&res_name);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].threshold, 10);
+    }
 
     #[test]
     fn empty_rules() {
@@ -254,8 +527,8 @@ mod test {
             r4,
             Arc::clone(&r5),
         ]);
-        let rule_map = RULE_MAP.read().unwrap();
-        let current_rules = CURRENT_RULES.lock().unwrap();
+        let rule_map = DEFAULT_RULE_MANAGER.rule_map.read().unwrap();
+        let current_rules = DEFAULT_RULE_MANAGER.current_rules.lock().unwrap();
         assert_eq!(2, rule_map.len());
         assert_eq!(2, rule_map["abc1"].len());
         assert_eq!(1, rule_map["abc3"].len());
@@ -265,8 +538,8 @@ mod test {
         drop(current_rules);
 
         clear_rules();
-        assert_eq!(0, RULE_MAP.read().unwrap().len());
-        assert_eq!(0, CURRENT_RULES.lock().unwrap().len());
+        assert_eq!(0, DEFAULT_RULE_MANAGER.rule_map.read().unwrap().len());
+        assert_eq!(0, DEFAULT_RULE_MANAGER.current_rules.lock().unwrap().len());
     }
 
     #[test]
@@ -278,7 +551,7 @@ mod test {
             ..Default::default()
         });
         let result = load_rules_of_resource(&"".into(), vec![r1]);
-        assert_eq!(0, RULE_MAP.read().unwrap().len());
+        assert_eq!(0, DEFAULT_RULE_MANAGER.rule_map.read().unwrap().len());
         result.unwrap();
     }
 
@@ -312,8 +585,8 @@ mod test {
         // that is, rule of "abc3" cannot be loaded to "abc1"
         load_rules_of_resource(&"abc1".into(), vec![Arc::clone(&r1), Arc::clone(&r2)]).unwrap();
         load_rules_of_resource(&"abc3".into(), vec![Arc::clone(&r3), Arc::clone(&r4)]).unwrap();
-        let rule_map = RULE_MAP.read().unwrap();
-        let current_rules = CURRENT_RULES.lock().unwrap();
+        let rule_map = DEFAULT_RULE_MANAGER.rule_map.read().unwrap();
+        let current_rules = DEFAULT_RULE_MANAGER.current_rules.lock().unwrap();
         assert_eq!(2, rule_map.len());
         assert_eq!(2, rule_map["abc1"].len());
         assert_eq!(1, rule_map["abc3"].len());
@@ -323,10 +596,10 @@ mod test {
         drop(current_rules);
 
         clear_rules_of_resource(&"abc1".into());
-        assert_eq!(1, RULE_MAP.read().unwrap().len());
-        assert_eq!(1, CURRENT_RULES.lock().unwrap().len());
+        assert_eq!(1, DEFAULT_RULE_MANAGER.rule_map.read().unwrap().len());
+        assert_eq!(1, DEFAULT_RULE_MANAGER.current_rules.lock().unwrap().len());
         clear_rules_of_resource(&"abc3".into());
-        assert_eq!(0, RULE_MAP.read().unwrap().len());
-        assert_eq!(0, CURRENT_RULES.lock().unwrap().len());
+        assert_eq!(0, DEFAULT_RULE_MANAGER.rule_map.read().unwrap().len());
+        assert_eq!(0, DEFAULT_RULE_MANAGER.current_rules.lock().unwrap().len());
     }
 }
@@ -6,10 +6,24 @@ use std::sync::Arc;
 const RULE_CHECK_SLOT_ORDER: u32 = 3000;
 
 /// A RuleSlot for flow related metrics
-pub struct AdaptiveSlot {}
+pub struct AdaptiveSlot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl AdaptiveSlot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        AdaptiveSlot { rule_manager }
+    }
+}
+
+impl Default for AdaptiveSlot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
 
 lazy_static! {
-    pub static ref DEFAULT_ADAPTIVE_SLOT: Arc<AdaptiveSlot> = Arc::new(AdaptiveSlot {});
+    pub static ref DEFAULT_ADAPTIVE_SLOT: Arc<AdaptiveSlot> = Arc::new(AdaptiveSlot::default());
 }
 
 pub fn default_slot() -> Arc<AdaptiveSlot> {
@@ -28,7 +42,7 @@ impl RuleCheckSlot for AdaptiveSlot {
         if res_name.is_empty() {
             return ctx.result().clone();
         }
-        let (passed, rule, snapshot) = can_pass_check(ctx, &res_name);
+        let (passed, rule, snapshot) = can_pass_check(&self.rule_manager, ctx, &res_name);
         if !passed {
             // never panic
             ctx.set_result(TokenResult::new_blocked_with_cause(
@@ -43,13 +57,14 @@ impl RuleCheckSlot for AdaptiveSlot {
 }
 
 fn can_pass_check(
+    rule_manager: &RuleManager,
     ctx: &EntryContext,
     res: &String,
 ) -> (bool, Option<Arc<Rule>>, Option<Arc<Snapshot>>) {
     let stat_node = ctx.stat_node().unwrap();
     let batch_count = ctx.input().batch_count();
-    for rule in get_rules_of_resource(res) {
-        let threshold = rule.threshold;
+    for rule in rule_manager.get_active_rules_for(res) {
+        let threshold = rule_manager.current_limit_of(&rule);
         if rule.metric_type == MetricType::Concurrency {
             let curr_count = stat_node.current_concurrency();
             // if pass `batch_count` tasks in the `ctx`, the limits on concurrency would break
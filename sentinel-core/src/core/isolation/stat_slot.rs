@@ -0,0 +1,110 @@
+use super::*;
+use crate::base::{BaseSlot, BlockError, EntryContext, EntryError, StatSlot};
+use crate::Error;
+use lazy_static::lazy_static;
+use std::sync::Arc;
+
+const STAT_SLOT_ORDER: u32 = 3000;
+
+/// LimiterStatSlot feeds the RT and the in-flight invocations to the adaptive limiters on invocation completed.
+/// LimiterStatSlot must be filled into slot chain if adaptive isolation rules are alive.
+pub struct LimiterStatSlot {
+    rule_manager: Arc<RuleManager>,
+}
+
+impl LimiterStatSlot {
+    pub fn new(rule_manager: Arc<RuleManager>) -> Self {
+        LimiterStatSlot { rule_manager }
+    }
+}
+
+impl Default for LimiterStatSlot {
+    fn default() -> Self {
+        Self::new(default_rule_manager())
+    }
+}
+
+lazy_static! {
+    pub static ref DEFAULT_LIMITER_STAT_SLOT: Arc<LimiterStatSlot> =
+        Arc::new(LimiterStatSlot::default());
+}
+
+pub fn default_limiter_stat_slot() -> Arc<LimiterStatSlot> {
+    DEFAULT_LIMITER_STAT_SLOT.clone()
+}
+
+/// `is_dropped` checks whether the error traced on the invocation is a signal of overload,
+/// i.e., the invocation timed out, or it was blocked by the rules of the downstream resources.
+/// The other errors, such as business errors, are not regarded as drops.
+fn is_dropped(err: &Error) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<BlockError>() || cause.is::<EntryError>() {
+            return true;
+        }
+        if let Some(io_err) = cause.downcast_ref::<std::io::Error>() {
+            return io_err.kind() == std::io::ErrorKind::TimedOut;
+        }
+        // e.g., the timeout errors traced by `run()`, which only keep the messages
+        let msg = cause.to_string().to_lowercase();
+        msg.contains("timed out") || msg.contains("timeout")
+    })
+}
+
+impl BaseSlot for LimiterStatSlot {
+    fn order(&self) -> u32 {
+        STAT_SLOT_ORDER
+    }
+}
+
+impl StatSlot for LimiterStatSlot {
+    fn on_entry_pass(&self, _ctx: &EntryContext) {}
+
+    fn on_entry_blocked(&self, _ctx: &EntryContext, _block_error: BlockError) {}
+
+    fn on_completed(&self, ctx: &mut EntryContext) {
+        let res = ctx.resource().name();
+        let stat_node = match ctx.stat_node() {
+            Some(stat_node) => stat_node,
+            None => return,
+        };
+        let sample = Sample {
+            rt: ctx.round_trip(),
+            // the concurrency has been decreased by the `ResourceNodeStatSlot`
+            in_flight: stat_node.current_concurrency() + 1,
+            dropped: ctx.get_err().as_ref().is_some_and(is_dropped),
+        };
+        // every limiter is fed, and the effective limit of the resource is the minimum one of its adaptive rules
+        let _limit = self
+            .rule_manager
+            .get_rules_for(res)
+            .iter()
+            .filter_map(|rule| self.rule_manager.get_limiter_of(rule))
+            .map(|limiter| limiter.on_sample(&sample))
+            .min();
+        #[cfg(feature = "exporter")]
+        if let Some(limit) = _limit {
+            crate::exporter::set_concurrency_limit(res, limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::BlockType;
+
+    #[test]
+    fn dropped() {
+        assert!(!is_dropped(&Error::msg("biz error")));
+        assert!(!is_dropped(&Error::new(std::io::Error::other("reset"))));
+        assert!(is_dropped(&Error::new(std::io::Error::from(
+            std::io::ErrorKind::TimedOut
+        ))));
+        assert!(is_dropped(
+            &Error::msg("request timed out").context("failed to call the dependency")
+        ));
+        assert!(is_dropped(&Error::new(EntryError::from(BlockError::new(
+            BlockType::Flow
+        )))));
+    }
+}
@@ -104,6 +104,8 @@ fn is_active_metric_item(item: &MetricItem) -> bool {
         || item.error_qps > 0
         || item.avg_rt > 0
         || item.concurrency > 0
+        || item.shadow_block_qps > 0
+        || item.occupied_pass_qps > 0
 }
 
 fn is_item_time_stamp_in_time(ts: u64, current_sec_start: u64) -> bool {
@@ -1,9 +1,13 @@
 //! Core implementations of Sentinel.
 
+/// Origin based access control (allow/deny list) rules and slots.
+pub mod authority;
 /// Basic definitions, traits, and implementations for Sentinel slot chain, entry, context and so on.
 pub mod base;
 /// Circuit breaker rules and slots.
 pub mod circuitbreaker;
+/// Cluster flow control, including the token server and the token client.
+pub mod cluster;
 /// Configuration utilities.
 pub mod config;
 /// Flow control rules and slots.
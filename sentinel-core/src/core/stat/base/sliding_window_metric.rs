@@ -142,6 +142,8 @@ impl SlidingWindowMetric {
             metric_item.block_qps += b.get(MetricEvent::Block);
             metric_item.error_qps += b.get(MetricEvent::Error);
             metric_item.complete_qps += b.get(MetricEvent::Complete);
+            metric_item.shadow_block_qps += b.get(MetricEvent::ShadowBlock);
+            metric_item.occupied_pass_qps += b.get(MetricEvent::OccupiedPass);
             metric_item.concurrency = cmp::max(b.max_concurrency(), metric_item.concurrency);
             all_rt += b.get(MetricEvent::Rt);
         }
@@ -172,6 +174,8 @@ impl SlidingWindowMetric {
             complete_qps,
             error_qps: bucket.get(MetricEvent::Error),
             avg_rt,
+            shadow_block_qps: bucket.get(MetricEvent::ShadowBlock),
+            occupied_pass_qps: bucket.get(MetricEvent::OccupiedPass),
             ..MetricItem::default()
         }
     }
@@ -199,6 +203,12 @@ impl ReadStat for SlidingWindowMetric {
         }
     }
 
+    fn next_bucket_sum(&self, event: MetricEvent) -> Option<(u64, u64)> {
+        let now = curr_time_millis();
+        let next_start = self.inner.calculate_start_stamp(now) + self.inner.bucket_len_ms() as u64;
+        Some((next_start, self.sum_with_time(next_start, event)))
+    }
+
     fn min_rt(&self) -> f64 {
         let buckets = self.satisfied_buckets(curr_time_millis());
         let mut res = DEFAULT_STATISTIC_MAX_RT;
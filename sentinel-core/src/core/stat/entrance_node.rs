@@ -0,0 +1,69 @@
+use super::ResourceNode;
+use crate::base::{ResourceType, DEFAULT_MAX_RESOURCE_AMOUNT};
+use crate::logging;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// EntranceNode is the root of the call tree of an invocation context.
+/// It keeps the statistic nodes of the resources invoked under this context,
+/// so that the resources can be limited differently per entrance.
+/// The tree is flattened, i.e., the children are keyed by the resource names directly,
+/// and a resource reached through different paths under the same context shares a single node.
+/// It is sufficient for `flow::RelationStrategy::Chain`, which only cares about the entrance.
+#[derive(Debug)]
+pub struct EntranceNode {
+    pub(crate) name: String,
+    /// statistic nodes of the resources invoked under this context
+    pub(crate) children: RwLock<HashMap<String, Arc<ResourceNode>>>,
+}
+
+impl EntranceNode {
+    pub fn new(name: String) -> Self {
+        EntranceNode {
+            name,
+            children: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_child_node(&self, res_name: &str) -> Option<Arc<ResourceNode>> {
+        self.children.read().unwrap().get(res_name).cloned()
+    }
+
+    /// get_or_create_child_node returns the statistic node of the resource under this context,
+    /// the node will be created if it does not exist
+    pub fn get_or_create_child_node(
+        &self,
+        res_name: &str,
+        resource_type: &ResourceType,
+    ) -> Arc<ResourceNode> {
+        if let Some(node) = self.get_child_node(res_name) {
+            return node;
+        }
+        let mut children = self.children.write().unwrap();
+        if children.len() >= DEFAULT_MAX_RESOURCE_AMOUNT {
+            logging::warn!(
+                "[EntranceNode] Resource amount of context {} exceeds the threshold {}",
+                self.name,
+                DEFAULT_MAX_RESOURCE_AMOUNT
+            )
+        }
+        children
+            .entry(res_name.into())
+            .or_insert_with(|| Arc::new(ResourceNode::new(res_name.into(), *resource_type)))
+            .clone()
+    }
+
+    /// child_node_list returns all the statistic nodes under this context, with their resource names
+    pub fn child_node_list(&self) -> Vec<(String, Arc<ResourceNode>)> {
+        self.children
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(res_name, node)| (res_name.clone(), node.clone()))
+            .collect()
+    }
+}
@@ -1,4 +1,4 @@
-use super::ResourceNode;
+use super::{EntranceNode, ResourceNode};
 use crate::{
     base::{ResourceType, DEFAULT_MAX_RESOURCE_AMOUNT, TOTAL_IN_BOUND_RESOURCE_NAME},
     logging,
@@ -8,58 +8,159 @@ use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 type ResourceNodeMap = HashMap<String, Arc<ResourceNode>>;
+type EntranceNodeMap = HashMap<String, Arc<EntranceNode>>;
+
+/// `NodeStorage` keeps the statistic nodes of a Sentinel instance,
+/// including the global inbound node, the resource nodes and the entrance nodes.
+#[derive(Debug)]
+pub struct NodeStorage {
+    inbound_node: Arc<ResourceNode>,
+    resource_node_map: RwLock<ResourceNodeMap>,
+    entrance_node_map: RwLock<EntranceNodeMap>,
+}
+
+impl Default for NodeStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeStorage {
+    pub fn new() -> Self {
+        NodeStorage {
+            inbound_node: Arc::new(ResourceNode::new(
+                TOTAL_IN_BOUND_RESOURCE_NAME.into(),
+                ResourceType::Common,
+            )),
+            resource_node_map: RwLock::new(ResourceNodeMap::new()),
+            entrance_node_map: RwLock::new(EntranceNodeMap::new()),
+        }
+    }
+
+    pub fn inbound_node(&self) -> Arc<ResourceNode> {
+        self.inbound_node.clone()
+    }
+
+    // resource_node_list returns the slice of all existing resource nodes.
+    pub fn resource_node_list(&self) -> Vec<Arc<ResourceNode>> {
+        let res_map = self.resource_node_map.read().unwrap();
+        res_map.values().cloned().collect()
+    }
+
+    pub fn get_resource_node(&self, res_name: &String) -> Option<Arc<ResourceNode>> {
+        let res_map = self.resource_node_map.read().unwrap();
+        res_map.get(res_name).cloned()
+    }
+
+    pub fn get_or_create_resource_node(
+        &self,
+        res_name: &String,
+        resource_type: &ResourceType,
+    ) -> Arc<ResourceNode> {
+        let node = self.get_resource_node(res_name);
+        match node {
+            Some(node) => node,
+            None => {
+                if self.resource_node_map.read().unwrap().len() >= DEFAULT_MAX_RESOURCE_AMOUNT {
+                    logging::warn!(
+                        "[get_or_create_resource_node] Resource amount exceeds the threshold {}",
+                        DEFAULT_MAX_RESOURCE_AMOUNT
+                    )
+                }
+                self.resource_node_map
+                    .write()
+                    .unwrap()
+                    .entry(res_name.clone())
+                    .or_insert_with(|| {
+                        Arc::new(ResourceNode::new(res_name.clone(), *resource_type))
+                    })
+                    .clone()
+            }
+        }
+    }
+
+    pub fn reset_resource_map(&self) {
+        self.resource_node_map.write().unwrap().clear();
+    }
+
+    // entrance_node_list returns the slice of all existing entrance nodes, i.e., the roots of call trees.
+    pub fn entrance_node_list(&self) -> Vec<Arc<EntranceNode>> {
+        let entrance_map = self.entrance_node_map.read().unwrap();
+        entrance_map.values().cloned().collect()
+    }
+
+    pub fn get_entrance_node(&self, context_name: &str) -> Option<Arc<EntranceNode>> {
+        let entrance_map = self.entrance_node_map.read().unwrap();
+        entrance_map.get(context_name).cloned()
+    }
+
+    pub fn get_or_create_entrance_node(&self, context_name: &str) -> Arc<EntranceNode> {
+        if let Some(node) = self.get_entrance_node(context_name) {
+            return node;
+        }
+        let mut entrance_map = self.entrance_node_map.write().unwrap();
+        if entrance_map.len() >= DEFAULT_MAX_RESOURCE_AMOUNT {
+            logging::warn!(
+                "[get_or_create_entrance_node] Context amount exceeds the threshold {}",
+                DEFAULT_MAX_RESOURCE_AMOUNT
+            )
+        }
+        entrance_map
+            .entry(context_name.into())
+            .or_insert_with(|| Arc::new(EntranceNode::new(context_name.into())))
+            .clone()
+    }
+
+    pub fn reset_entrance_map(&self) {
+        self.entrance_node_map.write().unwrap().clear();
+    }
+}
 
 lazy_static! {
-    pub static ref INBOUND_NODE: Arc<ResourceNode> = Arc::new(ResourceNode::new(
-        TOTAL_IN_BOUND_RESOURCE_NAME.into(),
-        ResourceType::Common
-    ));
-    static ref RESOURCE_NODE_MAP: RwLock<ResourceNodeMap> = RwLock::new(ResourceNodeMap::new());
+    static ref DEFAULT_NODE_STORAGE: Arc<NodeStorage> = Arc::new(NodeStorage::new());
+}
+
+/// `default_node_storage` returns the node storage used by the default Sentinel instance
+pub fn default_node_storage() -> Arc<NodeStorage> {
+    DEFAULT_NODE_STORAGE.clone()
 }
 
+// The following functions operate on the default node storage.
 pub fn inbound_node() -> Arc<ResourceNode> {
-    INBOUND_NODE.clone()
+    DEFAULT_NODE_STORAGE.inbound_node()
 }
 
-// resource_node_list returns the slice of all existing resource nodes.
 pub fn resource_node_list() -> Vec<Arc<ResourceNode>> {
-    let res_map = RESOURCE_NODE_MAP.read().unwrap();
-    res_map.values().cloned().collect()
+    DEFAULT_NODE_STORAGE.resource_node_list()
 }
 
 pub fn get_resource_node(res_name: &String) -> Option<Arc<ResourceNode>> {
-    let res_map = RESOURCE_NODE_MAP.read().unwrap();
-    res_map.get(res_name).cloned()
+    DEFAULT_NODE_STORAGE.get_resource_node(res_name)
 }
 
 pub fn get_or_create_resource_node(
     res_name: &String,
     resource_type: &ResourceType,
 ) -> Arc<ResourceNode> {
-    let node = get_resource_node(res_name);
-    match node {
-        Some(node) => node,
-        None => {
-            if RESOURCE_NODE_MAP.read().unwrap().len() >= DEFAULT_MAX_RESOURCE_AMOUNT {
-                logging::warn!(
-                    "[get_or_create_resource_node] Resource amount exceeds the threshold {}",
-                    DEFAULT_MAX_RESOURCE_AMOUNT
-                )
-            }
-            RESOURCE_NODE_MAP.write().unwrap().insert(
-                res_name.clone(),
-                Arc::new(ResourceNode::new(res_name.clone(), *resource_type)),
-            );
-            RESOURCE_NODE_MAP
-                .read()
-                .unwrap()
-                .get(res_name)
-                .unwrap()
-                .clone()
-        }
-    }
+    DEFAULT_NODE_STORAGE.get_or_create_resource_node(res_name, resource_type)
 }
 
 pub fn reset_resource_map() {
-    RESOURCE_NODE_MAP.write().unwrap().clear();
+    DEFAULT_NODE_STORAGE.reset_resource_map()
+}
+
+pub fn entrance_node_list() -> Vec<Arc<EntranceNode>> {
+    DEFAULT_NODE_STORAGE.entrance_node_list()
+}
+
+pub fn get_entrance_node(context_name: &str) -> Option<Arc<EntranceNode>> {
+    DEFAULT_NODE_STORAGE.get_entrance_node(context_name)
+}
+
+pub fn get_or_create_entrance_node(context_name: &str) -> Arc<EntranceNode> {
+    DEFAULT_NODE_STORAGE.get_or_create_entrance_node(context_name)
+}
+
+pub fn reset_entrance_map() {
+    DEFAULT_NODE_STORAGE.reset_entrance_map()
 }
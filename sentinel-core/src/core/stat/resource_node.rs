@@ -2,13 +2,14 @@ use super::{BucketLeapArray, SlidingWindowMetric};
 use crate::{
     base::{
         ConcurrencyStat, MetricEvent, MetricItem, MetricItemRetriever, ReadStat, ResourceType,
-        StatNode, TimePredicate, WriteStat,
+        StatNode, TimePredicate, WriteStat, DEFAULT_MAX_ORIGIN_AMOUNT,
     },
     config, Result,
 };
+use lru::LruCache;
 use std::sync::{
     atomic::{AtomicU32, Ordering},
-    Arc,
+    Arc, RwLock,
 };
 
 #[allow(dead_code)]
@@ -21,6 +22,9 @@ pub struct ResourceNode {
     pub(crate) concurrency: AtomicU32,
     pub(crate) arr: Arc<BucketLeapArray>,
     pub(crate) metric: Arc<SlidingWindowMetric>,
+    /// statistic nodes of this resource for each origin (caller),
+    /// at most `DEFAULT_MAX_ORIGIN_AMOUNT` nodes are kept since the origins are controlled by the callers
+    pub(crate) origin_nodes: RwLock<LruCache<String, Arc<ResourceNode>>>,
 }
 
 impl ResourceNode {
@@ -44,9 +48,38 @@ impl ResourceNode {
             concurrency: AtomicU32::new(0),
             arr,
             metric,
+            origin_nodes: RwLock::new(LruCache::new(DEFAULT_MAX_ORIGIN_AMOUNT)),
         }
     }
 
+    /// get_origin_node returns the statistic node of the given origin (caller) on this resource,
+    /// the lookup does not refresh the recency of the node, which is refreshed by `get_or_create_origin_node()`
+    pub fn get_origin_node(&self, origin: &str) -> Option<Arc<ResourceNode>> {
+        self.origin_nodes.read().unwrap().peek(origin).cloned()
+    }
+
+    /// get_or_create_origin_node returns the statistic node of the given origin (caller) on this resource,
+    /// the node will be created if it does not exist, evicting the least recently used one if the amount is exceeded
+    pub fn get_or_create_origin_node(&self, origin: &str) -> Arc<ResourceNode> {
+        let mut origin_nodes = self.origin_nodes.write().unwrap();
+        if let Some(node) = origin_nodes.get(origin) {
+            return node.clone();
+        }
+        let node = Arc::new(ResourceNode::new(self.res_name.clone(), self.resource_type));
+        origin_nodes.put(origin.into(), node.clone());
+        node
+    }
+
+    /// origin_node_list returns all the existing origin statistic nodes on this resource, with their origins
+    pub fn origin_node_list(&self) -> Vec<(String, Arc<ResourceNode>)> {
+        self.origin_nodes
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(origin, node)| (origin.clone(), node.clone()))
+            .collect()
+    }
+
     pub fn default_metric(&self) -> Arc<dyn ReadStat> {
         self.metric.clone()
     }
@@ -92,6 +125,9 @@ impl ReadStat for ResourceNode {
     fn avg_rt(&self) -> f64 {
         self.metric.avg_rt()
     }
+    fn next_bucket_sum(&self, event: MetricEvent) -> Option<(u64, u64)> {
+        self.metric.next_bucket_sum(event)
+    }
 }
 
 impl WriteStat for ResourceNode {
@@ -125,3 +161,22 @@ impl StatNode for ResourceNode {
         Ok(Arc::new(stat))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn origin_nodes_bounded() {
+        let node = ResourceNode::new("origin_nodes_bounded".into(), ResourceType::Common);
+        let first = node.get_or_create_origin_node("0");
+        assert!(Arc::ptr_eq(&first, &node.get_or_create_origin_node("0")));
+        for i in 1..=DEFAULT_MAX_ORIGIN_AMOUNT {
+            node.get_or_create_origin_node(&i.to_string());
+        }
+        assert_eq!(node.origin_node_list().len(), DEFAULT_MAX_ORIGIN_AMOUNT);
+        // the least recently used origin is evicted
+        assert!(node.get_origin_node("0").is_none());
+        assert!(node.get_origin_node("1").is_some());
+    }
+}
@@ -1,20 +1,24 @@
-use super::get_or_create_resource_node;
-use crate::base::{BaseSlot, EntryContext, StatPrepareSlot};
-use lazy_static::lazy_static;
+use super::{default_node_storage, NodeStorage};
+use crate::base::{BaseSlot, EntryContext, StatPrepareSlot, DEFAULT_CONTEXT_NAME};
 use std::sync::Arc;
 
 const PREPARE_SLOT_ORDER: u32 = 1000;
 
-lazy_static! {
-    pub static ref DEFAULT_RESOURCE_NODE_PREPARE_SLOT: Arc<ResourceNodePrepareSlot> =
-        Arc::new(ResourceNodePrepareSlot {});
+pub struct ResourceNodePrepareSlot {
+    storage: Arc<NodeStorage>,
 }
 
-pub fn default_resource_node_prepare_slot() -> Arc<ResourceNodePrepareSlot> {
-    DEFAULT_RESOURCE_NODE_PREPARE_SLOT.clone()
+impl ResourceNodePrepareSlot {
+    pub fn new(storage: Arc<NodeStorage>) -> Self {
+        ResourceNodePrepareSlot { storage }
+    }
 }
 
-pub struct ResourceNodePrepareSlot {}
+impl Default for ResourceNodePrepareSlot {
+    fn default() -> Self {
+        Self::new(default_node_storage())
+    }
+}
 
 impl BaseSlot for ResourceNodePrepareSlot {
     fn order(&self) -> u32 {
@@ -24,8 +28,21 @@ impl BaseSlot for ResourceNodePrepareSlot {
 
 impl StatPrepareSlot for ResourceNodePrepareSlot {
     fn prepare(&self, ctx: &mut EntryContext) {
-        let node =
-            get_or_create_resource_node(ctx.resource().name(), ctx.resource().resource_type());
+        let node = self
+            .storage
+            .get_or_create_resource_node(ctx.resource().name(), ctx.resource().resource_type());
+        // the origin node is created by the rule slots on demand,
+        // i.e., only if the resource is limited by origin-aware rules
+        // the call tree is only kept for the named invocation contexts
+        if !ctx.context_name().is_empty() && ctx.context_name() != DEFAULT_CONTEXT_NAME {
+            let entrance = self.storage.get_or_create_entrance_node(ctx.context_name());
+            ctx.set_chain_node(
+                entrance.get_or_create_child_node(
+                    ctx.resource().name(),
+                    ctx.resource().resource_type(),
+                ),
+            );
+        }
         ctx.set_stat_node(node);
     }
 }
@@ -1,27 +1,29 @@
-use super::inbound_node;
+use super::{default_node_storage, NodeStorage};
 #[cfg(feature = "exporter")]
 use crate::base::TokenResult;
 use crate::{
     base::{BaseSlot, BlockError, EntryContext, MetricEvent, StatNode, StatSlot, TrafficType},
     utils::curr_time_millis,
 };
-use lazy_static::lazy_static;
 use std::sync::Arc;
 
 const STAT_SLOT_ORDER: u32 = 1000;
 
-lazy_static! {
-    pub static ref DEFAULT_RESOURCE_STAT_SLOT: Arc<ResourceNodeStatSlot> =
-        Arc::new(ResourceNodeStatSlot {});
+pub struct ResourceNodeStatSlot {
+    storage: Arc<NodeStorage>,
 }
 
-pub fn default_resource_stat_slot() -> Arc<ResourceNodeStatSlot> {
-    DEFAULT_RESOURCE_STAT_SLOT.clone()
+impl Default for ResourceNodeStatSlot {
+    fn default() -> Self {
+        Self::new(default_node_storage())
+    }
 }
 
-pub struct ResourceNodeStatSlot {}
-
 impl ResourceNodeStatSlot {
+    pub fn new(storage: Arc<NodeStorage>) -> Self {
+        ResourceNodeStatSlot { storage }
+    }
+
     fn record_pass_for(&self, node: Arc<dyn StatNode>, count: u32) {
         node.increase_concurrency();
         node.add_count(MetricEvent::Pass, count as u64);
@@ -31,6 +33,14 @@ impl ResourceNodeStatSlot {
         node.add_count(MetricEvent::Block, count as u64)
     }
 
+    fn record_shadow_block_for(&self, node: Arc<dyn StatNode>, count: u32) {
+        node.add_count(MetricEvent::ShadowBlock, count as u64)
+    }
+
+    fn record_occupied_pass_for(&self, node: Arc<dyn StatNode>, count: u32) {
+        node.add_count(MetricEvent::OccupiedPass, count as u64)
+    }
+
     fn record_complete_for(&self, node: Arc<dyn StatNode>, count: u32, round_trip: u64) {
         // todo: cannot capture error now
         node.add_count(MetricEvent::Rt, round_trip);
@@ -39,6 +49,29 @@ impl ResourceNodeStatSlot {
     }
 }
 
+impl ResourceNodeStatSlot {
+    /// `on_entry_shadow_blocked` records the invocations passed with rules in shadow mode violated,
+    /// which would have been blocked in enforce mode.
+    fn on_entry_shadow_blocked(&self, ctx: &EntryContext) {
+        let res = ctx.resource();
+        let input = ctx.input();
+        if let Some(stat_node) = ctx.stat_node().clone() {
+            self.record_shadow_block_for(stat_node, input.batch_count());
+            if *res.traffic_type() == TrafficType::Inbound {
+                self.record_shadow_block_for(self.storage.inbound_node(), input.batch_count())
+            }
+        }
+        #[cfg(feature = "exporter")]
+        for block_error in ctx.shadow_blocks() {
+            crate::exporter::add_shadow_blocked_counter(
+                input.batch_count(),
+                res.name(),
+                block_error.block_type(),
+            );
+        }
+    }
+}
+
 impl BaseSlot for ResourceNodeStatSlot {
     fn order(&self) -> u32 {
         STAT_SLOT_ORDER
@@ -52,9 +85,26 @@ impl StatSlot for ResourceNodeStatSlot {
         if let Some(stat_node) = ctx.stat_node().clone() {
             self.record_pass_for(stat_node, input.batch_count());
             if *res.traffic_type() == TrafficType::Inbound {
-                self.record_pass_for(inbound_node(), input.batch_count())
+                self.record_pass_for(self.storage.inbound_node(), input.batch_count())
+            }
+        }
+        if let Some(origin_node) = ctx.origin_node() {
+            self.record_pass_for(origin_node, input.batch_count());
+        }
+        if let Some(chain_node) = ctx.chain_node() {
+            self.record_pass_for(chain_node, input.batch_count());
+        }
+        if ctx.is_occupied() {
+            if let Some(stat_node) = ctx.stat_node().clone() {
+                self.record_occupied_pass_for(stat_node, input.batch_count());
+                if *res.traffic_type() == TrafficType::Inbound {
+                    self.record_occupied_pass_for(self.storage.inbound_node(), input.batch_count())
+                }
             }
         }
+        if ctx.is_shadow_blocked() {
+            self.on_entry_shadow_blocked(ctx);
+        }
         #[cfg(feature = "exporter")]
         crate::exporter::add_handled_counter(
             input.batch_count(),
@@ -71,9 +121,15 @@ impl StatSlot for ResourceNodeStatSlot {
         if let Some(stat_node) = ctx.stat_node().clone() {
             self.record_block_for(stat_node, input.batch_count());
             if *res.traffic_type() == TrafficType::Inbound {
-                self.record_block_for(inbound_node(), input.batch_count())
+                self.record_block_for(self.storage.inbound_node(), input.batch_count())
             }
         }
+        if let Some(origin_node) = ctx.origin_node() {
+            self.record_block_for(origin_node, input.batch_count());
+        }
+        if let Some(chain_node) = ctx.chain_node() {
+            self.record_block_for(chain_node, input.batch_count());
+        }
         #[cfg(feature = "exporter")]
         {
             let tp = block_error.block_type();
@@ -92,8 +148,18 @@ impl StatSlot for ResourceNodeStatSlot {
         if let Some(stat_node) = ctx.stat_node().clone() {
             self.record_complete_for(stat_node, ctx.input().batch_count(), round_trip);
             if *ctx.resource().traffic_type() == TrafficType::Inbound {
-                self.record_complete_for(inbound_node(), ctx.input().batch_count(), round_trip);
+                self.record_complete_for(
+                    self.storage.inbound_node(),
+                    ctx.input().batch_count(),
+                    round_trip,
+                );
             }
         }
+        if let Some(origin_node) = ctx.origin_node() {
+            self.record_complete_for(origin_node, ctx.input().batch_count(), round_trip);
+        }
+        if let Some(chain_node) = ctx.chain_node() {
+            self.record_complete_for(chain_node, ctx.input().batch_count(), round_trip);
+        }
     }
 }
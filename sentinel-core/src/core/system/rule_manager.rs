@@ -6,90 +6,133 @@ use std::sync::{Arc, Mutex, RwLock};
 
 pub type RuleMap = HashMap<MetricType, HashSet<Arc<Rule>>>;
 
-lazy_static! {
-    static ref RULE_MAP: RwLock<RuleMap> = RwLock::new(RuleMap::new());
-    static ref CURRENT_RULES: Mutex<Vec<Arc<Rule>>> = Mutex::new(Vec::new());
+/// `RuleManager` holds the system adaptive rules of a Sentinel instance.
+pub struct RuleManager {
+    rule_map: RwLock<RuleMap>,
+    current_rules: Mutex<Vec<Arc<Rule>>>,
 }
 
-/// `get_rules` returns all the rules in the global `RULE_MAP`
-// This func acquires a read lock on global `RULE_MAP`,
-// please release the lock before calling this func
-pub fn get_rules() -> Vec<Arc<Rule>> {
-    let rule_map = RULE_MAP.read().unwrap();
-    let mut rules: Vec<Arc<Rule>> = Vec::with_capacity(rule_map.len());
-    for r in rule_map.values() {
-        rules.append(&mut r.clone().into_iter().collect());
+impl Default for RuleManager {
+    fn default() -> Self {
+        Self::new()
     }
-    rules
 }
 
-pub fn append_rule(rule: Arc<Rule>) -> bool {
-    if RULE_MAP
-        .read()
-        .unwrap()
-        .get(&rule.metric_type)
-        .unwrap_or(&HashSet::new())
-        .contains(&rule)
-    {
-        return false;
+lazy_static! {
+    static ref DEFAULT_RULE_MANAGER: Arc<RuleManager> = Arc::new(RuleManager::new());
+}
+
+/// `default_rule_manager` returns the rule manager used by the default Sentinel instance
+pub fn default_rule_manager() -> Arc<RuleManager> {
+    DEFAULT_RULE_MANAGER.clone()
+}
+
+impl RuleManager {
+    pub fn new() -> Self {
+        RuleManager {
+            rule_map: RwLock::new(RuleMap::new()),
+            current_rules: Mutex::new(Vec::new()),
+        }
     }
 
-    match rule.is_valid() {
-        Ok(_) => {
-            RULE_MAP
-                .write()
-                .unwrap()
-                .entry(rule.metric_type)
-                .or_default()
-                .insert(Arc::clone(&rule));
-            CURRENT_RULES.lock().unwrap().push(rule);
+    /// `get_rules` returns all the rules in the `rule_map`
+    // This func acquires a read lock on `rule_map`,
+    // please release the lock before calling this func
+    pub fn get_rules(&self) -> Vec<Arc<Rule>> {
+        let rule_map = self.rule_map.read().unwrap();
+        let mut rules: Vec<Arc<Rule>> = Vec::with_capacity(rule_map.len());
+        for r in rule_map.values() {
+            rules.append(&mut r.clone().into_iter().collect());
         }
-        Err(err) => logging::warn!(
-            "[System append_rule] Ignoring invalid rule {:?}, reason: {:?}",
-            rule,
-            err
-        ),
-    };
-    true
-}
+        rules
+    }
 
-/// `load_rules` loads given system rules to the rule manager, while all previous rules will be replaced.
-// This func acquires the lock on global `CURRENT_RULES`,
-// please release the lock before calling this func
-pub fn load_rules(rules: Vec<Arc<Rule>>) {
-    let mut current_rules = CURRENT_RULES.lock().unwrap();
-    if *current_rules == rules {
+    pub fn append_rule(&self, rule: Arc<Rule>) -> bool {
+        if self
+            .rule_map
+            .read()
+            .unwrap()
+            .get(&rule.metric_type)
+            .unwrap_or(&HashSet::new())
+            .contains(&rule)
+        {
+            return false;
+        }
+
+        match rule.is_valid() {
+            Ok(_) => {
+                self.rule_map
+                    .write()
+                    .unwrap()
+                    .entry(rule.metric_type)
+                    .or_default()
+                    .insert(Arc::clone(&rule));
+                self.current_rules.lock().unwrap().push(rule);
+            }
+            Err(err) => logging::warn!(
+                "[System append_rule] Ignoring invalid rule {:?}, reason: {:?}",
+                rule,
+                err
+            ),
+        };
+        true
+    }
+
+    /// `load_rules` loads given system rules to the rule manager, while all previous rules will be replaced.
+    // This func acquires the lock on `current_rules`,
+    // please release the lock before calling this func
+    pub fn load_rules(&self, rules: Vec<Arc<Rule>>) {
+        let mut current_rules = self.current_rules.lock().unwrap();
+        if *current_rules == rules {
+            logging::info!(
+                "[System] Load rules is the same with current rules, so ignore load operation."
+            );
+            return;
+        }
+
+        // when rule_map is different with global one, update the global one
+        // ignore invalid rules
+        let m = build_rule_map(rules.clone());
+
+        let start = utils::curr_time_nanos();
+        let mut rule_map = self.rule_map.write().unwrap();
+        *rule_map = m;
+
+        logging::debug!(
+            "[System load_rules] Time statistic(ns) for updating system rule, timeCost {:?}",
+            utils::curr_time_nanos() - start
+        );
         logging::info!(
-            "[System] Load rules is the same with current rules, so ignore load operation."
+            "[SystemRuleManager] System rules loaded, rules {:?}",
+            rule_map
         );
-        return;
+        *current_rules = rules;
     }
 
-    // when rule_map is different with global one, update the global one
-    // ignore invalid rules
-    let m = build_rule_map(rules.clone());
-
-    let start = utils::curr_time_nanos();
-    let mut rule_map = RULE_MAP.write().unwrap();
-    *rule_map = m;
-
-    logging::debug!(
-        "[System load_rules] Time statistic(ns) for updating system rule, timeCost {:?}",
-        utils::curr_time_nanos() - start
-    );
-    logging::info!(
-        "[SystemRuleManager] System rules loaded, rules {:?}",
-        rule_map
-    );
-    *current_rules = rules;
+    /// `clear_rules` clear all the previous rules
+    // This func acquires the locks on `current_rules` and `rule_map`,
+    // please release the locks before calling this func
+    pub fn clear_rules(&self) {
+        self.current_rules.lock().unwrap().clear();
+        self.rule_map.write().unwrap().clear();
+    }
+}
+
+// The following functions operate on the default rule manager.
+pub fn get_rules() -> Vec<Arc<Rule>> {
+    DEFAULT_RULE_MANAGER.get_rules()
+}
+
+pub fn append_rule(rule: Arc<Rule>) -> bool {
+    DEFAULT_RULE_MANAGER.append_rule(rule)
+}
+
+pub fn load_rules(rules: Vec<Arc<Rule>>) {
+    DEFAULT_RULE_MANAGER.load_rules(rules)
 }
 
-/// `clear_rules` clear all the previous rules
-// This func acquires the locks on global `CURRENT_RULES` and `RULE_MAP`,
-// please release the locks before calling this func
 pub fn clear_rules() {
-    CURRENT_RULES.lock().unwrap().clear();
-    RULE_MAP.write().unwrap().clear();
+    DEFAULT_RULE_MANAGER.clear_rules()
 }
 
 fn build_rule_map(rules: Vec<Arc<Rule>>) -> RuleMap {
@@ -142,7 +185,7 @@ mod test {
                 ..Default::default()
             }));
 
-        let mut rule_map = RULE_MAP.write().unwrap();
+        let mut rule_map = DEFAULT_RULE_MANAGER.rule_map.write().unwrap();
         *rule_map = map.clone();
         drop(rule_map);
         let rules = get_rules();
@@ -154,7 +197,7 @@ mod test {
             ..Default::default()
         });
         map.get_mut(&MetricType::InboundQPS).unwrap().insert(rule);
-        let mut rule_map = RULE_MAP.write().unwrap();
+        let mut rule_map = DEFAULT_RULE_MANAGER.rule_map.write().unwrap();
         *rule_map = map;
         drop(rule_map);
         let rules = get_rules();
@@ -179,10 +222,10 @@ mod test {
             }),
         ];
         load_rules(rules);
-        assert_eq!(2, RULE_MAP.read().unwrap().len());
+        assert_eq!(2, DEFAULT_RULE_MANAGER.rule_map.read().unwrap().len());
         clear_rules();
-        assert_eq!(0, RULE_MAP.read().unwrap().len());
-        assert_eq!(0, CURRENT_RULES.lock().unwrap().len());
+        assert_eq!(0, DEFAULT_RULE_MANAGER.rule_map.read().unwrap().len());
+        assert_eq!(0, DEFAULT_RULE_MANAGER.current_rules.lock().unwrap().len());
     }
 
     #[test]
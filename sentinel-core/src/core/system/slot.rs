@@ -4,7 +4,8 @@ use crate::{
         BaseSlot, BlockType, ConcurrencyStat, EntryContext, MetricEvent, ReadStat, RuleCheckSlot,
         Snapshot, TokenResult, TrafficType,
     },
-    stat, system_metric,
+    stat::{self, NodeStorage},
+    system_metric,
 };
 use lazy_static::lazy_static;
 use std::sync::Arc;
@@ -12,10 +13,28 @@ use std::sync::Arc;
 const RULE_CHECK_SLOT_ORDER: u32 = 1000;
 
 /// A RuleSlot for flow related metrics
-pub struct AdaptiveSlot {}
+pub struct AdaptiveSlot {
+    rule_manager: Arc<RuleManager>,
+    storage: Arc<NodeStorage>,
+}
+
+impl AdaptiveSlot {
+    pub fn new(rule_manager: Arc<RuleManager>, storage: Arc<NodeStorage>) -> Self {
+        AdaptiveSlot {
+            rule_manager,
+            storage,
+        }
+    }
+}
+
+impl Default for AdaptiveSlot {
+    fn default() -> Self {
+        Self::new(default_rule_manager(), stat::default_node_storage())
+    }
+}
 
 lazy_static! {
-    pub static ref DEFAULT_ADAPTIVE_SLOT: Arc<AdaptiveSlot> = Arc::new(AdaptiveSlot {});
+    pub static ref DEFAULT_ADAPTIVE_SLOT: Arc<AdaptiveSlot> = Arc::new(AdaptiveSlot::default());
 }
 
 pub fn default_slot() -> Arc<AdaptiveSlot> {
@@ -35,9 +54,9 @@ impl RuleCheckSlot for AdaptiveSlot {
         if *traffic_type == TrafficType::Outbound {
             return ctx.result().clone();
         }
-        let rules = get_rules();
+        let rules = self.rule_manager.get_rules();
         for rule in rules {
-            let (passed, msg, snapshot) = can_pass_check(&rule);
+            let (passed, msg, snapshot) = can_pass_check(&self.storage, &rule);
             if passed {
                 continue;
             }
@@ -54,14 +73,17 @@ impl RuleCheckSlot for AdaptiveSlot {
     }
 }
 
-fn can_pass_check(rule: &Arc<Rule>) -> (bool, String, Option<Arc<Snapshot>>) {
+fn can_pass_check(
+    storage: &NodeStorage,
+    rule: &Arc<Rule>,
+) -> (bool, String, Option<Arc<Snapshot>>) {
     let threshold = rule.threshold;
     let mut res = true;
     let mut msg = String::new();
     let mut snapshot = None;
     match rule.metric_type {
         MetricType::InboundQPS => {
-            let qps = stat::inbound_node().qps(MetricEvent::Pass);
+            let qps = storage.inbound_node().qps(MetricEvent::Pass);
             res = qps < threshold;
             if !res {
                 msg = "system qps check blocked".into();
@@ -69,7 +91,7 @@ fn can_pass_check(rule: &Arc<Rule>) -> (bool, String, Option<Arc<Snapshot>>) {
             }
         }
         MetricType::Concurrency => {
-            let n = stat::inbound_node().current_concurrency() as f64;
+            let n = storage.inbound_node().current_concurrency() as f64;
             res = n < threshold;
             if !res {
                 msg = "system concurrency check blocked".into();
@@ -77,7 +99,7 @@ fn can_pass_check(rule: &Arc<Rule>) -> (bool, String, Option<Arc<Snapshot>>) {
             }
         }
         MetricType::AvgRT => {
-            let rt = stat::inbound_node().avg_rt();
+            let rt = storage.inbound_node().avg_rt();
             res = rt < threshold;
             if !res {
                 msg = "system avg rt check blocked".into();
@@ -86,7 +108,9 @@ fn can_pass_check(rule: &Arc<Rule>) -> (bool, String, Option<Arc<Snapshot>>) {
         }
         MetricType::Load => {
             let l = system_metric::current_load();
-            if l > threshold && (rule.strategy != AdaptiveStrategy::BBR || !check_bbr_simple()) {
+            if l > threshold
+                && (rule.strategy != AdaptiveStrategy::BBR || !check_bbr_simple(storage))
+            {
                 res = false;
                 msg = "system load check blocked".into();
             }
@@ -94,7 +118,9 @@ fn can_pass_check(rule: &Arc<Rule>) -> (bool, String, Option<Arc<Snapshot>>) {
         }
         MetricType::CpuUsage => {
             let c = system_metric::current_cpu_usage() as f64;
-            if c > threshold && (rule.strategy != AdaptiveStrategy::BBR || !check_bbr_simple()) {
+            if c > threshold
+                && (rule.strategy != AdaptiveStrategy::BBR || !check_bbr_simple(storage))
+            {
                 res = false;
                 msg = "system cpu usage check blocked".into();
             }
@@ -104,8 +130,8 @@ fn can_pass_check(rule: &Arc<Rule>) -> (bool, String, Option<Arc<Snapshot>>) {
     (res, msg, snapshot)
 }
 
-fn check_bbr_simple() -> bool {
-    let global_inbound = &stat::inbound_node();
+fn check_bbr_simple(storage: &NodeStorage) -> bool {
+    let global_inbound = &storage.inbound_node();
     let concurrency = global_inbound.current_concurrency() as f64;
     let min_rt = global_inbound.min_rt();
     let max_complete = global_inbound.max_avg(MetricEvent::Complete);
@@ -119,7 +145,7 @@ mod test {
 
     #[test]
     fn unsuitable_traffic_type() {
-        let slot = AdaptiveSlot {};
+        let slot = AdaptiveSlot::default();
         let res_name = String::from("test");
         let res_node = stat::get_or_create_resource_node(&res_name, &ResourceType::Common);
         let rw = ResourceWrapper::new(res_name, ResourceType::Common, TrafficType::Outbound);
@@ -133,7 +159,7 @@ mod test {
 
     #[test]
     fn empty_rule() {
-        let slot = AdaptiveSlot {};
+        let slot = AdaptiveSlot::default();
         let res_name = String::from("test");
         let res_node = stat::get_or_create_resource_node(&res_name, &ResourceType::Common);
         let rw = ResourceWrapper::new(res_name, ResourceType::Common, TrafficType::Outbound);
@@ -153,7 +179,7 @@ mod test {
             threshold: 0.5,
             ..Default::default()
         });
-        let (r, _, v) = can_pass_check(&rule);
+        let (r, _, v) = can_pass_check(&stat::default_node_storage(), &rule);
         assert!(r);
         assert!(v.is_none());
     }
@@ -167,7 +193,7 @@ mod test {
             ..Default::default()
         });
         stat::inbound_node().increase_concurrency();
-        let (r, _, v) = can_pass_check(&rule);
+        let (r, _, v) = can_pass_check(&stat::default_node_storage(), &rule);
         stat::inbound_node().decrease_concurrency();
         assert!(!r);
         assert!(
@@ -184,7 +210,7 @@ mod test {
             ..Default::default()
         });
         system_metric::set_system_load(0.2);
-        let (r, _, v) = can_pass_check(&rule);
+        let (r, _, v) = can_pass_check(&stat::default_node_storage(), &rule);
         assert!(r);
         assert!(
             (0.2 - *Arc::downcast::<f64>(v.unwrap().as_any_arc()).unwrap()).abs() < f64::EPSILON
@@ -203,7 +229,7 @@ mod test {
         });
         system_metric::set_system_load(1.0);
         stat::inbound_node().increase_concurrency();
-        let (r, _, v) = can_pass_check(&rule);
+        let (r, _, v) = can_pass_check(&stat::default_node_storage(), &rule);
         stat::inbound_node().decrease_concurrency();
         assert!(r);
         assert!(
@@ -221,7 +247,7 @@ mod test {
             ..Default::default()
         });
         system_metric::set_cpu_usage(0.0);
-        let (r, _, _) = can_pass_check(&rule);
+        let (r, _, _) = can_pass_check(&stat::default_node_storage(), &rule);
         assert!(r)
     }
 
@@ -235,7 +261,7 @@ mod test {
             ..Default::default()
         });
         system_metric::set_cpu_usage(0.8);
-        let (r, _, v) = can_pass_check(&rule);
+        let (r, _, v) = can_pass_check(&stat::default_node_storage(), &rule);
         assert!(r);
         const DELTA: f64 = 0.0001;
         let snapshot = *Arc::downcast::<f64>(v.unwrap().as_any_arc()).unwrap();
@@ -1,5 +1,5 @@
 use super::*;
-use crate::core::{circuitbreaker, flow, hotspot, isolation, system};
+use crate::core::{authority, circuitbreaker, flow, hotspot, isolation, system};
 
 /// flow_rule_updater load the flow::Rule vector to downstream flow component.
 fn flow_rule_updater(rules: Vec<Arc<flow::Rule>>) -> Result<bool> {
@@ -35,6 +35,19 @@ pub fn new_circuitbreaker_rule_handler(
     DefaultPropertyHandler::new(converter, circuitbreaker_rule_updater)
 }
 
+/// circuitbreaker_override_rule_updater load the circuitbreaker::OverrideRule vector to downstream flow component.
+fn circuitbreaker_override_rule_updater(
+    rules: Vec<Arc<circuitbreaker::OverrideRule>>,
+) -> Result<bool> {
+    Ok(circuitbreaker::load_override_rules(rules))
+}
+
+pub fn new_circuitbreaker_override_rule_handler(
+    converter: PropertyConverter<circuitbreaker::OverrideRule>,
+) -> Arc<impl PropertyHandler<circuitbreaker::OverrideRule>> {
+    DefaultPropertyHandler::new(converter, circuitbreaker_override_rule_updater)
+}
+
 /// isolation_rule_updater load the isolation::Rule vector to downstream flow component.
 fn isolation_rule_updater(rules: Vec<Arc<isolation::Rule>>) -> Result<bool> {
     isolation::load_rules(rules);
@@ -57,3 +70,15 @@ pub fn new_hotspot_rule_handler(
 ) -> Arc<impl PropertyHandler<hotspot::Rule>> {
     DefaultPropertyHandler::new(converter, hotspot_rule_updater)
 }
+
+/// authority_rule_updater load the authority::Rule vector to downstream authority component.
+fn authority_rule_updater(rules: Vec<Arc<authority::Rule>>) -> Result<bool> {
+    authority::load_rules(rules);
+    Ok(true)
+}
+
+pub fn new_authority_rule_handler(
+    converter: PropertyConverter<authority::Rule>,
+) -> Arc<impl PropertyHandler<authority::Rule>> {
+    DefaultPropertyHandler::new(converter, authority_rule_updater)
+}
@@ -1,14 +1,21 @@
 use crate::{
     base::{BlockType, TokenResult},
     config,
+    flow::Controller,
 };
 ///! exporter the process protected by Sentinel
 use lazy_static::lazy_static;
 use prometheus_exporter::{
-    prometheus::{default_registry, opts, CounterVec, GaugeVec, Registry},
+    prometheus::{
+        core::{Collector, Desc},
+        default_registry, opts,
+        proto::MetricFamily,
+        CounterVec, GaugeVec, Registry,
+    },
     Builder,
 };
-use std::sync::Once;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Once, Weak};
 use sysinfo::{System, SystemExt};
 
 lazy_static! {
@@ -41,6 +48,25 @@ lazy_static! {
         &["host", "process", "pid", "resource"]
     )
     .unwrap();
+    static ref FLOW_QUEUE_LENGTH_GAUGE: GaugeVec = GaugeVec::new(
+        opts!(
+            "sentinel_FLOW_QUEUE_LENGTH_GAUGE",
+            "number of requests queueing in the throttling flow controllers"
+        ),
+        &["host", "process", "pid", "resource"]
+    )
+    .unwrap();
+    // the flow controllers of which the queue length is computed at scrape time
+    static ref FLOW_CONTROLLERS: Mutex<Vec<Weak<Controller>>> = Mutex::new(Vec::new());
+    // crate::core::isolation
+    static ref CONCURRENCY_LIMIT_GAUGE: GaugeVec = GaugeVec::new(
+        opts!(
+            "sentinel_CONCURRENCY_LIMIT_GAUGE",
+            "resource concurrency limit adjusted by adaptive isolation rules, the minimum one of the rules of the resource"
+        ),
+        &["host", "process", "pid", "resource"]
+    )
+    .unwrap();
     // crate::core::circuitbreaker
     static ref STATE_CHANGE_COUNTER: CounterVec = CounterVec::new(
         opts!(
@@ -59,11 +85,19 @@ lazy_static! {
         &["host", "process", "pid", "resource","result","block_type"]
     )
     .unwrap();
+    static ref SHADOW_BLOCKED_COUNTER: CounterVec = CounterVec::new(
+        opts!(
+            "shadow_blocked_total",
+            "Total count of the requests that would have been blocked by rules in shadow mode"
+        ),
+        &["host", "process", "pid", "resource","block_type"]
+    )
+    .unwrap();
     static ref GAUGE_METRICS: Vec<GaugeVec> = {
-        vec![CPU_RATIO_GAUGE.clone(), MEMORY_SIZE_GAUGE.clone(), FLOW_THRESHOLD_GAUGE.clone()]
+        vec![CPU_RATIO_GAUGE.clone(), MEMORY_SIZE_GAUGE.clone(), FLOW_THRESHOLD_GAUGE.clone(), CONCURRENCY_LIMIT_GAUGE.clone()]
     };
     static ref COUNTER_METRICS: Vec<CounterVec> = {
-        vec![STATE_CHANGE_COUNTER.clone(), HANDLED_COUNTER.clone()]
+        vec![STATE_CHANGE_COUNTER.clone(), HANDLED_COUNTER.clone(), SHADOW_BLOCKED_COUNTER.clone()]
     };
     static ref INIT_ONCE: Once = Once::new();
 }
@@ -86,6 +120,55 @@ pub fn set_flow_threshold(resourse: &str, threshold: f64) {
         .set(threshold);
 }
 
+/// `watch_flow_queue_length` registers the flow controller, of which the queue length is exported
+pub fn watch_flow_queue_length(controller: &Arc<Controller>) {
+    let mut controllers = FLOW_CONTROLLERS.lock().unwrap();
+    // prune the dropped controllers before the list grows, in case the metrics are never scraped
+    if controllers.len() == controllers.capacity() {
+        controllers.retain(|controller| controller.strong_count() > 0);
+    }
+    controllers.push(Arc::downgrade(controller));
+}
+
+/// `FlowQueueLengthCollector` computes the queue length of the flow controllers at scrape time,
+/// since the queueing requests leave the queue without any further check on the controllers.
+struct FlowQueueLengthCollector;
+
+impl Collector for FlowQueueLengthCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        FLOW_QUEUE_LENGTH_GAUGE.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let mut lengths: HashMap<String, usize> = HashMap::new();
+        // the controllers of the removed rules are dropped
+        FLOW_CONTROLLERS
+            .lock()
+            .unwrap()
+            .retain(|controller| match controller.upgrade() {
+                Some(controller) => {
+                    *lengths.entry(controller.rule().resource.clone()).or_default() +=
+                        controller.queue_length();
+                    true
+                }
+                None => false,
+            });
+        FLOW_QUEUE_LENGTH_GAUGE.reset();
+        for (resource, length) in lengths {
+            FLOW_QUEUE_LENGTH_GAUGE
+                .with_label_values(&[&HOST_NAME, &PROCESS_NAME, &PID_STRING, &resource])
+                .set(length as f64);
+        }
+        FLOW_QUEUE_LENGTH_GAUGE.collect()
+    }
+}
+
+pub fn set_concurrency_limit(resourse: &str, limit: u32) {
+    CONCURRENCY_LIMIT_GAUGE
+        .with_label_values(&[&HOST_NAME, &PROCESS_NAME, &PID_STRING, resourse])
+        .set(limit as f64);
+}
+
 pub fn add_state_change_counter(resourse: &str, from: &str, to: &str) {
     STATE_CHANGE_COUNTER
         .with_label_values(&[&HOST_NAME, &PROCESS_NAME, &PID_STRING, resourse, from, to])
@@ -110,6 +193,18 @@ pub fn add_handled_counter(
         .inc_by(batch_count as f64);
 }
 
+pub fn add_shadow_blocked_counter(batch_count: u32, resource: &str, block_type: BlockType) {
+    SHADOW_BLOCKED_COUNTER
+        .with_label_values(&[
+            &HOST_NAME,
+            &PROCESS_NAME,
+            &PID_STRING,
+            resource,
+            &block_type.to_string(),
+        ])
+        .inc_by(batch_count as f64);
+}
+
 fn register_sentinel_metrics(registry: Option<Box<Registry>>) {
     let r = match registry {
         Some(ref r) => r,
@@ -118,6 +213,7 @@ fn register_sentinel_metrics(registry: Option<Box<Registry>>) {
     for item in &*GAUGE_METRICS {
         r.register(Box::new(item.clone())).unwrap();
     }
+    r.register(Box::new(FlowQueueLengthCollector)).unwrap();
     for item in &*COUNTER_METRICS {
         r.register(Box::new(item.clone())).unwrap();
     }
@@ -127,6 +223,7 @@ pub fn reset_sentinel_metrics() {
     for item in &*GAUGE_METRICS {
         item.reset();
     }
+    FLOW_QUEUE_LENGTH_GAUGE.reset();
     for item in &*COUNTER_METRICS {
         item.reset();
     }
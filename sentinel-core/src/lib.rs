@@ -78,6 +78,30 @@
 //! }
 //! ```
 //!
+//! The error is a `base::EntryError`. If the calling is blocked, it holds a `base::BlockError`,
+//! which tells the block type, the triggered rule and the snapshot value.
+//!
+//! ```rust
+//! if let Err(err) = entry_builder.build() {
+//!     if let base::EntryError::Blocked(block_err) = &err {
+//!         match block_err.block_type() {
+//!             base::BlockType::Flow => { /* e.g., 429 Too Many Requests */ }
+//!             base::BlockType::CircuitBreaking => { /* e.g., 503 Service Unavailable */ }
+//!             _ => {}
+//!         }
+//!         logging::warn!("blocked by rule {:?}", block_err.triggered_rule());
+//!     }
+//! }
+//! ```
+//!
+//! To avoid leaking entries on early returns or panics, wrap the entry by `base::EntryGuard`, which exits it on dropping.
+//! Or simply use `run()`/`run_async()`, errors returned by the closure/future are traced automatically,
+//! keeping their type names for the error classifiers of circuit breakers (see `api::TracedError`).
+//!
+//! ```rust
+//! let res = sentinel_core::run("example", || -> Result<(), std::io::Error> { Ok(()) });
+//! ```
+//!
 //! ## Load Sentinel Rules
 //!
 //! ### Manually Create Sentinel Entry and Load Rules
@@ -98,6 +122,20 @@
 //! })]);
 //! ```
 //!
+//! The free functions above operate on the default Sentinel instance.
+//! If independently configured rules are needed in a single process, e.g., for multi-tenant gateways,
+//! create a `Sentinel` instance, which owns its rule managers, statistic nodes and slot chain:
+//!
+//! ```rust
+//! let tenant = Sentinel::new();
+//! tenant.flow_rule_manager().load_rules(vec![Arc::new(flow::Rule {
+//!     resource: "example".into(),
+//!     threshold: 10.0,
+//!     ..Default::default()
+//! })]);
+//! let entry = tenant.entry("example".into()).build();
+//! ```
+//!
 //! ### Via Attribute-Like Macros
 //! We also provide macros to help you define Sentinel resources and load rules easily:
 //!
@@ -146,7 +184,7 @@ pub mod api;
 /// such as the slding window and its underlying LeapArray, the rule managers,
 ///  and other utilities on configuration and metric logs.
 /// The rule managers are responsible for managing the flow controller, circuit breaker,
-/// isolation and system status related rules.
+/// isolation, authority and system status related rules.
 pub mod core;
 /// Adapters for different logging crates.
 pub mod logging;
@@ -23,6 +23,13 @@ pub fn sleep_for_ns(ns: u64) {
     std::thread::sleep(std::time::Duration::from_nanos(ns));
 }
 
+/// Asynchronously sleep without blocking the current thread.
+/// The timer is runtime-agnostic, so it works with any async executor.
+#[inline]
+pub async fn sleep_for_ns_async(ns: u64) {
+    futures_timer::Delay::new(std::time::Duration::from_nanos(ns)).await;
+}
+
 #[inline]
 fn cal_curr_time_millis() -> u64 {
     (OffsetDateTime::now_utc().unix_timestamp_nanos() / (*UNIX_TIME_UNIT_OFFSET)) as u64
@@ -1,3 +1,4 @@
+use crate::utils::parse_mode;
 use darling::FromMeta;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
@@ -11,6 +12,8 @@ pub(crate) struct Params {
     pub args: Option<String>,
     // rule
     #[darling(default)]
+    pub mode: Option<String>,
+    #[darling(default)]
     pub threshold: Option<f64>,
     #[darling(default)]
     pub strategy: Option<String>,
@@ -24,10 +27,15 @@ pub(crate) struct Params {
     pub stat_sliding_window_bucket_count: Option<u32>,
     #[darling(default)]
     pub max_allowed_rt_ms: Option<u64>,
+    #[darling(default)]
+    pub probe_num: Option<u32>,
+    #[darling(default)]
+    pub probe_success_ratio: Option<f64>,
 }
 
 pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
     let strategy = parse_strategy(&rule.strategy);
+    let mode = parse_mode(&rule.mode);
     let optional_params = expand_optional_params!(
         rule,
         threshold,
@@ -35,13 +43,16 @@ pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
         min_request_amount,
         stat_interval_ms,
         stat_sliding_window_bucket_count,
-        max_allowed_rt_ms
+        max_allowed_rt_ms,
+        probe_num,
+        probe_success_ratio
     );
     quote! {
         circuitbreaker::Rule {
             id: String::from(#resource_name),
             resource: String::from(#resource_name),
             #strategy
+            #mode
             #optional_params
             ..Default::default()
         }
@@ -59,6 +70,12 @@ fn parse_strategy(input: &Option<String>) -> TokenStream2 {
             "ErrorCount" => {
                 quote! {strategy: circuitbreaker::BreakerStrategy::ErrorCount,}
             }
+            "ConsecutiveErrors" => {
+                quote! {strategy: circuitbreaker::BreakerStrategy::ConsecutiveErrors,}
+            }
+            "LatencyPercentile" => {
+                quote! {strategy: circuitbreaker::BreakerStrategy::LatencyPercentile,}
+            }
             _ => quote! {},
         })
     }
@@ -1,3 +1,4 @@
+use crate::utils::parse_mode;
 use darling::FromMeta;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
@@ -13,6 +14,8 @@ pub(crate) struct Params {
     pub args: Option<String>,
     // rule
     #[darling(default)]
+    pub mode: Option<String>,
+    #[darling(default)]
     pub threshold: Option<f64>,
     #[darling(default)]
     pub calculate_strategy: Option<String>,
@@ -27,6 +30,8 @@ pub(crate) struct Params {
     #[darling(default)]
     pub max_queueing_time_ms: Option<u32>,
     #[darling(default)]
+    pub max_queue_size: Option<u32>,
+    #[darling(default)]
     pub stat_interval_ms: Option<u32>,
     #[darling(default)]
     pub low_mem_usage_threshold: Option<u64>,
@@ -36,6 +41,18 @@ pub(crate) struct Params {
     pub mem_low_water_mark: Option<u64>,
     #[darling(default)]
     pub mem_high_water_mark: Option<u64>,
+    #[darling(default)]
+    pub low_cpu_usage_threshold: Option<u64>,
+    #[darling(default)]
+    pub high_cpu_usage_threshold: Option<u64>,
+    #[darling(default)]
+    pub cpu_low_water_mark: Option<f64>,
+    #[darling(default)]
+    pub cpu_high_water_mark: Option<f64>,
+    #[darling(default)]
+    pub burst: Option<u32>,
+    #[darling(default)]
+    pub refill_rate: Option<f64>,
 }
 
 pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
@@ -44,17 +61,25 @@ pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
         &rule.control_strategy,
         &rule.relation_strategy,
     );
+    let mode = parse_mode(&rule.mode);
     let optional_params = expand_optional_params!(
         rule,
         threshold,
         warm_up_period_sec,
         warm_up_cold_factor,
         max_queueing_time_ms,
+        max_queue_size,
         stat_interval_ms,
         low_mem_usage_threshold,
         high_mem_usage_threshold,
         mem_low_water_mark,
-        mem_high_water_mark
+        mem_high_water_mark,
+        low_cpu_usage_threshold,
+        high_cpu_usage_threshold,
+        cpu_low_water_mark,
+        cpu_high_water_mark,
+        burst,
+        refill_rate
     );
     quote! {
         flow::Rule {
@@ -62,6 +87,7 @@ pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
             resource: String::from(#resource_name),
             ref_resource: String::from(#resource_name),
             #strategy
+            #mode
             #optional_params
             ..Default::default()
         }
@@ -81,6 +107,8 @@ fn parse_strategy(
             "MemoryAdaptive" => {
                 quote! {calculate_strategy: flow::CalculateStrategy::MemoryAdaptive,}
             }
+            "CpuAdaptive" => quote! {calculate_strategy: flow::CalculateStrategy::CpuAdaptive,},
+            "TokenBucket" => quote! {calculate_strategy: flow::CalculateStrategy::TokenBucket,},
             _ => quote! {},
         })
     }
@@ -95,6 +123,7 @@ fn parse_strategy(
         strategy.extend(match &val[..] {
             "Current" => quote! {relation_strategy: flow::RelationStrategy::Current,},
             "Associated" => quote! {relation_strategy: flow::RelationStrategy::Associated,},
+            "Chain" => quote! {relation_strategy: flow::RelationStrategy::Chain,},
             _ => quote! {},
         })
     }
@@ -1,3 +1,4 @@
+use crate::utils::parse_mode;
 use darling::FromMeta;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
@@ -11,6 +12,8 @@ pub(crate) struct Params {
     pub args: Option<String>,
     // rule
     #[darling(default)]
+    pub mode: Option<String>,
+    #[darling(default)]
     pub threshold: Option<u64>,
     #[darling(default)]
     pub metric_type: Option<String>,
@@ -31,6 +34,7 @@ pub(crate) struct Params {
 pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
     let control_strategy = parse_strategy(&rule.control_strategy);
     let metric_type = parse_metric(&rule.metric_type);
+    let mode = parse_mode(&rule.mode);
     let optional_params = expand_optional_params!(
         rule,
         threshold,
@@ -46,6 +50,7 @@ pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
             resource: String::from(#resource_name),
             #control_strategy
             #metric_type
+            #mode
             #optional_params
             ..Default::default()
         }
@@ -14,16 +14,24 @@ pub(crate) struct Params {
     pub threshold: Option<u32>,
     #[darling(default)]
     pub metric_type: Option<String>,
+    #[darling(default)]
+    pub strategy: Option<String>,
+    #[darling(default)]
+    pub min_threshold: Option<u32>,
+    #[darling(default)]
+    pub max_threshold: Option<u32>,
 }
 
 pub(crate) fn process_rule(resource_name: &str, rule: &Params) -> TokenStream2 {
     let metric_type = parse_metric(&rule.metric_type);
-    let optional_params = expand_optional_params!(rule, threshold);
+    let strategy = parse_strategy(&rule.strategy);
+    let optional_params = expand_optional_params!(rule, threshold, min_threshold, max_threshold);
     quote! {
         isolation::Rule {
             id: String::from(#resource_name), // incase of duplication
             resource: String::from(#resource_name),
             #metric_type
+            #strategy
             #optional_params
             ..Default::default()
         }
@@ -40,3 +48,17 @@ fn parse_metric(input: &Option<String>) -> TokenStream2 {
     }
     metric
 }
+
+fn parse_strategy(input: &Option<String>) -> TokenStream2 {
+    let mut strategy = TokenStream2::new();
+    if let Some(val) = input {
+        strategy.extend(match &val[..] {
+            "Fixed" => quote! {strategy: isolation::LimitStrategy::Fixed,},
+            "AIMD" => quote! {strategy: isolation::LimitStrategy::AIMD,},
+            "Vegas" => quote! {strategy: isolation::LimitStrategy::Vegas,},
+            "Gradient2" => quote! {strategy: isolation::LimitStrategy::Gradient2,},
+            _ => quote! {},
+        })
+    }
+    strategy
+}
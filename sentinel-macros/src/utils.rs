@@ -41,6 +41,15 @@ pub(crate) fn parse_args(input: &Option<String>) -> proc_macro2::TokenStream {
     }
 }
 
+#[inline]
+pub(crate) fn parse_mode(input: &Option<String>) -> proc_macro2::TokenStream {
+    match input.as_deref() {
+        Some("Enforce") => quote::quote! {mode: base::RuleMode::Enforce,},
+        Some("Shadow") => quote::quote! {mode: base::RuleMode::Shadow,},
+        _ => quote::quote! {},
+    }
+}
+
 /// build the sentinel entry
 macro_rules! wrap_sentinel {
     // fn $name::wrap_sentinel(rule: $name::Rule, func: ItemFn) -> TokenStream
@@ -57,6 +66,12 @@ macro_rules! wrap_sentinel {
         let resource_name = sig.ident.to_string();
         let traffic_type = parse_traffic(&$params.traffic_type);
         let args = parse_args(&$params.args);
+        // async functions should not block the thread when waiting
+        let build = if sig.asyncness.is_some() {
+            quote::quote! {build_async().await}
+        } else {
+            quote::quote! {build()}
+        };
 
         // parse rule params
         let rule = $name::process_rule(&resource_name, &$params);
@@ -73,7 +88,7 @@ macro_rules! wrap_sentinel {
                 let entry_builder = EntryBuilder::new(String::from(#resource_name))
                     .with_traffic_type(#traffic_type)
                     .with_args(#args);
-                match entry_builder.build() {
+                match entry_builder.#build {
                     Ok(entry) => {
                         // Passed, wrap the logic here.
                         let result = {#(#stmts)*};
@@ -82,7 +97,7 @@ macro_rules! wrap_sentinel {
                         Ok(result)
                     },
                     Err(err) => {
-                        Err(err)
+                        Err(err.into())
                     }
                 }
             }